@@ -3,22 +3,88 @@ use cl::{
         balance::{UnitWitness, NOP_COVENANT},
         tx::LedgerUpdate,
         InputWitness, Nonce, Nullifier, NullifierCommitment, NullifierSecret, OutputWitness, Tx,
-        Unit,
+        TxWitness, Unit,
     },
     mantle::ZoneId,
 };
 use rand::RngCore;
 use risc0_zkvm::sha::rust_crypto::{Digest, Sha256};
-use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 const FUNDS_SK: NullifierSecret = NullifierSecret([0; 16]);
 pub const ZONE_ID: [u8; 32] = [128; 32];
 
+/// Data of the executor tx. The shares covenant only lets txs carrying it mint
+/// and burn pool shares, and the stf only accepts it on the executor tx, whose
+/// mints and burns it checks.
+pub const EXECUTOR_TX_DATA: &[u8] = b"SWAPVM_EXECUTOR_TX";
+
+/// Most pools a route found by [`ZoneData::best_route`] goes through, the
+/// search visits every route up to that length.
+pub const MAX_ROUTE_HOPS: usize = 4;
+
+/// Why the zone rejects a tx. The stf fails on such a tx, and the executor
+/// refuses to take it in its bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxError {
+    /// The tx goes through a pair without a pool
+    NoPool(Pair),
+    /// The amounts of the tx overflow, or can not be priced by an empty pool
+    Overflow,
+    /// The tx gives less than the limit of its goal
+    BelowLimit { amount_out: u64, limit: u64 },
+    /// The deposits of the tx would mint different shares of the pool
+    UnbalancedDeposit(Pair),
+    /// The tx burns all the shares of the pool
+    DrainsPool(Pair),
+}
+
+impl std::fmt::Display for TxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoPool(pair) => write!(f, "no pool for the pair {pair:?}"),
+            Self::Overflow => write!(f, "the amounts of the tx overflow the pools"),
+            Self::BelowLimit { amount_out, limit } => {
+                write!(f, "the tx gives {amount_out}, below its limit {limit}")
+            }
+            Self::UnbalancedDeposit(pair) => {
+                write!(f, "the deposits are not at the ratio of the pool {pair:?}")
+            }
+            Self::DrainsPool(pair) => write!(f, "the tx drains the pool {pair:?}"),
+        }
+    }
+}
+
+impl std::error::Error for TxError {}
+
+/// What a run of the stf guest proves. The guest is also the minting and
+/// burning covenant of the pool shares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StfMode {
+    /// A transition of the zone
+    Transition,
+    /// A mint or burn of pool shares by a tx marked as the executor tx
+    SharesCovenant,
+}
+
+/// Whether `tx` is marked as the executor tx of the swapvm zone.
+pub fn is_executor_tx(tx: &Tx) -> bool {
+    tx.updates.contains_key(&ZONE_ID) && tx.data == EXECUTOR_TX_DATA
+}
+
 pub fn swap_goal_unit() -> UnitWitness {
     UnitWitness::nop(b"SWAP")
 }
 
+pub fn add_liquidity_goal_unit() -> UnitWitness {
+    UnitWitness::nop(b"ADD_LIQUIDITY")
+}
+
+pub fn remove_liquidity_goal_unit() -> UnitWitness {
+    UnitWitness::nop(b"REMOVE_LIQUIDITY")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SwapOutput {
     // value will be set at the market price
@@ -43,12 +109,63 @@ impl SwapOutput {
             nf_pk,
         }
     }
+
+    pub fn to_output(self, value: u64) -> OutputWitness {
+        OutputWitness {
+            state: self.state,
+            value,
+            unit: self.unit,
+            nonce: self.nonce,
+            zone_id: self.zone_id,
+            nf_pk: self.nf_pk,
+        }
+    }
+}
+
+/// The args of a goal note, the single output a user tx makes in the swapvm
+/// zone. The executor spends the goal note and fulfills the args.
+pub trait Goal: DeserializeOwned {
+    fn unit() -> UnitWitness;
+
+    fn nonce(&self) -> Nonce;
+
+    fn note(nonce: Nonce) -> InputWitness {
+        goal_note(Self::unit(), nonce)
+    }
+
+    /// Whether `tx` asks for this goal, i.e. it creates the goal note.
+    fn is_requested_by(tx: &Tx) -> bool {
+        tx.balance.unit_balance(Self::unit().unit()).is_neg()
+    }
+
+    /// Read the args of the goal note created by `zone_update`, checking the
+    /// note matches the args.
+    fn read(zone_update: &LedgerUpdate) -> Self {
+        // as a simplifying assumption, the goal note is the only output
+        assert_eq!(zone_update.outputs.len(), 1);
+        let (goal_cm, args_bytes) = &zone_update.outputs[0];
+        let args: Self = cl::deserialize(args_bytes);
+        assert_eq!(goal_cm, &Self::note(args.nonce()).note_commitment());
+        args
+    }
+}
+
+/// Net amounts provided by `tx`, excluding its goal note.
+pub fn provided(tx: &Tx, goal: Unit) -> Vec<(Unit, u64)> {
+    tx.balance
+        .balances
+        .iter()
+        .filter(|bal| bal.unit != goal && bal.is_pos())
+        .map(|bal| (bal.unit, bal.pos - bal.neg))
+        .collect()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SwapArgs {
     // the user specifies the template forthe output note
     pub output: SwapOutput,
+    // units the swap goes through before the output unit, empty for a direct swap
+    pub route: Vec<Unit>,
     // minimum value of the output note
     pub limit: u64,
     // the nonce used in the swap goal note
@@ -56,40 +173,98 @@ pub struct SwapArgs {
 }
 
 impl SwapArgs {
+    /// Units the swap goes through from `t_in` to the output unit.
+    pub fn path(&self, t_in: Unit) -> Vec<Unit> {
+        [&[t_in], self.route.as_slice(), &[self.output.unit]].concat()
+    }
+
     pub fn to_output(self, value: u64) -> OutputWitness {
         assert!(value >= self.limit);
-        OutputWitness {
-            state: self.output.state,
-            value,
-            unit: self.output.unit,
-            nonce: self.output.nonce,
-            zone_id: self.output.zone_id,
-            nf_pk: self.output.nf_pk,
-        }
+        self.output.to_output(value)
     }
 }
 
-pub fn swap_goal_note(nonce: Nonce) -> InputWitness {
+impl Goal for SwapArgs {
+    fn unit() -> UnitWitness {
+        swap_goal_unit()
+    }
+
+    fn nonce(&self) -> Nonce {
+        self.nonce
+    }
+}
+
+/// Deposit both tokens of a pool in exchange for pool shares, the first
+/// deposit in a pair creates its pool.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddLiquidityArgs {
+    // units of the pool, the executor needs them to spend the fund notes of a new pool
+    pub pair: [UnitWitness; 2],
+    // template for the share note, its unit is the share unit of the pool
+    pub output: SwapOutput,
+    // minimum amount of shares minted
+    pub min_shares: u64,
+    // the nonce used in the goal note
+    pub nonce: Nonce,
+}
+
+impl Goal for AddLiquidityArgs {
+    fn unit() -> UnitWitness {
+        add_liquidity_goal_unit()
+    }
+
+    fn nonce(&self) -> Nonce {
+        self.nonce
+    }
+}
+
+/// Burn pool shares in exchange for the pro-rata balances of the pool.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveLiquidityArgs {
+    // templates for the notes receiving each token of the pool
+    pub outputs: [SwapOutput; 2],
+    // minimum value of each output note
+    pub limits: [u64; 2],
+    // the nonce used in the goal note
+    pub nonce: Nonce,
+}
+
+impl Goal for RemoveLiquidityArgs {
+    fn unit() -> UnitWitness {
+        remove_liquidity_goal_unit()
+    }
+
+    fn nonce(&self) -> Nonce {
+        self.nonce
+    }
+}
+
+fn goal_note(unit_witness: UnitWitness, nonce: Nonce) -> InputWitness {
     InputWitness {
         state: [0u8; 32],
         value: 1,
-        unit_witness: swap_goal_unit(),
+        unit_witness,
         nonce,
         zone_id: ZONE_ID,
         nf_sk: NullifierSecret::zero(),
     }
 }
 
-// TODO: order pair tokens lexicographically
-fn get_pair_share_unit(pair: Pair) -> UnitWitness {
+pub fn swap_goal_note(nonce: Nonce) -> InputWitness {
+    SwapArgs::note(nonce)
+}
+
+/// The shares unit of `pair`, only minted and burned with a proof of the
+/// `covenant` image, the swapvm stf.
+pub fn get_pair_share_unit(pair: Pair, covenant: [u8; 32]) -> UnitWitness {
     let mut hasher = Sha256::new();
     hasher.update(b"SWAP_PAIR_SHARE_UNIT");
     hasher.update(pair.t0);
     hasher.update(pair.t1);
     UnitWitness {
         spending_covenant: NOP_COVENANT,
-        minting_covenant: NOP_COVENANT,
-        burning_covenant: NOP_COVENANT,
+        minting_covenant: covenant,
+        burning_covenant: covenant,
         arg: hasher.finalize().into(),
     }
 }
@@ -105,10 +280,18 @@ pub struct Swap {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZoneData {
-    pub nfs: BTreeSet<Nullifier>,
+    /// Nullifier of the current fund note of each unit
+    pub nfs: BTreeMap<Unit, Nullifier>,
     pub pools: BTreeMap<Pair, Pool>,
     pub zone_id: ZoneId,
-    pub swaps_output: Vec<OutputWitness>,
+    /// Image of the swapvm stf, the covenant of the pool shares
+    pub shares_covenant: [u8; 32],
+    /// Notes the executor tx owes to the users: swap outputs, pool shares and
+    /// removed liquidity
+    pub outputs: Vec<OutputWitness>,
+    /// Pool shares the executor tx owes to mint and burn, per shares unit
+    pub mints: BTreeMap<Unit, u64>,
+    pub burns: BTreeMap<Unit, u64>,
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
@@ -135,6 +318,15 @@ pub struct Pool {
 }
 
 impl Pool {
+    fn new(pair: Pair, shares_covenant: [u8; 32]) -> Self {
+        Self {
+            balance_0: 0,
+            balance_1: 0,
+            shares_unit: get_pair_share_unit(pair, shares_covenant).unit(),
+            total_shares: 0,
+        }
+    }
+
     pub fn price(&self) -> f64 {
         self.balance_1 as f64 / self.balance_0 as f64
     }
@@ -142,12 +334,15 @@ impl Pool {
 
 // Txs are of the following form:
 impl ZoneData {
-    pub fn new() -> Self {
+    pub fn new(shares_covenant: [u8; 32]) -> Self {
         Self {
             nfs: Default::default(),
             pools: Default::default(),
             zone_id: ZONE_ID,
-            swaps_output: Default::default(),
+            shares_covenant,
+            outputs: Default::default(),
+            mints: Default::default(),
+            burns: Default::default(),
         }
     }
 
@@ -172,37 +367,284 @@ impl ZoneData {
             (pool.balance_1, pool.balance_0)
         };
 
-        let amount_in_after_fee = amount_in * 1000 - amount_in * 3;
+        // None when the amounts are too large for the pool to price them
+        let amount_in_after_fee = amount_in.checked_mul(997)?;
+        let denominator = balance_in
+            .checked_mul(1000)?
+            .checked_add(amount_in_after_fee)?;
+
+        mul_div(balance_out, amount_in_after_fee, denominator)
+    }
+
+    /// Amount received by swapping `amount_in` of `path[0]` through each unit
+    /// of `path` in turn.
+    pub fn route_amount_out(&self, path: &[Unit], amount_in: u64) -> Option<u64> {
+        path.windows(2).try_fold(amount_in, |amount, hop| {
+            self.amount_out(hop[0], hop[1], amount)
+        })
+    }
 
-        let amount_out =
-            (balance_out * 1000 * amount_in_after_fee) / (balance_in * 1000 + amount_in_after_fee);
+    /// The route from `t_in` to `t_out` through at most `max_hops` pools, capped
+    /// at [`MAX_ROUTE_HOPS`], giving the most of `t_out`, along with that
+    /// amount. The route lists the units in between, as in [`SwapArgs::route`].
+    ///
+    /// Routes never go through a unit twice, the shortest route wins ties.
+    pub fn best_route(
+        &self,
+        t_in: Unit,
+        t_out: Unit,
+        amount_in: u64,
+        max_hops: usize,
+    ) -> Option<(Vec<Unit>, u64)> {
+        // the units each unit has a pool with, indexed once for the search
+        let mut neighbours = BTreeMap::<Unit, Vec<Unit>>::new();
+        for pair in self.pools.keys() {
+            neighbours.entry(pair.t0).or_default().push(pair.t1);
+            neighbours.entry(pair.t1).or_default().push(pair.t0);
+        }
 
-        Some(amount_out / 1000)
+        let mut best = None;
+        self.search_routes(
+            &neighbours,
+            &mut vec![t_in],
+            t_out,
+            amount_in,
+            max_hops.min(MAX_ROUTE_HOPS),
+            &mut best,
+        );
+        best
+    }
+
+    fn search_routes(
+        &self,
+        neighbours: &BTreeMap<Unit, Vec<Unit>>,
+        path: &mut Vec<Unit>,
+        t_out: Unit,
+        amount: u64,
+        hops_left: usize,
+        best: &mut Option<(Vec<Unit>, u64)>,
+    ) {
+        let last = *path.last().unwrap();
+        if last == t_out {
+            let route = &path[1..path.len() - 1];
+            if best.as_ref().is_none_or(|(best_route, best_amount)| {
+                (amount, std::cmp::Reverse(route.len()))
+                    > (*best_amount, std::cmp::Reverse(best_route.len()))
+            }) {
+                *best = Some((route.to_vec(), amount));
+            }
+            return;
+        }
+        if hops_left == 0 {
+            return;
+        }
+        for &next in neighbours.get(&last).into_iter().flatten() {
+            if path.contains(&next) {
+                continue;
+            }
+            let Some(amount_out) = self.amount_out(last, next, amount) else {
+                continue;
+            };
+            path.push(next);
+            self.search_routes(neighbours, path, t_out, amount_out, hops_left - 1, best);
+            path.pop();
+        }
     }
 
     /// A swap does not need to directly modify the pool balances, but the executor
     /// should make sure that required funds are provided.
-    pub fn swap(&mut self, t_in: Unit, amount_in: u64, swap: SwapArgs) {
-        // TODO: calculate amout outside proof and check here for efficiency
-        let amount_out = self.amount_out(t_in, swap.output.unit, amount_in).unwrap();
+    ///
+    /// The swap goes through each pool of its route in turn, only the final
+    /// output is checked against the limit. Returns the amount paid out, the
+    /// zone is left untouched on error.
+    pub fn swap(&mut self, t_in: Unit, amount_in: u64, swap: SwapArgs) -> Result<u64, TxError> {
+        let path = swap.path(t_in);
+        // the pools the route went through are restored if the swap is rejected
+        let pools = self.pools.clone();
+        let swapped = path.windows(2).try_fold(amount_in, |amount, hop| {
+            self.swap_pair(hop[0], hop[1], amount)
+        });
+        let amount_out = match swapped {
+            Ok(amount_out) if amount_out >= swap.limit => amount_out,
+            Ok(amount_out) => {
+                self.pools = pools;
+                return Err(TxError::BelowLimit {
+                    amount_out,
+                    limit: swap.limit,
+                });
+            }
+            Err(err) => {
+                self.pools = pools;
+                return Err(err);
+            }
+        };
+        self.outputs.push(swap.to_output(amount_out));
+        Ok(amount_out)
+    }
 
-        let pair = Pair::new(t_in, swap.output.unit);
-        let pool = self.pools.get_mut(&pair).unwrap();
+    fn swap_pair(&mut self, t_in: Unit, t_out: Unit, amount_in: u64) -> Result<u64, TxError> {
+        // TODO: calculate amout outside proof and check here for efficiency
+        let pair = Pair::new(t_in, t_out);
+        let Some(pool) = self.pools.get(&pair) else {
+            return Err(TxError::NoPool(pair));
+        };
+        let amount_out = self
+            .amount_out(t_in, t_out, amount_in)
+            .ok_or(TxError::Overflow)?;
 
+        let mut pool = pool.clone();
         let (balance_in, balance_out) = if pair.t0 == t_in {
             (&mut pool.balance_0, &mut pool.balance_1)
         } else {
             (&mut pool.balance_1, &mut pool.balance_0)
         };
+        *balance_in = balance_in.checked_add(amount_in).ok_or(TxError::Overflow)?;
+        *balance_out = balance_out
+            .checked_sub(amount_out)
+            .ok_or(TxError::Overflow)?;
+
+        self.pools.insert(pair, pool);
+        Ok(amount_out)
+    }
+
+    /// Deposit both tokens of a pool, minting shares at the pool ratio.
+    /// Returns the shares minted, the zone is left untouched on error.
+    ///
+    /// Both deposits must mint the same shares, up to rounding. Tokens
+    /// deposited above the pool ratio would be donated to the other share
+    /// holders, and refunding them would need a template for a refund note of
+    /// each token, so such deposits are rejected. The first deposit creates
+    /// the pool at its own ratio and mints the geometric mean of the deposits.
+    pub fn add_liquidity(
+        &mut self,
+        deposits: [(Unit, u64); 2],
+        args: AddLiquidityArgs,
+    ) -> Result<u64, TxError> {
+        let [(t_a, in_a), (t_b, in_b)] = deposits;
+        let pair = Pair::new(t_a, t_b);
+        assert_ne!(pair.t0, pair.t1);
+        assert_eq!(pair, Pair::new(args.pair[0].unit(), args.pair[1].unit()));
+        let mut pool = match self.pools.get(&pair) {
+            Some(pool) => pool.clone(),
+            None => Pool::new(pair, self.shares_covenant),
+        };
+        assert_eq!(args.output.unit, pool.shares_unit);
+
+        let (in_0, in_1) = if pair.t0 == t_a {
+            (in_a, in_b)
+        } else {
+            (in_b, in_a)
+        };
+        // pools are never drained, no shares means the pool is new
+        let shares = if pool.total_shares == 0 {
+            (in_0 as u128 * in_1 as u128).isqrt() as u64
+        } else {
+            let shares_0 =
+                mul_div(in_0, pool.total_shares, pool.balance_0).ok_or(TxError::Overflow)?;
+            let shares_1 =
+                mul_div(in_1, pool.total_shares, pool.balance_1).ok_or(TxError::Overflow)?;
+            if shares_0 != shares_1 {
+                return Err(TxError::UnbalancedDeposit(pair));
+            }
+            shares_0
+        };
+        let limit = args.min_shares.max(1);
+        if shares < limit {
+            return Err(TxError::BelowLimit {
+                amount_out: shares,
+                limit,
+            });
+        }
+
+        pool.balance_0 = pool.balance_0.checked_add(in_0).ok_or(TxError::Overflow)?;
+        pool.balance_1 = pool.balance_1.checked_add(in_1).ok_or(TxError::Overflow)?;
+        pool.total_shares = pool
+            .total_shares
+            .checked_add(shares)
+            .ok_or(TxError::Overflow)?;
+        let minted = self.mints.get(&pool.shares_unit).copied().unwrap_or(0);
+        let minted = minted.checked_add(shares).ok_or(TxError::Overflow)?;
+
+        self.mints.insert(pool.shares_unit, minted);
+        self.pools.insert(pair, pool);
+        self.outputs.push(args.output.to_output(shares));
+        Ok(shares)
+    }
+
+    /// Burn `shares` of the pool of the output units for their pro-rata part
+    /// of the pool balances. Returns the amounts paid out, in the order of the
+    /// outputs, the zone is left untouched on error.
+    ///
+    /// The pool can not be drained, some shares always remain.
+    pub fn remove_liquidity(
+        &mut self,
+        shares_unit: Unit,
+        shares: u64,
+        args: RemoveLiquidityArgs,
+    ) -> Result<[u64; 2], TxError> {
+        let [out_a, out_b] = args.outputs;
+        let pair = Pair::new(out_a.unit, out_b.unit);
+        let mut pool = self.pools.get(&pair).ok_or(TxError::NoPool(pair))?.clone();
+        assert_eq!(shares_unit, pool.shares_unit);
+        if shares >= pool.total_shares {
+            return Err(TxError::DrainsPool(pair));
+        }
+
+        let out_0 = mul_div(pool.balance_0, shares, pool.total_shares).ok_or(TxError::Overflow)?;
+        let out_1 = mul_div(pool.balance_1, shares, pool.total_shares).ok_or(TxError::Overflow)?;
+        let amounts = if pair.t0 == out_a.unit {
+            [out_0, out_1]
+        } else {
+            [out_1, out_0]
+        };
+        for (amount, limit) in amounts.into_iter().zip(args.limits) {
+            let limit = limit.max(1);
+            if amount < limit {
+                return Err(TxError::BelowLimit {
+                    amount_out: amount,
+                    limit,
+                });
+            }
+        }
 
-        *balance_in += amount_in;
-        *balance_out -= amount_out;
-        self.swaps_output.push(swap.to_output(amount_out));
+        pool.balance_0 = pool.balance_0.checked_sub(out_0).ok_or(TxError::Overflow)?;
+        pool.balance_1 = pool.balance_1.checked_sub(out_1).ok_or(TxError::Overflow)?;
+        pool.total_shares -= shares;
+        let burned = self.burns.get(&shares_unit).copied().unwrap_or(0);
+        let burned = burned.checked_add(shares).ok_or(TxError::Overflow)?;
+
+        self.burns.insert(shares_unit, burned);
+        self.pools.insert(pair, pool);
+        for (output, amount) in args.outputs.into_iter().zip(amounts) {
+            self.outputs.push(output.to_output(amount));
+        }
+        Ok(amounts)
     }
 
     /// Check no pool notes are used in this tx
     pub fn validate_no_pools(&self, zone_update: &LedgerUpdate) -> bool {
-        self.nfs.iter().all(|nf| !zone_update.has_input(nf))
+        self.nfs.values().all(|nf| !zone_update.has_input(nf))
+    }
+
+    /// The fund note holding `value` of `unit` for the pools, replacing the
+    /// current fund note of the unit.
+    ///
+    /// Its nonce is derived from the nullifier of the fund note it replaces,
+    /// or from the unit for the first fund note of a unit.
+    pub fn fund_note(&self, unit: UnitWitness, value: u64) -> InputWitness {
+        let seed = match self.nfs.get(&unit.unit()) {
+            Some(nf) => nf.as_bytes().to_vec(),
+            None => [self.zone_id, unit.unit()].concat(),
+        };
+        let nonce = cl::hash(&[b"SWAPVM_FUND_NOTE".as_slice(), &seed].concat());
+        InputWitness {
+            state: [0u8; 32],
+            value,
+            unit_witness: unit,
+            nonce: Nonce::from_bytes(nonce),
+            zone_id: self.zone_id,
+            nf_sk: FUNDS_SK,
+        }
     }
 
     pub fn pools_update(&mut self, tx: &Tx, pool_notes: &[InputWitness]) {
@@ -211,21 +653,24 @@ impl ZoneData {
             return;
         };
         // check all previous nullifiers are used
-        assert!(self.nfs.iter().all(|nf| zone_update.has_input(nf)));
-        self.nfs.clear();
+        assert!(self.nfs.values().all(|nf| zone_update.has_input(nf)));
 
-        // check the exepected pool balances are reflected in the tx outputs
+        // check the exepected pool balances are reflected in the tx outputs,
+        // with a single fund note per unit
         let expected_pool_balances = self.expected_pool_balances();
+        assert_eq!(pool_notes.len(), expected_pool_balances.len());
+        let mut nfs = BTreeMap::new();
         for note in pool_notes {
-            assert_eq!(note.nf_sk, FUNDS_SK);
-            // TODO: check nonce derivation
-            let output = note.to_output();
-            let value = expected_pool_balances.get(&output.unit).unwrap();
-            assert_eq!(note.value, *value);
-
-            assert!(zone_update.has_output(&output.note_commitment()));
-            self.nfs.insert(note.nullifier());
+            let unit = note.unit_witness.unit();
+            let value = expected_pool_balances
+                .get(&unit)
+                .expect("fund note for a unit without a pool");
+            assert_eq!(note, &self.fund_note(note.unit_witness, *value));
+
+            assert!(zone_update.has_output(&note.to_output().note_commitment()));
+            assert!(nfs.insert(unit, note.nullifier()).is_none());
         }
+        self.nfs = nfs;
     }
 
     pub fn expected_pool_balances(&self) -> BTreeMap<Unit, u64> {
@@ -238,41 +683,74 @@ impl ZoneData {
         expected_pool_balances
     }
 
-    // TODO: only for testing purposes
-    pub fn add_liquidity(&mut self, t0_unit: Unit, t1_unit: Unit, t0_in: u64, t1_in: u64) {
+    /// Create a pool backed by the executor fund notes, its initial shares are
+    /// not held by anyone.
+    pub fn seed_pool(&mut self, t0_unit: Unit, t1_unit: Unit, t0_in: u64, t1_in: u64) {
         let pair = Pair::new(t0_unit, t1_unit);
-        let pool = self.pools.entry(pair).or_insert(Pool {
-            balance_0: 0,
-            balance_1: 0,
-            shares_unit: get_pair_share_unit(pair).unit(),
-            total_shares: 0,
-        });
+        assert!(!self.pools.contains_key(&pair), "pool already exists");
         let (balance_0, balance_1) = if pair.t0 == t0_unit {
-            (&mut pool.balance_0, &mut pool.balance_1)
+            (t0_in, t1_in)
         } else {
-            (&mut pool.balance_1, &mut pool.balance_0)
+            (t1_in, t0_in)
         };
-
-        *balance_0 += t0_in;
-        *balance_1 += t1_in;
+        self.pools.insert(
+            pair,
+            Pool {
+                balance_0,
+                balance_1,
+                total_shares: (balance_0 as u128 * balance_1 as u128).isqrt() as u64,
+                ..Pool::new(pair, self.shares_covenant)
+            },
+        );
     }
 
-    pub fn check_swaps_executed(&self, tx: &Tx) {
+    pub fn check_outputs_executed(&self, tx: &Tx) {
         let zone_update = tx.updates.get(&self.zone_id).unwrap();
-        for output in &self.swaps_output {
+        for output in &self.outputs {
             assert!(zone_update.has_output(&output.note_commitment()));
         }
     }
 
-    pub fn update_and_commit(mut self, tx: &Tx, pool_notes: &[InputWitness]) -> [u8; 32] {
-        self.pools_update(&tx, pool_notes);
-        self.check_swaps_executed(&tx);
+    /// Check the tx mints and burns exactly the pool shares owed, and nothing
+    /// else.
+    pub fn check_shares_executed(&self, tx: &TxWitness) {
+        let mut mints = BTreeMap::<Unit, u64>::new();
+        for mint in &tx.mints {
+            *mints.entry(mint.unit.unit()).or_default() += mint.amount;
+        }
+        let mut burns = BTreeMap::<Unit, u64>::new();
+        for burn in &tx.burns {
+            *burns.entry(burn.unit.unit()).or_default() += burn.amount;
+        }
+        assert_eq!(mints, self.mints, "executor tx mints other shares");
+        assert_eq!(burns, self.burns, "executor tx burns other shares");
+    }
+
+    /// Check the executor tx `tx` settles what the zone owes, `witness` is
+    /// the witness of `tx` to check the shares it mints and burns.
+    pub fn update_and_commit(
+        mut self,
+        tx: &Tx,
+        witness: &TxWitness,
+        pool_notes: &[InputWitness],
+    ) -> [u8; 32] {
+        let committed = witness.commit(
+            &witness.mint_amounts(),
+            &witness.burn_amounts(),
+            &witness.inputs_derived_fields(),
+        );
+        assert_eq!(committed.root, tx.root, "witness of another tx");
+        assert_eq!(tx.data, EXECUTOR_TX_DATA);
+
+        self.pools_update(tx, pool_notes);
+        self.check_outputs_executed(tx);
+        self.check_shares_executed(witness);
         self.commit()
     }
 
     pub fn commit(&self) -> [u8; 32] {
         let mut hasher = Sha256::new();
-        for nf in &self.nfs {
+        for nf in self.nfs.values() {
             hasher.update(nf);
         }
         for (pair, pool) in self.pools.iter() {
@@ -280,14 +758,18 @@ impl ZoneData {
             hasher.update(pair.t1);
             hasher.update(pool.balance_0.to_le_bytes());
             hasher.update(pool.balance_1.to_le_bytes());
+            hasher.update(pool.total_shares.to_le_bytes());
+            hasher.update(pool.shares_unit);
         }
         hasher.update(self.zone_id);
+        hasher.update(self.shares_covenant);
         hasher.finalize().into()
     }
 }
 
-impl Default for ZoneData {
-    fn default() -> Self {
-        Self::new()
-    }
+/// `a * b / c` rounded down, without overflowing the intermediate product.
+/// None when `c` is zero or the result does not fit a u64.
+fn mul_div(a: u64, b: u64, c: u64) -> Option<u64> {
+    let quotient = (a as u128 * b as u128).checked_div(c as u128)?;
+    u64::try_from(quotient).ok()
 }
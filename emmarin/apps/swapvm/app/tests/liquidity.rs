@@ -0,0 +1,193 @@
+use app::{AddLiquidityArgs, Pair, RemoveLiquidityArgs, SwapOutput, TxError, ZoneData, ZONE_ID};
+use cl::{
+    crust::{Nonce, NullifierSecret, Unit, UnitWitness},
+    test_utils::{mem, nmo},
+};
+
+const STF: [u8; 32] = [1; 32];
+
+fn shares_unit() -> Unit {
+    app::get_pair_share_unit(Pair::new(nmo().unit(), mem().unit()), STF).unit()
+}
+
+fn output(unit: Unit) -> SwapOutput {
+    let mut rng = rand::thread_rng();
+    SwapOutput::basic(
+        unit,
+        ZONE_ID,
+        NullifierSecret::random(&mut rng).commit(),
+        &mut rng,
+    )
+}
+
+fn add_args(min_shares: u64) -> AddLiquidityArgs {
+    AddLiquidityArgs {
+        pair: [nmo(), mem()],
+        output: output(shares_unit()),
+        min_shares,
+        nonce: Nonce::random(rand::thread_rng()),
+    }
+}
+
+fn remove_args(limits: [u64; 2]) -> RemoveLiquidityArgs {
+    RemoveLiquidityArgs {
+        outputs: [output(nmo().unit()), output(mem().unit())],
+        limits,
+        nonce: Nonce::random(rand::thread_rng()),
+    }
+}
+
+fn pool() -> ZoneData {
+    let mut swapvm_state = ZoneData::new(STF);
+    // 100 initial shares
+    swapvm_state.seed_pool(nmo().unit(), mem().unit(), 10, 1000);
+    swapvm_state
+}
+
+#[test]
+fn add_liquidity_mints_shares_at_the_pool_ratio() {
+    let mut swapvm_state = pool();
+
+    // deposits are accepted in either order
+    let shares = swapvm_state
+        .add_liquidity([(mem().unit(), 500), (nmo().unit(), 5)], add_args(50))
+        .unwrap();
+    assert_eq!(shares, 50);
+    assert_eq!(swapvm_state.outputs.len(), 1);
+    assert_eq!(swapvm_state.outputs[0].value, 50);
+    assert_eq!(swapvm_state.outputs[0].unit, shares_unit());
+    assert_eq!(swapvm_state.mints[&shares_unit()], 50);
+
+    // deposits above the pool ratio are rejected and leave the zone untouched
+    let pair = Pair::new(nmo().unit(), mem().unit());
+    assert_eq!(
+        swapvm_state.add_liquidity([(nmo().unit(), 3), (mem().unit(), 900)], add_args(0)),
+        Err(TxError::UnbalancedDeposit(pair))
+    );
+    assert_eq!(swapvm_state.pools[&pair].total_shares, 150);
+    assert_eq!(swapvm_state.outputs.len(), 1);
+
+    let shares = swapvm_state
+        .add_liquidity([(nmo().unit(), 3), (mem().unit(), 300)], add_args(0))
+        .unwrap();
+    assert_eq!(shares, 30);
+
+    let expected = swapvm_state.expected_pool_balances();
+    assert_eq!(expected[&nmo().unit()], 18);
+    assert_eq!(expected[&mem().unit()], 1800);
+    assert_eq!(
+        swapvm_state.pools[&Pair::new(nmo().unit(), mem().unit())].total_shares,
+        180
+    );
+}
+
+#[test]
+fn add_liquidity_respects_min_shares() {
+    let mut swapvm_state = pool();
+    assert_eq!(
+        swapvm_state.add_liquidity([(nmo().unit(), 5), (mem().unit(), 500)], add_args(51)),
+        Err(TxError::BelowLimit {
+            amount_out: 50,
+            limit: 51
+        })
+    );
+    assert!(swapvm_state.mints.is_empty());
+}
+
+#[test]
+fn add_liquidity_rejects_overflowing_balances() {
+    let mut swapvm_state = ZoneData::new(STF);
+    swapvm_state.seed_pool(nmo().unit(), mem().unit(), u64::MAX - 10, u64::MAX - 10);
+    assert_eq!(
+        swapvm_state.add_liquidity([(nmo().unit(), 20), (mem().unit(), 20)], add_args(0)),
+        Err(TxError::Overflow)
+    );
+}
+
+#[test]
+fn first_liquidity_creates_the_pool() {
+    let mut swapvm_state = ZoneData::new(STF);
+
+    let shares = swapvm_state
+        .add_liquidity([(nmo().unit(), 4), (mem().unit(), 900)], add_args(60))
+        .unwrap();
+    assert_eq!(shares, 60);
+    let pool = &swapvm_state.pools[&Pair::new(nmo().unit(), mem().unit())];
+    assert_eq!(pool.shares_unit, shares_unit());
+    assert_eq!(pool.total_shares, 60);
+    assert_eq!(
+        swapvm_state.pair_price(nmo().unit(), mem().unit()),
+        Some(225.0)
+    );
+}
+
+#[test]
+#[should_panic]
+fn add_liquidity_matches_the_pair() {
+    let mut args = add_args(0);
+    args.pair = [nmo(), UnitWitness::nop(b"PIZZA")];
+    let _ = pool().add_liquidity([(nmo().unit(), 5), (mem().unit(), 500)], args);
+}
+
+#[test]
+fn fund_notes_derive_from_the_notes_they_replace() {
+    let mut swapvm_state = ZoneData::new(STF);
+
+    let first = swapvm_state.fund_note(nmo(), 10);
+    assert_eq!(first, swapvm_state.fund_note(nmo(), 10));
+    assert_ne!(first.nonce, swapvm_state.fund_note(mem(), 10).nonce);
+
+    swapvm_state.nfs.insert(nmo().unit(), first.nullifier());
+    let next = swapvm_state.fund_note(nmo(), 10);
+    assert_ne!(next.nonce, first.nonce);
+    assert_ne!(next.nullifier(), first.nullifier());
+}
+
+#[test]
+fn remove_liquidity_pays_out_pro_rata() {
+    let mut swapvm_state = pool();
+
+    let amounts = swapvm_state
+        .remove_liquidity(shares_unit(), 25, remove_args([2, 250]))
+        .unwrap();
+    assert_eq!(amounts, [2, 250]);
+    assert_eq!(swapvm_state.burns[&shares_unit()], 25);
+    assert_eq!(
+        swapvm_state
+            .outputs
+            .iter()
+            .map(|note| (note.unit, note.value))
+            .collect::<Vec<_>>(),
+        vec![(nmo().unit(), 2), (mem().unit(), 250)]
+    );
+
+    let expected = swapvm_state.expected_pool_balances();
+    assert_eq!(expected[&nmo().unit()], 8);
+    assert_eq!(expected[&mem().unit()], 750);
+    assert_eq!(
+        swapvm_state.pools[&Pair::new(nmo().unit(), mem().unit())].total_shares,
+        75
+    );
+}
+
+#[test]
+fn remove_liquidity_respects_limits() {
+    let mut swapvm_state = pool();
+    assert_eq!(
+        swapvm_state.remove_liquidity(shares_unit(), 25, remove_args([3, 0])),
+        Err(TxError::BelowLimit {
+            amount_out: 2,
+            limit: 3
+        })
+    );
+    assert!(swapvm_state.burns.is_empty());
+    assert!(swapvm_state.outputs.is_empty());
+}
+
+#[test]
+fn remove_liquidity_can_not_drain_the_pool() {
+    assert_eq!(
+        pool().remove_liquidity(shares_unit(), 100, remove_args([0, 0])),
+        Err(TxError::DrainsPool(Pair::new(nmo().unit(), mem().unit())))
+    );
+}
@@ -10,12 +10,12 @@ fn mem() -> UnitWitness {
 
 #[test]
 fn pair_price() {
-    let mut swapvm_state = ZoneData::new();
+    let mut swapvm_state = ZoneData::new([1; 32]);
 
     // initially there is no NMO/MEM pair
     assert_eq!(swapvm_state.pair_price(nmo().unit(), mem().unit()), None);
 
-    swapvm_state.add_liquidity(nmo().unit(), mem().unit(), 10, 100);
+    swapvm_state.seed_pool(nmo().unit(), mem().unit(), 10, 100);
 
     // given that there is 1nmo:10mem in the pool, the price should show that we get 10 MEM for 1 NMO
     assert_eq!(
@@ -0,0 +1,153 @@
+use app::{Pair, SwapArgs, SwapOutput, TxError, ZoneData, MAX_ROUTE_HOPS, ZONE_ID};
+use cl::{
+    crust::{Nonce, NullifierSecret, Unit, UnitWitness},
+    test_utils,
+};
+
+fn nmo() -> Unit {
+    test_utils::nmo().unit()
+}
+fn mem() -> Unit {
+    test_utils::mem().unit()
+}
+fn eth() -> Unit {
+    UnitWitness::nop(b"ETH").unit()
+}
+
+fn pools() -> ZoneData {
+    let mut swapvm_state = ZoneData::new([1; 32]);
+    swapvm_state.seed_pool(nmo(), mem(), 1_000_000, 10_000_000);
+    swapvm_state.seed_pool(mem(), eth(), 10_000_000, 100_000);
+    // a shallow direct pool, with a worse price than going through MEM
+    swapvm_state.seed_pool(nmo(), eth(), 1_000_000, 5_000);
+    swapvm_state
+}
+
+fn swap_args(unit: Unit, route: Vec<Unit>, limit: u64) -> SwapArgs {
+    let mut rng = rand::thread_rng();
+    SwapArgs {
+        output: SwapOutput::basic(
+            unit,
+            ZONE_ID,
+            NullifierSecret::random(&mut rng).commit(),
+            &mut rng,
+        ),
+        route,
+        limit,
+        nonce: Nonce::random(&mut rng),
+    }
+}
+
+#[test]
+fn best_route_maximizes_amount_out() {
+    let swapvm_state = pools();
+
+    let direct = swapvm_state.amount_out(nmo(), eth(), 1000).unwrap();
+    let through_mem = swapvm_state
+        .route_amount_out(&[nmo(), mem(), eth()], 1000)
+        .unwrap();
+    assert!(through_mem > direct);
+
+    assert_eq!(
+        swapvm_state.best_route(nmo(), eth(), 1000, 3),
+        Some((vec![mem()], through_mem))
+    );
+    // a single hop only allows the direct pool
+    assert_eq!(
+        swapvm_state.best_route(nmo(), eth(), 1000, 1),
+        Some((vec![], direct))
+    );
+    assert_eq!(
+        swapvm_state.best_route(nmo(), UnitWitness::nop(b"PIZZA").unit(), 1000, 3),
+        None
+    );
+}
+
+#[test]
+fn swap_goes_through_the_route() {
+    let mut swapvm_state = pools();
+    let (route, amount_out) = swapvm_state.best_route(nmo(), eth(), 1000, 3).unwrap();
+    let mem_out = swapvm_state.amount_out(nmo(), mem(), 1000).unwrap();
+
+    assert_eq!(
+        swapvm_state.swap(nmo(), 1000, swap_args(eth(), route, amount_out)),
+        Ok(amount_out)
+    );
+    assert_eq!(swapvm_state.outputs.len(), 1);
+    assert_eq!(swapvm_state.outputs[0].value, amount_out);
+
+    // both pools of the route moved, the intermediate MEM stays in the pools
+    let balances = swapvm_state.expected_pool_balances();
+    assert_eq!(balances[&nmo()], 2_001_000);
+    assert_eq!(balances[&mem()], 20_000_000);
+    assert_eq!(balances[&eth()], 105_000 - amount_out);
+    let nmo_mem = &swapvm_state.pools[&Pair::new(nmo(), mem())];
+    assert_eq!(nmo_mem.balance_0 + nmo_mem.balance_1, 11_001_000 - mem_out);
+}
+
+#[test]
+fn swap_limit_applies_to_the_final_output() {
+    let mut swapvm_state = pools();
+    let (route, amount_out) = swapvm_state.best_route(nmo(), eth(), 1000, 3).unwrap();
+    assert_eq!(
+        swapvm_state.swap(nmo(), 1000, swap_args(eth(), route, amount_out + 1)),
+        Err(TxError::BelowLimit {
+            amount_out,
+            limit: amount_out + 1
+        })
+    );
+    // the first pool of the route is restored along with the rest
+    assert_eq!(
+        swapvm_state.expected_pool_balances(),
+        pools().expected_pool_balances()
+    );
+    assert!(swapvm_state.outputs.is_empty());
+}
+
+#[test]
+fn swap_is_rejected_on_a_hop_without_pool() {
+    let mut swapvm_state = pools();
+    let pizza = UnitWitness::nop(b"PIZZA").unit();
+    assert_eq!(
+        swapvm_state.swap(nmo(), 1000, swap_args(pizza, vec![mem()], 0)),
+        Err(TxError::NoPool(Pair::new(mem(), pizza)))
+    );
+    assert_eq!(
+        swapvm_state.expected_pool_balances(),
+        pools().expected_pool_balances()
+    );
+}
+
+#[test]
+fn best_route_caps_the_hops() {
+    let units: Vec<Unit> = (0..=MAX_ROUTE_HOPS + 1)
+        .map(|i| UnitWitness::nop(&[b'U', i as u8]).unit())
+        .collect();
+    let mut swapvm_state = ZoneData::new([1; 32]);
+    for hop in units.windows(2) {
+        swapvm_state.seed_pool(hop[0], hop[1], 1_000_000, 1_000_000);
+    }
+
+    let longest = swapvm_state.best_route(units[0], units[MAX_ROUTE_HOPS], 1000, usize::MAX);
+    assert_eq!(
+        longest.map(|(route, _)| route),
+        Some(units[1..MAX_ROUTE_HOPS].to_vec())
+    );
+    assert_eq!(
+        swapvm_state.best_route(units[0], units[MAX_ROUTE_HOPS + 1], 1000, usize::MAX),
+        None
+    );
+}
+
+#[test]
+fn amount_out_does_not_overflow_deep_pools() {
+    let mut swapvm_state = ZoneData::new([1; 32]);
+    swapvm_state.seed_pool(nmo(), mem(), 1_000_000_000_000, 1_000_000_000_000);
+
+    assert_eq!(
+        swapvm_state.amount_out(nmo(), mem(), 1_000_000_000),
+        Some(996_006_981)
+    );
+    // too large for the pool to price
+    assert_eq!(swapvm_state.amount_out(nmo(), mem(), u64::MAX), None);
+}
@@ -0,0 +1,138 @@
+//! Records the cycle counts of the swapvm stf in the report format of the
+//! `bench` crate, compare runs with `bench compare`. Built with the `bench`
+//! feature: `cargo run --release --features bench --bin bench`.
+//!
+//! ```text
+//! bench [--swaps 1,4] [--out swapvm.json]
+//! ```
+
+use std::path::PathBuf;
+
+use app::ZONE_ID;
+use bench::report::{Record, Report};
+use cl::{
+    crust::{BundleWitness, InputWitness, Nonce, NullifierSecret, TxWitness},
+    test_utils::{mem, nmo},
+};
+use clap::Parser;
+use host::{ExecutorState, StfPrivate};
+use ledger::tx::ProvedTx;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+fn setup_executor(mut rng: impl RngCore) -> ExecutorState {
+    let mut exec_state = ExecutorState::new(NullifierSecret::random(&mut rng).commit());
+    exec_state.fund_pool([(nmo(), 1_000_000), (mem(), 9_000_000)], rng);
+    exec_state
+}
+
+/// Execute the stf over a bundle of `swaps` NMO -> MEM swaps and the executor
+/// tx solving them.
+fn swap(swaps: usize) -> risc0_zkvm::Result<Record> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut exec_state = setup_executor(&mut rng);
+
+    let users: Vec<_> = (0..swaps)
+        .map(|_| {
+            let sk = NullifierSecret::random(&mut rng);
+            let note = InputWitness {
+                state: [0u8; 32],
+                value: 10,
+                unit_witness: nmo(),
+                nonce: Nonce::random(&mut rng),
+                zone_id: ZONE_ID,
+                nf_sk: sk,
+            };
+            let (proof, _) = exec_state.observe_cm(&note.note_commitment());
+            (sk, note, proof)
+        })
+        .collect();
+
+    let old_zone_data = exec_state.swapvm.clone();
+    let old_ledger = exec_state.ledger.to_witness();
+
+    let mut txs = Vec::with_capacity(swaps + 1);
+    for (sk, note, proof) in users {
+        let nonce = Nonce::random(&mut rng);
+        let swap_tx = TxWitness::default().add_input(note, proof).add_output(
+            app::swap_goal_note(nonce).to_output(),
+            app::SwapArgs {
+                output: app::SwapOutput::basic(mem().unit(), ZONE_ID, sk.commit(), &mut rng),
+                route: vec![],
+                limit: 1,
+                nonce,
+            },
+        );
+        let swap_tx = ProvedTx::execute(swap_tx, vec![], vec![])?.public;
+        exec_state.process_tx(&swap_tx).unwrap();
+        txs.push(swap_tx);
+    }
+
+    let (exec_tx, fund_notes) = exec_state.update_and_get_executor_tx();
+    txs.push(ProvedTx::execute(exec_tx.clone(), vec![], vec![])?.public);
+
+    let bundle = BundleWitness::new(txs);
+    exec_state.ledger.add_bundle(bundle.root());
+    let nfs = bundle
+        .clone()
+        .commit()
+        .updates
+        .get(&ZONE_ID)
+        .map(|updates| {
+            updates
+                .iter()
+                .flat_map(|u| u.inputs.iter().copied())
+                .collect()
+        })
+        .unwrap_or_default();
+    exec_state.observe_nfs(nfs);
+
+    let session = StfPrivate {
+        zone_data: old_zone_data,
+        old_ledger,
+        new_ledger: exec_state.ledger.to_witness().commit(),
+        fund_notes,
+        bundle,
+        executor_tx: exec_tx,
+    }
+    .execute()?;
+
+    Ok(
+        Record::new("swapvm", &[("swaps", swaps as u64)]).with_cycles(
+            session.segments.len() as u64,
+            session.cycles(),
+            session.segments.iter().map(|s| 1u64 << s.po2).sum(),
+        ),
+    )
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Swaps settled by the stf, one run per value
+    #[arg(long, value_delimiter = ',', default_value = "1,4")]
+    swaps: Vec<usize>,
+
+    /// Where to write the report
+    #[arg(long, default_value = "swapvm.json")]
+    out: PathBuf,
+}
+
+fn main() -> risc0_zkvm::Result<()> {
+    let Args { swaps, out } = Args::parse();
+
+    let mut report = Report::default();
+    for swaps in swaps {
+        let record = swap(swaps)?;
+        println!(
+            "{}: {} user cycles, {} total cycles, {} segments",
+            record.key(),
+            record.user_cycles,
+            record.total_cycles,
+            record.segments
+        );
+        report.push(record);
+    }
+    report.write(out)?;
+
+    Ok(())
+}
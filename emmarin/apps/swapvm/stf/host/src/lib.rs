@@ -1,13 +1,23 @@
 use std::collections::BTreeMap;
 
-use app::{swap_goal_unit, SwapArgs, ZoneData};
-use cl::crust::{BundleWitness, InputWitness, NoteCommitment, Nullifier, Tx, TxWitness, Unit};
+pub use app::TxError;
+use app::{AddLiquidityArgs, Goal, Pair, RemoveLiquidityArgs, StfMode, SwapArgs, ZoneData};
+use cl::crust::{
+    tx::LedgerUpdate, Balance, BundleWitness, BurnWitness, InputWitness, MintWitness, Nonce,
+    NoteCommitment, Nullifier, NullifierCommitment, NullifierSecret, OutputWitness, Tx, TxWitness,
+    Unit, UnitWitness,
+};
 use cl::ds::mmr::{MMRFolds, MMRProof, MMR};
 use cl::mantle::ledger::{Ledger, LedgerState, LedgerWitness};
 use cl::mantle::ZoneState;
-use ledger::stf::{risc0_stf, StfProof};
+use ledger::{
+    covenant::SupplyCovenantProof,
+    stf::{risc0_stf, StfProof},
+};
+use ledger_proof_statements::covenant::SupplyCovenantPublic;
 use methods::{STF_ELF, STF_ID};
-use risc0_zkvm::{ExecutorEnv, Prover, Result};
+use rand::RngCore;
+use risc0_zkvm::{default_executor, sha::Digest, ExecutorEnv, Prover, Result, SessionInfo};
 
 #[derive(Debug)]
 struct FundNote {
@@ -16,24 +26,40 @@ struct FundNote {
     path: MMRProof,
 }
 
-impl FundNote {
-    fn evolve(&self, new_amount: u64) -> InputWitness {
-        let mut new_note = self.note.clone();
-        new_note.value = new_amount;
-        new_note.nonce = new_note.evolved_nonce(b"SWAP");
-        new_note
-    }
-}
-
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ExecutorState {
     pub ledger: LedgerState,
     pub swapvm: ZoneData,
     fund_notes: BTreeMap<Unit, FundNote>,
+    /// Witnesses of the units held by the pools, to spend their fund notes
+    units: BTreeMap<Unit, UnitWitness>,
     goal_notes: Vec<(InputWitness, MMR, MMRProof)>,
+    /// Pool shares minted and burned by the executor tx
+    mints: Vec<MintWitness>,
+    burns: Vec<BurnWitness>,
+    /// Receives the fees paid by the txs of the bundles built by this executor
+    pub fee_pk: NullifierCommitment,
+    /// Fee notes claimed so far
+    pub fee_notes: Vec<OutputWitness>,
+    fees: Balance,
 }
 
 impl ExecutorState {
+    pub fn new(fee_pk: NullifierCommitment) -> Self {
+        Self {
+            ledger: LedgerState::default(),
+            swapvm: ZoneData::new(Digest::from(STF_ID).into()),
+            fund_notes: BTreeMap::new(),
+            units: BTreeMap::new(),
+            goal_notes: Vec::new(),
+            mints: Vec::new(),
+            burns: Vec::new(),
+            fee_pk,
+            fee_notes: Vec::new(),
+            fees: Balance::zero(),
+        }
+    }
+
     pub fn zone_state(&self) -> ZoneState {
         ZoneState {
             stf: risc0_stf(STF_ID),
@@ -65,10 +91,33 @@ impl ExecutorState {
         self.ledger.add_nullifiers(nfs);
     }
 
-    pub fn process_tx(&mut self, tx: &Tx) {
-        let Some(swapvm_update) = tx.updates.get(&self.swapvm.zone_id) else {
+    /// Pick the txs paying the highest fees in `fee_unit` for the next bundle,
+    /// txs paying the same fee keep their order.
+    pub fn select_by_fee(mut txs: Vec<Tx>, fee_unit: Unit, max_txs: usize) -> Vec<Tx> {
+        txs.sort_by_key(|tx| std::cmp::Reverse(tx.fee_in(fee_unit)));
+        txs.truncate(max_txs);
+        txs
+    }
+
+    /// Take `tx` in the bundle being built, txs the stf would reject are
+    /// refused before they touch the executor state.
+    pub fn process_tx(&mut self, tx: &Tx) -> std::result::Result<(), TxError> {
+        let swapvm_update = tx.updates.get(&self.swapvm.zone_id);
+        // the zone takes the goal first, a tx it rejects leaves the executor
+        // state untouched
+        let goal_note = match swapvm_update {
+            Some(swapvm_update) => self.apply_goal(tx, swapvm_update)?,
+            None => None,
+        };
+
+        // the executor builds the bundle, so it collects the fees of every tx
+        if let Some(fee) = tx.fee {
+            self.fees.insert_positive(fee.unit, fee.amount);
+        }
+
+        let Some(swapvm_update) = swapvm_update else {
             // this tx is not related to the swapvm zone
-            return;
+            return Ok(());
         };
 
         let mut output_mmr_proofs = BTreeMap::<NoteCommitment, MMRProof>::new();
@@ -90,66 +139,138 @@ impl ExecutorState {
             }
         }
 
-        if tx.balance.unit_balance(swap_goal_unit().unit()).is_neg() {
-            // this is a SWAP
-            let (swap_goal_cm, swap_args_bytes) = &swapvm_update.outputs[0];
-            let swap_args: SwapArgs = cl::deserialize(&swap_args_bytes);
-
-            // verify the user proved the correct swap goal note
-            let swap_goal_witness = app::swap_goal_note(swap_args.nonce);
-            assert_eq!(swap_goal_cm, &swap_goal_witness.note_commitment());
+        if let Some(goal_note) = goal_note {
+            self.push_goal_note(goal_note, &output_mmr_proofs);
+        }
+        Ok(())
+    }
 
-            self.goal_notes.push((
-                swap_goal_witness,
-                self.ledger.commitments.clone(),
-                output_mmr_proofs[swap_goal_cm].clone(),
-            ));
+    /// Apply the goal of `tx` to the zone, returning the goal note for the
+    /// executor tx to spend.
+    fn apply_goal(
+        &mut self,
+        tx: &Tx,
+        swapvm_update: &LedgerUpdate,
+    ) -> std::result::Result<Option<InputWitness>, TxError> {
+        if SwapArgs::is_requested_by(tx) {
+            let swap_args = SwapArgs::read(swapvm_update);
+            let goal_note = SwapArgs::note(swap_args.nonce);
 
             // assume there are only the goal unit and tokenIn units at play
-            assert_eq!(tx.balance.balances.len(), 2);
-
-            let balance_in = tx
-                .balance
-                .balances
-                .iter()
-                .find(|bal| bal.unit != swap_goal_unit().unit())
-                .unwrap();
-
-            let token_in = balance_in.unit;
-            assert_eq!(balance_in.neg, 0);
-            assert!(balance_in.pos > 0);
-
-            let amount_in = balance_in.pos;
-            self.swapvm.swap(token_in, amount_in, swap_args);
+            let [(token_in, amount_in)] = app::provided(tx, SwapArgs::unit().unit())[..] else {
+                panic!("a swap provides a single token");
+            };
+            self.swapvm.swap(token_in, amount_in, swap_args)?;
+            Ok(Some(goal_note))
+        } else if AddLiquidityArgs::is_requested_by(tx) {
+            let args = AddLiquidityArgs::read(swapvm_update);
+            let goal_note = AddLiquidityArgs::note(args.nonce);
+            let units = args.pair;
+
+            let [deposit_a, deposit_b] = app::provided(tx, AddLiquidityArgs::unit().unit())[..]
+            else {
+                panic!("liquidity is added in both tokens of a pool");
+            };
+            let nonce = args.nonce;
+            let shares = self.swapvm.add_liquidity([deposit_a, deposit_b], args)?;
+            for unit in units {
+                self.units.insert(unit.unit(), unit);
+            }
+            self.mints.push(MintWitness {
+                amount: shares,
+                unit: app::get_pair_share_unit(
+                    Pair::new(deposit_a.0, deposit_b.0),
+                    self.swapvm.shares_covenant,
+                ),
+                salt: shares_salt(&nonce),
+            });
+            Ok(Some(goal_note))
+        } else if RemoveLiquidityArgs::is_requested_by(tx) {
+            let args = RemoveLiquidityArgs::read(swapvm_update);
+            let goal_note = RemoveLiquidityArgs::note(args.nonce);
+
+            let [(shares_unit, shares)] = app::provided(tx, RemoveLiquidityArgs::unit().unit())[..]
+            else {
+                panic!("liquidity is removed with the shares of a single pool");
+            };
+            let pair = Pair::new(args.outputs[0].unit, args.outputs[1].unit);
+            let nonce = args.nonce;
+            self.swapvm.remove_liquidity(shares_unit, shares, args)?;
+            self.burns.push(BurnWitness {
+                amount: shares,
+                unit: app::get_pair_share_unit(pair, self.swapvm.shares_covenant),
+                salt: shares_salt(&nonce),
+            });
+            Ok(Some(goal_note))
+        } else {
+            Ok(None)
         }
     }
 
+    /// Queue the goal note for the executor tx to spend
+    fn push_goal_note(
+        &mut self,
+        goal_note: InputWitness,
+        output_mmr_proofs: &BTreeMap<NoteCommitment, MMRProof>,
+    ) {
+        let proof = output_mmr_proofs[&goal_note.note_commitment()].clone();
+        self.goal_notes
+            .push((goal_note, self.ledger.commitments.clone(), proof));
+    }
+
     pub fn update_and_get_executor_tx(&mut self) -> (TxWitness, Vec<InputWitness>) {
-        let mut tx = TxWitness::default();
+        let mut tx = TxWitness {
+            data: app::EXECUTOR_TX_DATA.to_vec(),
+            ..Default::default()
+        };
         let mut new_fund_notes = Vec::new();
 
         let expected_pool_balances = self.swapvm.expected_pool_balances();
         let fund_notes = std::mem::take(&mut self.fund_notes);
 
-        for note in &self.swapvm.swaps_output {
-            tx = tx.add_output(note.clone(), "");
+        tx.mints = std::mem::take(&mut self.mints);
+        tx.burns = std::mem::take(&mut self.burns);
+        self.swapvm.mints.clear();
+        self.swapvm.burns.clear();
+        for note in std::mem::take(&mut self.swapvm.outputs) {
+            tx = tx.add_output(note, "");
             self.ledger.add_commitment(&note.note_commitment());
         }
 
-        self.swapvm.nfs.clear();
+        let mut nfs = BTreeMap::new();
         for (unit, value) in expected_pool_balances {
-            let note = if let Some(note) = fund_notes.get(&unit) {
-                note.evolve(value)
-            } else {
-                panic!("dynamically created fund notes are not supported");
-            };
+            // units without a fund note yet get their first one
+            let note = self.swapvm.fund_note(self.units[&unit], value);
             new_fund_notes.push(note);
             let output = note.to_output();
             tx = tx.add_output(output, "");
             let (mmr, path) = self.ledger.add_commitment(&output.note_commitment());
-            self.fund_notes
-                .insert(note.unit_witness.unit(), FundNote { note, mmr, path });
-            self.swapvm.nfs.insert(note.nullifier());
+            self.fund_notes.insert(unit, FundNote { note, mmr, path });
+            nfs.insert(unit, note.nullifier());
+        }
+        self.swapvm.nfs = nfs;
+
+        for fee in std::mem::take(&mut self.fees).balances {
+            // the commitment root changes with every note, making the nonce unique
+            let nonce = cl::hash(
+                &[
+                    b"SWAP_FEE".as_slice(),
+                    &fee.unit,
+                    &self.ledger.commitments.frontier_root(),
+                ]
+                .concat(),
+            );
+            let output = OutputWitness {
+                state: [0; 32],
+                value: fee.pos,
+                unit: fee.unit,
+                nonce: Nonce::from_bytes(nonce),
+                zone_id: self.swapvm.zone_id,
+                nf_pk: self.fee_pk,
+            };
+            tx = tx.add_output(output, "");
+            self.ledger.add_commitment(&output.note_commitment());
+            self.fee_notes.push(output);
         }
 
         for (_, FundNote { note, mmr, path }) in fund_notes.into_iter() {
@@ -162,35 +283,123 @@ impl ExecutorState {
         (tx, new_fund_notes)
     }
 
+    /// Hand the executor a note funding the pools, the stf expects the next
+    /// executor tx to spend it.
     pub fn set_fund_note(&mut self, note: InputWitness, mmr: MMR, path: MMRProof) {
-        self.fund_notes
-            .insert(note.unit_witness.unit(), FundNote { note, mmr, path });
+        let unit = note.unit_witness.unit();
+        self.units.insert(unit, note.unit_witness);
+        self.swapvm.nfs.insert(unit, note.nullifier());
+        self.fund_notes.insert(unit, FundNote { note, mmr, path });
+    }
+
+    /// Seed the pool of the units of `funds` along with the fund notes holding
+    /// them. The executor funds back the initial liquidity, so the initial
+    /// shares are not held by anyone.
+    pub fn fund_pool(&mut self, funds: [(UnitWitness, u64); 2], mut rng: impl RngCore) {
+        for (unit, value) in funds {
+            let fund = InputWitness {
+                state: [0u8; 32],
+                value,
+                unit_witness: unit,
+                nonce: Nonce::random(&mut rng),
+                zone_id: self.swapvm.zone_id,
+                nf_sk: NullifierSecret::zero(),
+            };
+            let ((mmr, mmr_proof), _) = self.observe_cm(&fund.note_commitment());
+            self.set_fund_note(fund, mmr, mmr_proof);
+        }
+        let [(t0, t0_in), (t1, t1_in)] = funds;
+        self.swapvm.seed_pool(t0.unit(), t1.unit(), t0_in, t1_in);
     }
 }
 
+/// Salt of the pool shares minted or burned for the goal with `nonce`
+fn shares_salt(nonce: &Nonce) -> [u8; 16] {
+    let hash = cl::hash(&[b"SWAPVM_SHARES".as_slice(), nonce.as_bytes()].concat());
+    hash[..16].try_into().unwrap()
+}
+
+/// Prove the mints and burns of pool shares by the executor tx `tx` to the
+/// shares covenant, for the tx proof.
+pub fn prove_shares_covenant(
+    prover: &dyn Prover,
+    tx: &TxWitness,
+) -> Result<Vec<SupplyCovenantProof>> {
+    let tx_root = tx
+        .commit(
+            &tx.mint_amounts(),
+            &tx.burn_amounts(),
+            &tx.inputs_derived_fields(),
+        )
+        .root;
+    let covenant: [u8; 32] = Digest::from(STF_ID).into();
+    let mints = tx
+        .mints
+        .iter()
+        .filter(|m| m.unit.minting_covenant == covenant)
+        .map(|m| (m.unit.unit(), m.amount));
+    let burns = tx
+        .burns
+        .iter()
+        .filter(|b| b.unit.burning_covenant == covenant)
+        .map(|b| (b.unit.unit(), b.amount));
+
+    mints
+        .chain(burns)
+        .map(|(unit, amount)| {
+            let public = SupplyCovenantPublic {
+                amount,
+                unit,
+                tx_root,
+            };
+            let env = ExecutorEnv::builder()
+                .write(&StfMode::SharesCovenant)?
+                .write(&public)?
+                .write(tx)?
+                .build()?;
+            let receipt = prover.prove(env, STF_ELF)?.receipt;
+            Ok(SupplyCovenantProof::from_risc0(STF_ID, receipt))
+        })
+        .collect()
+}
+
 pub struct StfPrivate {
     pub zone_data: ZoneData,
     pub old_ledger: LedgerWitness,
     pub new_ledger: Ledger,
     pub fund_notes: Vec<InputWitness>,
     pub bundle: BundleWitness,
+    /// Witness of the executor tx, the last tx of the bundle
+    pub executor_tx: TxWitness,
 }
 
 impl StfPrivate {
-    pub fn prove(&self, prover: &dyn Prover) -> Result<StfProof> {
-        let env = ExecutorEnv::builder()
+    fn env(&self) -> Result<ExecutorEnv<'static>> {
+        ExecutorEnv::builder()
+            .write(&StfMode::Transition)?
             .write(&self.zone_data)?
             .write(&self.old_ledger)?
             .write(&self.new_ledger)?
-            .write(&unsafe { std::intrinsics::transmute::<_, [u8; 32]>(STF_ID) })?
+            .write(&risc0_stf(STF_ID))?
             .write(&self.bundle)?
+            .write(&self.executor_tx)?
             .write(&self.fund_notes)?
-            .build()?;
+            .build()
+    }
 
-        let prove_info = prover.prove(env, STF_ELF)?;
+    pub fn prove(&self, prover: &dyn Prover) -> Result<StfProof> {
+        let prove_info = prover.prove(self.env()?, STF_ELF)?;
 
         debug_assert!(prove_info.receipt.verify(STF_ID).is_ok());
 
-        Ok(StfProof::from_risc0(risc0_stf(STF_ID), prove_info.receipt))
+        Ok(StfProof::from_risc0(
+            Digest::from(STF_ID).into(),
+            prove_info.receipt,
+        ))
+    }
+
+    /// Run the stf guest without proving it.
+    pub fn execute(&self) -> Result<SessionInfo> {
+        default_executor().execute(self.env()?, STF_ELF)
     }
 }
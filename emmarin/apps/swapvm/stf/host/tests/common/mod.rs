@@ -0,0 +1,48 @@
+//! Fixtures shared by the executor tests.
+#![allow(dead_code)]
+
+use app::{SwapArgs, SwapOutput, ZONE_ID};
+use cl::crust::{InputWitness, Nonce, NullifierSecret, TxWitness, Unit};
+use host::ExecutorState;
+use rand::RngCore;
+
+pub use cl::test_utils::{mem, nmo};
+
+/// An executor funding a NMO/MEM pool with `funds`.
+pub fn setup_executor(mut rng: impl RngCore, funds: [u64; 2]) -> ExecutorState {
+    let mut exec_state = ExecutorState::new(NullifierSecret::random(&mut rng).commit());
+    exec_state.fund_pool([(nmo(), funds[0]), (mem(), funds[1])], rng);
+    exec_state
+}
+
+/// A swap of `value` NMO into `unit` through `route`, for at least `limit`.
+pub fn swap_tx(
+    exec_state: &mut ExecutorState,
+    value: u64,
+    unit: Unit,
+    route: Vec<Unit>,
+    limit: u64,
+    mut rng: impl RngCore,
+) -> TxWitness {
+    let sk = NullifierSecret::random(&mut rng);
+    let note = InputWitness {
+        state: [0u8; 32],
+        value,
+        unit_witness: nmo(),
+        nonce: Nonce::random(&mut rng),
+        zone_id: ZONE_ID,
+        nf_sk: sk,
+    };
+    let (proof, _) = exec_state.observe_cm(&note.note_commitment());
+
+    let nonce = Nonce::random(&mut rng);
+    TxWitness::default().add_input(note, proof).add_output(
+        app::swap_goal_note(nonce).to_output(),
+        SwapArgs {
+            output: SwapOutput::basic(unit, ZONE_ID, sk.commit(), &mut rng),
+            route,
+            limit,
+            nonce,
+        },
+    )
+}
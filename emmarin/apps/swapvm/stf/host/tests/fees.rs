@@ -0,0 +1,61 @@
+use cl::crust::{tx::Fee, BundleWitness, Tx, TxWitness};
+use cl::test_utils::commit;
+use host::ExecutorState;
+use rand::RngCore;
+
+mod common;
+use common::{mem, nmo};
+
+/// A swap of `value` NMO into MEM paying `fee` NMO to the executor.
+fn swap_tx(exec_state: &mut ExecutorState, value: u64, fee: u64, rng: impl RngCore) -> Tx {
+    let tx = common::swap_tx(exec_state, value, mem().unit(), vec![], 1, rng).with_fee(Fee {
+        unit: nmo().unit(),
+        amount: fee,
+    });
+    commit(&tx)
+}
+
+#[test]
+fn executor_claims_swap_fees() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, [1348, 14102]);
+    let fee_pk = exec_state.fee_pk;
+
+    let swap = swap_tx(&mut exec_state, 10, 2, &mut rng);
+    // only the amount net of the fee is swapped
+    assert_eq!(swap.balance.unit_balance(nmo().unit()).pos, 8);
+    exec_state.process_tx(&swap).unwrap();
+
+    let (exec_tx, _fund_notes) = exec_state.update_and_get_executor_tx();
+    let exec_tx = commit(&exec_tx);
+
+    let bundle = BundleWitness::new(vec![swap, exec_tx])
+        .with_fee_outputs(exec_state.fee_notes.clone())
+        .commit();
+    assert_eq!(bundle.fees.unit_balance(nmo().unit()).pos, 2);
+    assert_eq!(bundle.fee_recipient, Some(fee_pk));
+
+    assert_eq!(exec_state.fee_notes.len(), 1);
+    assert_eq!(exec_state.fee_notes[0].value, 2);
+    assert_eq!(exec_state.fee_notes[0].nf_pk, fee_pk);
+}
+
+#[test]
+fn executor_selects_txs_by_fee() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, [1348, 14102]);
+
+    let txs: Vec<Tx> = [1, 3, 0, 3]
+        .into_iter()
+        .map(|fee| match fee {
+            0 => commit(&TxWitness::default()),
+            fee => swap_tx(&mut exec_state, 10, fee, &mut rng),
+        })
+        .collect();
+
+    let selected = ExecutorState::select_by_fee(txs.clone(), nmo().unit(), 3);
+    assert_eq!(
+        selected,
+        vec![txs[1].clone(), txs[3].clone(), txs[0].clone()]
+    );
+}
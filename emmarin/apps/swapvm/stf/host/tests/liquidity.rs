@@ -0,0 +1,271 @@
+use app::{AddLiquidityArgs, Goal, Pair, RemoveLiquidityArgs, SwapOutput, ZONE_ID};
+use cl::crust::{BundleWitness, InputWitness, Nonce, NullifierSecret, Tx, TxWitness, UnitWitness};
+use cl::ds::mmr::{MMRProof, MMR};
+use cl::test_utils::commit;
+use host::{ExecutorState, StfPrivate};
+use ledger::{prover::ProverContext, tx::ProvedTx};
+use ledger_proof_statements::stf::StfPublic;
+use rand::RngCore;
+use risc0_zkvm::sha::Digest;
+
+mod common;
+use common::{mem, nmo};
+
+fn shares() -> UnitWitness {
+    // the shares are minted and burned under the swapvm stf
+    app::get_pair_share_unit(
+        Pair::new(nmo().unit(), mem().unit()),
+        Digest::from(methods::STF_ID).into(),
+    )
+}
+
+/// Funds of the NMO/MEM pool, for 100 initial shares
+const FUNDS: [u64; 2] = [10, 1000];
+
+/// Notes of the LP, known to the executor
+fn lp_notes<const N: usize>(
+    exec_state: &mut ExecutorState,
+    sk: NullifierSecret,
+    notes: [(UnitWitness, u64); N],
+    mut rng: impl RngCore,
+) -> [(InputWitness, (MMR, MMRProof)); N] {
+    let mut observed: Vec<(InputWitness, (MMR, MMRProof))> = Vec::new();
+    for (unit, value) in notes {
+        let note = InputWitness {
+            state: [0u8; 32],
+            value,
+            unit_witness: unit,
+            nonce: Nonce::random(&mut rng),
+            zone_id: ZONE_ID,
+            nf_sk: sk,
+        };
+        let (proof, folds) = exec_state.observe_cm(&note.note_commitment());
+        for (other, (mmr, path)) in observed.iter_mut() {
+            path.update(&other.note_commitment().0, &folds);
+            *mmr = proof.0.clone();
+        }
+        observed.push((note, proof));
+    }
+    observed.try_into().unwrap()
+}
+
+/// An LP adding 5 NMO and 500 MEM to the pool for at least 50 shares
+fn add_liquidity_tx(
+    exec_state: &mut ExecutorState,
+    mut rng: impl RngCore,
+) -> (TxWitness, SwapOutput) {
+    let sk = NullifierSecret::random(&mut rng);
+    let [(nmo_in, nmo_proof), (mem_in, mem_proof)] =
+        lp_notes(exec_state, sk, [(nmo(), 5), (mem(), 500)], &mut rng);
+
+    let nonce = Nonce::random(&mut rng);
+    let output = SwapOutput::basic(shares().unit(), ZONE_ID, sk.commit(), &mut rng);
+    let tx = TxWitness::default()
+        .add_input(nmo_in, nmo_proof)
+        .add_input(mem_in, mem_proof)
+        .add_output(
+            AddLiquidityArgs::note(nonce).to_output(),
+            AddLiquidityArgs {
+                pair: [nmo(), mem()],
+                output,
+                min_shares: 50,
+                nonce,
+            },
+        );
+    (tx, output)
+}
+
+/// Bundle the LP tx with the executor tx solving it
+fn execute(exec_state: &mut ExecutorState, tx: TxWitness) -> Tx {
+    let tx = commit(&tx);
+    exec_state.process_tx(&tx).unwrap();
+    // the zone data as the stf sees it once it went through the LP tx
+    let zone_data = exec_state.swapvm.clone();
+    let (exec_witness, fund_notes) = exec_state.update_and_get_executor_tx();
+    let exec_tx = commit(&exec_witness);
+    // the stf accepts the fund notes and shares of the executor tx
+    assert_eq!(
+        zone_data.update_and_commit(&exec_tx, &exec_witness, &fund_notes),
+        exec_state.swapvm.commit()
+    );
+    // panics if the bundle is not balanced
+    BundleWitness::new(vec![tx, exec_tx.clone()]).commit();
+    exec_tx
+}
+
+fn received(exec_tx: &Tx, note: &SwapOutput, value: u64) -> bool {
+    exec_tx.updates[&ZONE_ID].has_output(&note.to_output(value).note_commitment())
+}
+
+fn pool_balances(exec_state: &ExecutorState) -> (u64, u64, u64) {
+    let balances = exec_state.swapvm.expected_pool_balances();
+    let pool = &exec_state.swapvm.pools[&Pair::new(nmo().unit(), mem().unit())];
+    (
+        balances[&nmo().unit()],
+        balances[&mem().unit()],
+        pool.total_shares,
+    )
+}
+
+#[test]
+fn executor_mints_shares_for_added_liquidity() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, FUNDS);
+    let (tx, output) = add_liquidity_tx(&mut exec_state, &mut rng);
+
+    // the minted shares are paid to the LP
+    let exec_tx = execute(&mut exec_state, tx);
+    assert!(received(&exec_tx, &output, 50));
+    assert_eq!(pool_balances(&exec_state), (15, 1500, 150));
+}
+
+#[test]
+#[should_panic(expected = "executor tx mints other shares")]
+fn stf_rejects_executor_tx_minting_other_shares() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, FUNDS);
+    let (tx, _) = add_liquidity_tx(&mut exec_state, &mut rng);
+
+    exec_state.process_tx(&commit(&tx)).unwrap();
+    let zone_data = exec_state.swapvm.clone();
+    let (mut exec_witness, fund_notes) = exec_state.update_and_get_executor_tx();
+    // the executor mints itself a share on top of those owed to the LP
+    exec_witness.mints[0].amount += 1;
+    zone_data.update_and_commit(&commit(&exec_witness), &exec_witness, &fund_notes);
+}
+
+/// The executor tx minting the shares owed for an added liquidity
+fn minting_executor_tx(mut rng: impl RngCore) -> TxWitness {
+    let mut exec_state = common::setup_executor(&mut rng, FUNDS);
+    let (tx, _) = add_liquidity_tx(&mut exec_state, &mut rng);
+    exec_state.process_tx(&commit(&tx)).unwrap();
+    exec_state.update_and_get_executor_tx().0
+}
+
+#[test]
+fn executor_tx_mints_shares_under_the_covenant() {
+    let ctx = ProverContext::default();
+    let exec_tx = minting_executor_tx(rand::thread_rng());
+
+    let covenant_proofs = host::prove_shares_covenant(ctx.prover().as_ref(), &exec_tx).unwrap();
+    assert_eq!(covenant_proofs.len(), 1);
+
+    let proved_tx = ProvedTx::prove(&ctx, exec_tx.clone(), covenant_proofs, vec![]).unwrap();
+    assert!(proved_tx.verify());
+    assert_eq!(proved_tx.public(), commit(&exec_tx));
+}
+
+#[test]
+fn shares_covenant_rejects_other_mints() {
+    let ctx = ProverContext::default();
+    let exec_tx = minting_executor_tx(rand::thread_rng());
+
+    // only the executor tx may mint shares
+    let mut other_tx = exec_tx.clone();
+    other_tx.data = Vec::new();
+    assert!(host::prove_shares_covenant(ctx.prover().as_ref(), &other_tx).is_err());
+
+    // the covenant proofs authorize the amount of the tx they were proven for
+    let covenant_proofs = host::prove_shares_covenant(ctx.prover().as_ref(), &exec_tx).unwrap();
+    let mut inflated_tx = exec_tx;
+    inflated_tx.mints[0].amount += 1;
+    assert!(ProvedTx::prove(&ctx, inflated_tx, covenant_proofs, vec![]).is_err());
+}
+
+#[test]
+fn stf_executes_liquidity_bundle() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, FUNDS);
+    let (tx, _) = add_liquidity_tx(&mut exec_state, &mut rng);
+
+    let old_zone_state = exec_state.zone_state();
+    let old_zone_data = exec_state.swapvm.clone();
+    let old_ledger = exec_state.ledger.to_witness();
+
+    let tx = commit(&tx);
+    exec_state.process_tx(&tx).unwrap();
+    let (exec_witness, fund_notes) = exec_state.update_and_get_executor_tx();
+    let bundle = BundleWitness::new(vec![tx, commit(&exec_witness)]);
+    exec_state.ledger.add_bundle(bundle.root());
+    exec_state.observe_nfs(
+        bundle.clone().commit().updates[&ZONE_ID]
+            .iter()
+            .flat_map(|u| u.inputs.iter().copied())
+            .collect(),
+    );
+
+    let session = StfPrivate {
+        zone_data: old_zone_data,
+        old_ledger,
+        new_ledger: exec_state.ledger.to_witness().commit(),
+        fund_notes,
+        bundle,
+        executor_tx: exec_witness,
+    }
+    .execute()
+    .unwrap();
+
+    let public: StfPublic = session.journal.decode().unwrap();
+    assert_eq!(public.old, old_zone_state);
+    assert_eq!(public.new, exec_state.zone_state());
+}
+
+#[test]
+fn executor_burns_shares_for_removed_liquidity() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, FUNDS);
+
+    let sk = NullifierSecret::random(&mut rng);
+    let [(shares_in, shares_proof)] = lp_notes(&mut exec_state, sk, [(shares(), 20)], &mut rng);
+
+    let nonce = Nonce::random(&mut rng);
+    let outputs =
+        [nmo(), mem()].map(|unit| SwapOutput::basic(unit.unit(), ZONE_ID, sk.commit(), &mut rng));
+    let tx = TxWitness::default()
+        .add_input(shares_in, shares_proof)
+        .add_output(
+            RemoveLiquidityArgs::note(nonce).to_output(),
+            RemoveLiquidityArgs {
+                outputs,
+                limits: [2, 200],
+                nonce,
+            },
+        );
+
+    // the shares are burned by the executor
+    let exec_tx = execute(&mut exec_state, tx);
+    assert!(received(&exec_tx, &outputs[0], 2));
+    assert!(received(&exec_tx, &outputs[1], 200));
+    assert_eq!(pool_balances(&exec_state), (8, 800, 80));
+}
+
+#[test]
+fn first_liquidity_creates_the_pool() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = ExecutorState::new(NullifierSecret::random(&mut rng).commit());
+
+    let sk = NullifierSecret::random(&mut rng);
+    let [(nmo_in, nmo_proof), (mem_in, mem_proof)] =
+        lp_notes(&mut exec_state, sk, [(nmo(), 4), (mem(), 900)], &mut rng);
+
+    let nonce = Nonce::random(&mut rng);
+    let output = SwapOutput::basic(shares().unit(), ZONE_ID, sk.commit(), &mut rng);
+    let tx = TxWitness::default()
+        .add_input(nmo_in, nmo_proof)
+        .add_input(mem_in, mem_proof)
+        .add_output(
+            AddLiquidityArgs::note(nonce).to_output(),
+            AddLiquidityArgs {
+                pair: [mem(), nmo()],
+                output,
+                min_shares: 60,
+                nonce,
+            },
+        );
+
+    // the executor creates the first fund notes of both units
+    let exec_tx = execute(&mut exec_state, tx);
+    assert!(received(&exec_tx, &output, 60));
+    assert_eq!(pool_balances(&exec_state), (4, 900, 60));
+    assert_eq!(exec_state.swapvm.nfs.len(), 2);
+}
@@ -0,0 +1,53 @@
+use app::Pair;
+use cl::crust::{Tx, Unit, UnitWitness};
+use cl::test_utils::commit;
+use host::{ExecutorState, TxError};
+use rand::RngCore;
+
+mod common;
+use common::mem;
+
+fn eth() -> Unit {
+    UnitWitness::nop(b"ETH").unit()
+}
+
+/// A swap of 10 NMO into `unit` through `route`, for at least `limit`.
+fn swap_tx(
+    exec_state: &mut ExecutorState,
+    unit: Unit,
+    route: Vec<Unit>,
+    limit: u64,
+    rng: impl RngCore,
+) -> Tx {
+    commit(&common::swap_tx(exec_state, 10, unit, route, limit, rng))
+}
+
+#[test]
+fn executor_rejects_swaps_through_missing_pools() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, [1000, 10000]);
+
+    let tx = swap_tx(&mut exec_state, eth(), vec![mem().unit()], 1, &mut rng);
+    let zone_state = exec_state.zone_state();
+    assert_eq!(
+        exec_state.process_tx(&tx),
+        Err(TxError::NoPool(Pair::new(mem().unit(), eth())))
+    );
+    // the rejected tx left the executor untouched
+    assert_eq!(exec_state.zone_state(), zone_state);
+}
+
+#[test]
+fn executor_rejects_swaps_below_their_limit() {
+    let mut rng = rand::thread_rng();
+    let mut exec_state = common::setup_executor(&mut rng, [1000, 10000]);
+
+    let tx = swap_tx(&mut exec_state, mem().unit(), vec![], 1000, &mut rng);
+    assert!(matches!(
+        exec_state.process_tx(&tx),
+        Err(TxError::BelowLimit { limit: 1000, .. })
+    ));
+
+    let tx = swap_tx(&mut exec_state, mem().unit(), vec![], 90, &mut rng);
+    exec_state.process_tx(&tx).unwrap();
+}
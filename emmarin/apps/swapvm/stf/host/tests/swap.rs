@@ -1,60 +1,19 @@
 use app::ZONE_ID;
-use cl::crust::{BundleWitness, InputWitness, Nonce, NullifierSecret, TxWitness, UnitWitness};
-use cl::mantle::ledger::LedgerState;
+use cl::crust::{BundleWitness, InputWitness, Nonce, NullifierSecret, TxWitness};
 use cl::mantle::update::{BatchUpdate, Update};
-use host::{ExecutorState, StfPrivate};
+use host::StfPrivate;
 use ledger::ledger::ProvedLedgerTransition;
 use ledger::update::ProvedBatchUpdate;
-use ledger::{bundle::ProvedBundle, tx::ProvedTx};
-use rand::RngCore;
+use ledger::{bundle::ProvedBundle, prover::ProverContext, tx::ProvedTx};
+use std::collections::BTreeMap;
 
-fn nmo() -> UnitWitness {
-    UnitWitness::nop(b"NMO")
-}
-fn mem() -> UnitWitness {
-    UnitWitness::nop(b"MEM")
-}
-
-fn setup_executor(mut rng: impl RngCore, ledger: LedgerState) -> ExecutorState {
-    let mut exec_state = ExecutorState::default();
-    exec_state.ledger = ledger;
-
-    let nmo_fund = InputWitness {
-        state: [0u8; 32],
-        value: 1348,
-        unit_witness: nmo(),
-        nonce: Nonce::random(&mut rng),
-        zone_id: ZONE_ID,
-        nf_sk: NullifierSecret::zero(),
-    };
-
-    let ((mmr, mmr_proof), _) = exec_state.observe_cm(&nmo_fund.note_commitment());
-    exec_state.set_fund_note(nmo_fund, mmr, mmr_proof);
-
-    let mem_fund = InputWitness {
-        state: [0u8; 32],
-        value: 14102,
-        unit_witness: mem(),
-        nonce: Nonce::random(&mut rng),
-        zone_id: ZONE_ID,
-        nf_sk: NullifierSecret::zero(),
-    };
-    let ((mmr, mmr_proof), _) = exec_state.observe_cm(&mem_fund.note_commitment());
-    exec_state.set_fund_note(mem_fund, mmr, mmr_proof);
-
-    // HACK: we don't currently support liquidity notes, we directly hard code the corresponding liquidity
-    // in the swapvm instead of minting pool LP tokens
-    exec_state
-        .swapvm
-        .add_liquidity(nmo().unit(), mem().unit(), nmo_fund.value, mem_fund.value);
-
-    exec_state
-}
+mod common;
+use common::{mem, nmo, setup_executor};
 
 #[test]
 fn simple_swap() {
     let mut rng = rand::thread_rng();
-    let ledger = LedgerState::default();
+    let ctx = ProverContext::default();
 
     // ---- setup scenario ----
 
@@ -69,7 +28,7 @@ fn simple_swap() {
         nf_sk: alice_sk,
     };
 
-    let mut exec_state = setup_executor(&mut rng, ledger);
+    let mut exec_state = setup_executor(&mut rng, [1348, 14102]);
     let (alice_in_proof, _) = exec_state.observe_cm(&alice_in.note_commitment());
 
     // ----- end setup ----
@@ -88,28 +47,28 @@ fn simple_swap() {
             app::swap_goal_note(swap_goal_nonce).to_output(),
             app::SwapArgs {
                 output: app::SwapOutput::basic(mem().unit(), ZONE_ID, alice_sk.commit(), &mut rng),
+                route: vec![],
                 limit: 90,
                 nonce: swap_goal_nonce,
             },
         );
 
-    let swap_tx_proof = ProvedTx::prove(swap_tx, vec![], vec![]).unwrap();
+    let swap_tx_proof = ProvedTx::prove(&ctx, swap_tx, vec![], vec![]).unwrap();
 
     //
     // alice ---- (swap_tx, swap_tx_proof) ---> executor
     //
     // alice sends the tx to an executor
-    exec_state.process_tx(&swap_tx_proof.public());
+    exec_state.process_tx(&swap_tx_proof.public()).unwrap();
 
     // the executor builds the solving tx
     let (exec_tx, fund_notes) = exec_state.update_and_get_executor_tx();
-    let proved_exec_tx = ProvedTx::prove(exec_tx, vec![], vec![]).unwrap();
+    let proved_exec_tx = ProvedTx::prove(&ctx, exec_tx.clone(), vec![], vec![]).unwrap();
 
-    let swap_bundle = BundleWitness {
-        txs: vec![swap_tx_proof.public(), proved_exec_tx.public()],
-    };
+    let swap_bundle = BundleWitness::new(vec![swap_tx_proof.public(), proved_exec_tx.public()]);
 
-    let swap_bundle_proof = ProvedBundle::prove(vec![swap_tx_proof, proved_exec_tx]);
+    let swap_bundle_proof =
+        ProvedBundle::prove(&ctx, vec![swap_tx_proof, proved_exec_tx], vec![]).unwrap();
     exec_state.ledger.add_bundle(swap_bundle.root());
     exec_state.observe_nfs(
         swap_bundle
@@ -129,12 +88,19 @@ fn simple_swap() {
         new_ledger: exec_state.ledger.clone().to_witness().commit(),
         fund_notes,
         bundle: swap_bundle,
+        executor_tx: exec_tx,
     }
-    .prove(risc0_zkvm::default_prover().as_ref())
+    .prove(ctx.prover().as_ref())
     .unwrap();
 
-    let ledger_proof =
-        ProvedLedgerTransition::prove(&mut temp_ledger_state, ZONE_ID, vec![swap_bundle_proof]);
+    let ledger_proof = ProvedLedgerTransition::prove(
+        &ctx,
+        &mut temp_ledger_state,
+        ZONE_ID,
+        vec![swap_bundle_proof],
+        &[],
+    )
+    .unwrap();
 
     let new_zone_state = exec_state.zone_state();
 
@@ -144,13 +110,14 @@ fn simple_swap() {
     let zone_update = ProvedBatchUpdate {
         batch: BatchUpdate {
             updates: vec![Update {
+                zone_id: ZONE_ID,
                 old: old_zone_state,
                 new: new_zone_state,
             }],
         },
-        ledger_proofs: vec![ledger_proof],
-        stf_proofs: vec![stf_proof],
+        ledger_proofs: BTreeMap::from([(ZONE_ID, ledger_proof)]),
+        stf_proofs: BTreeMap::from([(ZONE_ID, stf_proof)]),
     };
 
-    assert!(zone_update.verify())
+    zone_update.verify().unwrap();
 }
@@ -1,23 +1,36 @@
-use app::{SwapArgs, ZoneData};
+use app::{AddLiquidityArgs, Goal, RemoveLiquidityArgs, StfMode, SwapArgs, ZoneData};
 use cl::{
-    crust::{BundleWitness, InputWitness},
+    crust::{BundleWitness, InputWitness, TxWitness},
     mantle::{
         ledger::{Ledger, LedgerWitness},
-        zone::ZoneState,
+        zone::{ProofSystem, ZoneState},
     },
 };
-use ledger_proof_statements::stf::StfPublic;
+use ledger_proof_statements::{covenant::SupplyCovenantPublic, stf::StfPublic};
 use risc0_zkvm::guest::env;
 
 fn main() {
+    match env::read() {
+        StfMode::Transition => transition(),
+        StfMode::SharesCovenant => shares_covenant(),
+    }
+}
+
+fn transition() {
     let mut zone_data: ZoneData = env::read();
     let mut ledger_witness: LedgerWitness = env::read();
     let new_ledger: Ledger = env::read();
     let stf: [u8; 32] = env::read();
     let mut bundle: BundleWitness = env::read();
+    let executor_tx_witness: TxWitness = env::read();
     let pools_notes: Vec<InputWitness> = env::read();
 
     let zone_id = zone_data.zone_id;
+    // the pool shares are bound to this stf
+    assert_eq!(ProofSystem::Risc0.stf(&zone_data.shares_covenant), stf);
+    // what the executor tx owes is not committed, it starts out empty
+    assert!(zone_data.outputs.is_empty());
+    assert!(zone_data.mints.is_empty() && zone_data.burns.is_empty());
 
     let old_state = ZoneState {
         ledger: ledger_witness.commit(),
@@ -38,36 +51,40 @@ fn main() {
         };
 
         assert!(zone_data.validate_no_pools(zone_update));
+        // only the executor tx may mint or burn pool shares
+        assert!(!app::is_executor_tx(&tx));
 
-        // is it a SWAP?
-        if tx
-            .balance
-            .unit_balance(app::swap_goal_unit().unit())
-            .is_neg()
-        {
+        if SwapArgs::is_requested_by(&tx) {
             // This TX encodes a SWAP request.
-            // as a simplifying assumption, we will assume that the SWAP goal note is the only output
-            // and a single input represents the funds provided by the user for the swap.
-            assert_eq!(zone_update.outputs.len(), 1);
+            // as a simplifying assumption, a single input represents the funds provided by the
+            // user for the swap.
             assert_eq!(zone_update.inputs.len(), 1);
-            let (swap_goal_cm, swap_args_bytes) = &zone_update.outputs[0];
-            let swap_args: SwapArgs = cl::deserialize(&swap_args_bytes);
-
-            // ensure the witness corresponds to the swap goal cm
-            assert_eq!(
-                swap_goal_cm,
-                &app::swap_goal_note(swap_args.nonce).note_commitment()
-            );
-
-            let funds = tx
-                .balance
-                .balances
-                .iter()
-                .find(|bal| bal.unit != app::swap_goal_unit().unit())
-                .unwrap();
-            let t_in = funds.unit;
-            let amount_in = funds.pos - funds.neg;
-            zone_data.swap(t_in, amount_in, swap_args);
+            let swap_args = SwapArgs::read(zone_update);
+            let [(t_in, amount_in)] = app::provided(&tx, SwapArgs::unit().unit())[..] else {
+                panic!("a swap provides a single token");
+            };
+            zone_data
+                .swap(t_in, amount_in, swap_args)
+                .unwrap_or_else(|err| panic!("{err}"));
+        } else if AddLiquidityArgs::is_requested_by(&tx) {
+            let args = AddLiquidityArgs::read(zone_update);
+            let [deposit_a, deposit_b] = app::provided(&tx, AddLiquidityArgs::unit().unit())[..]
+            else {
+                panic!("liquidity is added in both tokens of a pool");
+            };
+            zone_data
+                .add_liquidity([deposit_a, deposit_b], args)
+                .unwrap_or_else(|err| panic!("{err}"));
+        } else if RemoveLiquidityArgs::is_requested_by(&tx) {
+            let args = RemoveLiquidityArgs::read(zone_update);
+            let [(shares_unit, shares)] =
+                app::provided(&tx, RemoveLiquidityArgs::unit().unit())[..]
+            else {
+                panic!("liquidity is removed with the shares of a single pool");
+            };
+            zone_data
+                .remove_liquidity(shares_unit, shares, args)
+                .unwrap_or_else(|err| panic!("{err}"));
         }
     }
 
@@ -75,10 +92,38 @@ fn main() {
         old: old_state,
         new: ZoneState {
             ledger: new_ledger,
-            zone_data: zone_data.update_and_commit(&executor_tx, &pools_notes),
+            zone_data: zone_data.update_and_commit(
+                &executor_tx,
+                &executor_tx_witness,
+                &pools_notes,
+            ),
             stf,
         },
     };
 
     env::commit(&public);
 }
+
+/// Authorize a mint or burn of pool shares by a tx marked as the executor tx.
+///
+/// The tx touches the swapvm zone, so its bundle goes through the stf, which
+/// rejects it unless it is the executor tx minting and burning exactly the
+/// shares owed.
+fn shares_covenant() {
+    let public: SupplyCovenantPublic = env::read();
+    let tx: TxWitness = env::read();
+
+    let mints = tx.mint_amounts();
+    let burns = tx.burn_amounts();
+    let tx_public = tx.commit(&mints, &burns, &tx.inputs_derived_fields());
+    assert_eq!(tx_public.root, public.tx_root);
+    assert!(app::is_executor_tx(&tx_public));
+
+    let supply = |unit, amount| unit == public.unit && amount == public.amount;
+    assert!(
+        mints.iter().any(|m| supply(m.unit, m.amount))
+            || burns.iter().any(|b| supply(b.unit, b.amount))
+    );
+
+    env::commit(&public);
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeSet;
+
+use cl::mantle::{update::BatchUpdate, ProofSystem};
+use hex::FromHex;
+use ledger_proof_statements::{
+    batch::{BatchProofPrivate, BatchZoneWitness},
+    ledger::check_sync_logs,
+    stf::StfPublic,
+};
+use risc0_zkvm::{guest::env, serde};
+
+fn main() {
+    let BatchProofPrivate { zones } = env::read();
+
+    let ledger_id = <[u8; 32]>::from_hex(risc0_images::LEDGER_ID).unwrap();
+
+    let mut seen_zones = BTreeSet::new();
+    let mut updates = Vec::with_capacity(zones.len());
+    let mut ledgers = Vec::with_capacity(zones.len());
+
+    for BatchZoneWitness {
+        update,
+        ledger,
+        stf_system,
+        stf_image,
+    } in zones
+    {
+        assert!(
+            seen_zones.insert(update.zone_id),
+            "zone appears more than once in the batch"
+        );
+
+        env::verify(ledger_id, &serde::to_vec(&ledger).unwrap()).unwrap();
+        assert_eq!(ledger.id, update.zone_id);
+        assert_eq!(ledger.old_ledger, update.old.ledger);
+        assert_eq!(ledger.ledger, update.new.ledger);
+
+        // the zone transition is governed by the stf of the old state
+        assert_eq!(stf_system.stf(&stf_image), update.old.stf);
+        // the stf proof is resolved as a risc0 assumption, groth16 seals are not
+        assert_eq!(
+            stf_system,
+            ProofSystem::Risc0,
+            "only risc0 stfs can be batched"
+        );
+        env::verify(
+            stf_image,
+            &serde::to_vec(&StfPublic {
+                old: update.old,
+                new: update.new,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+
+        updates.push(update);
+        ledgers.push(ledger);
+    }
+
+    check_sync_logs(&ledgers).expect("cross zone bundle not settled by all zones");
+
+    env::commit(&BatchUpdate { updates });
+}
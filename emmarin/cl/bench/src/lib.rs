@@ -0,0 +1,3 @@
+pub mod report;
+#[cfg(feature = "guests")]
+pub mod workload;
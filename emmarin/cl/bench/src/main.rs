@@ -0,0 +1,166 @@
+//! Records the cycle counts of the ledger guests over parameterized workloads,
+//! built with the `guests` feature: `cargo run --release -p bench --features
+//! guests -- run`.
+//!
+//! ```text
+//! bench run [--guests tx,bundle,ledger,stf_nop,batch] [--inputs 1,4] [--outputs 1,4]
+//!           [--txs 1,4] [--nullifiers 0,1024] [--zones 1,2] [--out bench.json]
+//! bench compare <baseline.json> <current.json> [--threshold 5]
+//! ```
+//!
+//! Guests are only executed, not proven, so runs take seconds and the cycle
+//! counts are deterministic. `compare` exits with a non-zero status when a
+//! workload regressed by more than the threshold percentage.
+
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use bench::{
+    report::{Record, Report},
+    workload,
+};
+use clap::{Args, Parser, ValueEnum};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[value(rename_all = "snake_case")]
+enum Guest {
+    Tx,
+    Bundle,
+    Ledger,
+    StfNop,
+    Batch,
+}
+
+/// The parameters of the workloads, each guest is run once per combination of
+/// the values it uses.
+#[derive(Args, Debug)]
+struct Workloads {
+    /// Guests to execute
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "tx,bundle,ledger,stf_nop,batch"
+    )]
+    guests: Vec<Guest>,
+    /// Inputs of each tx of the tx workloads
+    #[arg(long, value_delimiter = ',', default_value = "1,4")]
+    inputs: Vec<usize>,
+    /// Outputs of each tx of the tx workloads
+    #[arg(long, value_delimiter = ',', default_value = "1,4")]
+    outputs: Vec<usize>,
+    /// Txs of the bundle and ledger workloads
+    #[arg(long, value_delimiter = ',', default_value = "1,4")]
+    txs: Vec<usize>,
+    /// Nullifiers already spent in the ledger of the ledger workloads
+    #[arg(long, value_delimiter = ',', default_value = "0,1024")]
+    nullifiers: Vec<usize>,
+    /// Zones of the batch workloads
+    #[arg(long, value_delimiter = ',', default_value = "1,2")]
+    zones: Vec<usize>,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+enum Action {
+    /// Execute the guests over the workloads and write a report
+    Run {
+        #[command(flatten)]
+        workloads: Workloads,
+        /// Where to write the report
+        #[arg(long, default_value = "bench.json")]
+        out: PathBuf,
+    },
+    /// Compare two reports, failing on regressions
+    Compare {
+        baseline: PathBuf,
+        current: PathBuf,
+        /// Regression threshold, in percent of the baseline cycles
+        #[arg(long, default_value_t = 5.0)]
+        threshold: f64,
+    },
+}
+
+fn run(workloads: &Workloads, out: &Path) -> Result<(), String> {
+    let mut records: Vec<ledger::error::Result<Record>> = Vec::new();
+    for guest in &workloads.guests {
+        match guest {
+            Guest::Tx => {
+                for &i in &workloads.inputs {
+                    for &o in &workloads.outputs {
+                        records.push(workload::tx(i, o));
+                    }
+                }
+            }
+            Guest::Bundle => records.extend(workloads.txs.iter().map(|&t| workload::bundle(t))),
+            Guest::Ledger => {
+                for &t in &workloads.txs {
+                    for &n in &workloads.nullifiers {
+                        records.push(workload::ledger(t, n));
+                    }
+                }
+            }
+            Guest::StfNop => records.push(workload::stf_nop()),
+            Guest::Batch => records.extend(workloads.zones.iter().map(|&z| workload::batch(z))),
+        }
+    }
+
+    let mut report = Report::default();
+    for record in records {
+        let record = record.map_err(|e| format!("failed to execute guest: {e}"))?;
+        println!(
+            "{}: {} user cycles, {} total cycles, {} segments",
+            record.key(),
+            record.user_cycles,
+            record.total_cycles,
+            record.segments
+        );
+        report.push(record);
+    }
+
+    report
+        .write(out)
+        .map_err(|e| format!("failed to write {}: {e}", out.display()))
+}
+
+fn compare(baseline: &Path, current: &Path, threshold: f64) -> Result<bool, String> {
+    let read = |path: &Path| {
+        Report::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))
+    };
+
+    let comparison = read(current)?.compare(&read(baseline)?, threshold);
+    for delta in &comparison.regressions {
+        println!("REGRESSION {delta}");
+    }
+    for delta in &comparison.unchanged {
+        println!("ok {delta}");
+    }
+    for key in &comparison.added {
+        println!("new {key}");
+    }
+    for key in &comparison.removed {
+        println!("missing {key}");
+    }
+    Ok(!comparison.has_regressions())
+}
+
+fn main() -> ExitCode {
+    let result = match Action::parse() {
+        Action::Run { workloads, out } => run(&workloads, &out).map(|_| true),
+        Action::Compare {
+            baseline,
+            current,
+            threshold,
+        } => compare(&baseline, &current, threshold),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the report format, bumped on any change to its layout.
+pub const REPORT_VERSION: u32 = 1;
+
+/// Cycle counts of a single guest run on a single workload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub guest: String,
+    /// Parameters of the workload, e.g. `inputs` and `outputs` for the tx guest
+    pub params: BTreeMap<String, u64>,
+    pub segments: u64,
+    pub user_cycles: u64,
+    pub total_cycles: u64,
+}
+
+impl Record {
+    pub fn new(guest: impl Into<String>, params: &[(&str, u64)]) -> Self {
+        Self {
+            guest: guest.into(),
+            params: params.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            segments: 0,
+            user_cycles: 0,
+            total_cycles: 0,
+        }
+    }
+
+    pub fn with_cycles(mut self, segments: u64, user_cycles: u64, total_cycles: u64) -> Self {
+        self.segments = segments;
+        self.user_cycles = user_cycles;
+        self.total_cycles = total_cycles;
+        self
+    }
+
+    /// Identifies the workload across reports, e.g. `tx{inputs=1,outputs=2}`.
+    pub fn key(&self) -> String {
+        let params = self
+            .params
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!("{}{{{params}}}", self.guest)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    pub version: u32,
+    pub records: Vec<Record>,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            version: REPORT_VERSION,
+            records: Vec::new(),
+        }
+    }
+}
+
+impl Report {
+    pub fn push(&mut self, record: Record) {
+        self.records.push(record);
+    }
+
+    pub fn extend(&mut self, other: Report) {
+        self.records.extend(other.records);
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let report: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if report.version != REPORT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported report version {}", report.version),
+            ));
+        }
+        Ok(report)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Compare the records of this report against a baseline, a workload
+    /// regresses when its user or total cycles grew by more than `threshold`
+    /// percent.
+    pub fn compare(&self, baseline: &Report, threshold: f64) -> Comparison {
+        let baseline: BTreeMap<String, &Record> =
+            baseline.records.iter().map(|r| (r.key(), r)).collect();
+        let mut comparison = Comparison::default();
+
+        for record in &self.records {
+            let key = record.key();
+            let Some(old) = baseline.get(&key) else {
+                comparison.added.push(key);
+                continue;
+            };
+
+            let delta = Delta {
+                key,
+                user_cycles: (old.user_cycles, record.user_cycles),
+                total_cycles: (old.total_cycles, record.total_cycles),
+                segments: (old.segments, record.segments),
+            };
+            if delta.grew_by(threshold) {
+                comparison.regressions.push(delta);
+            } else {
+                comparison.unchanged.push(delta);
+            }
+        }
+
+        let current: Vec<String> = self.records.iter().map(Record::key).collect();
+        comparison.removed = baseline
+            .into_keys()
+            .filter(|key| !current.contains(key))
+            .collect();
+
+        comparison
+    }
+}
+
+/// Change in cycle counts of a workload, as `(baseline, current)` pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delta {
+    pub key: String,
+    pub user_cycles: (u64, u64),
+    pub total_cycles: (u64, u64),
+    pub segments: (u64, u64),
+}
+
+fn percent((old, new): (u64, u64)) -> f64 {
+    if old == 0 {
+        return if new == 0 { 0.0 } else { f64::INFINITY };
+    }
+    (new as f64 - old as f64) / old as f64 * 100.0
+}
+
+impl Delta {
+    fn grew_by(&self, threshold: f64) -> bool {
+        percent(self.user_cycles) > threshold || percent(self.total_cycles) > threshold
+    }
+}
+
+impl std::fmt::Display for Delta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: user cycles {} -> {} ({:+.2}%), total cycles {} -> {} ({:+.2}%), segments {} -> {}",
+            self.key,
+            self.user_cycles.0,
+            self.user_cycles.1,
+            percent(self.user_cycles),
+            self.total_cycles.0,
+            self.total_cycles.1,
+            percent(self.total_cycles),
+            self.segments.0,
+            self.segments.1,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    pub regressions: Vec<Delta>,
+    pub unchanged: Vec<Delta>,
+    /// Workloads missing from the baseline
+    pub added: Vec<String>,
+    /// Workloads missing from the current report
+    pub removed: Vec<String>,
+}
+
+impl Comparison {
+    pub fn has_regressions(&self) -> bool {
+        !self.regressions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(records: &[(&str, u64, u64)]) -> Report {
+        Report {
+            version: REPORT_VERSION,
+            records: records
+                .iter()
+                .map(|(guest, inputs, cycles)| {
+                    Record::new(*guest, &[("inputs", *inputs)]).with_cycles(1, *cycles, *cycles)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_record_key() {
+        let record = Record::new("tx", &[("outputs", 2), ("inputs", 1)]);
+        assert_eq!(record.key(), "tx{inputs=1,outputs=2}");
+        assert_eq!(Record::new("stf_nop", &[]).key(), "stf_nop{}");
+    }
+
+    #[test]
+    fn test_compare_flags_regressions_above_threshold() {
+        let baseline = report(&[("tx", 1, 1000), ("tx", 2, 1000), ("bundle", 1, 1000)]);
+        let current = report(&[("tx", 1, 1049), ("tx", 2, 1051), ("ledger", 1, 1000)]);
+
+        let comparison = current.compare(&baseline, 5.0);
+        assert!(comparison.has_regressions());
+        assert_eq!(comparison.regressions.len(), 1);
+        assert_eq!(comparison.regressions[0].key, "tx{inputs=2}");
+        assert_eq!(comparison.unchanged.len(), 1);
+        assert_eq!(comparison.added, vec!["ledger{inputs=1}".to_string()]);
+        assert_eq!(comparison.removed, vec!["bundle{inputs=1}".to_string()]);
+
+        assert!(!current.compare(&current, 0.0).has_regressions());
+    }
+
+    #[test]
+    fn test_report_json_round_trip() {
+        let report = report(&[("tx", 1, 1000)]);
+        let json = serde_json::to_vec(&report).unwrap();
+        assert_eq!(serde_json::from_slice::<Report>(&json).unwrap(), report);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use cl::{
+    crust::{Bundle, InputWitness, NullifierSecret, OutputWitness, Tx, TxWitness},
+    ds::mmr::{MMRProof, MMR},
+    mantle::{
+        ledger::LedgerState,
+        update::{BatchUpdate, Update},
+        ProofSystem, ZoneId,
+    },
+    test_utils::{fill_nfs, nmo},
+};
+use ledger::{
+    batch::ProvedBatch, bundle::ProvedBundle, error::Result, ledger::ProvedLedgerTransition,
+    prover::ExecuteStats, stf::StfProof, tx::ProvedTx,
+};
+use ledger_proof_statements::{ledger::LedgerProofPublic, stf::StfPublic};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+use crate::report::Record;
+
+/// Value of every note spent by a workload tx
+const NOTE_VALUE: u64 = 1000;
+
+/// Workloads are seeded so reruns of the same workload are comparable.
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+pub fn zone(i: usize) -> ZoneId {
+    let mut zone_id = [0u8; 32];
+    zone_id[..8].copy_from_slice(&(i as u64).to_le_bytes());
+    zone_id
+}
+
+fn record(guest: &str, params: &[(&str, u64)], stats: &ExecuteStats) -> Record {
+    Record::new(guest, params).with_cycles(
+        stats.segments as u64,
+        stats.user_cycles,
+        stats.total_cycles,
+    )
+}
+
+/// A tx of `zone_id` spending `inputs` fresh notes of `ledger` into `outputs`
+/// notes of the same total value.
+pub fn transfer(
+    ledger: &mut LedgerState,
+    zone_id: ZoneId,
+    inputs: usize,
+    outputs: usize,
+    mut rng: impl RngCore,
+) -> TxWitness {
+    let sender = NullifierSecret::random(&mut rng);
+    let receiver = NullifierSecret::random(&mut rng);
+
+    // keep the paths of the earlier notes up to date as the mmr grows, so all
+    // inputs are proven against the same mmr
+    let mut notes: Vec<(InputWitness, MMRProof)> = Vec::with_capacity(inputs);
+    let mut mmr = MMR::default();
+    for _ in 0..inputs {
+        let utxo = OutputWitness::new(NOTE_VALUE, nmo().unit(), sender.commit(), zone_id, &mut rng);
+        let cm = utxo.note_commitment();
+        let folds = ledger.commitments.folds(&cm.0);
+        for (note, path) in notes.iter_mut() {
+            path.update(&note.note_commitment().0, &folds);
+        }
+        let (new_mmr, path) = ledger.add_commitment(&cm);
+        mmr = new_mmr;
+        notes.push((InputWitness::from_output(utxo, sender, nmo()), path));
+    }
+
+    let total = NOTE_VALUE * inputs as u64;
+    let outputs = outputs.max(1) as u64;
+    let mut tx = TxWitness::default();
+    for (input, path) in notes {
+        tx = tx.add_input(input, (mmr.clone(), path));
+    }
+    for i in 0..outputs {
+        // the first output takes the remainder of the split
+        let value = total / outputs + if i == 0 { total % outputs } else { 0 };
+        let output = OutputWitness::new(value, nmo().unit(), receiver.commit(), zone_id, &mut rng);
+        tx = tx.add_output(output, "");
+    }
+    tx
+}
+
+fn execute_txs(txs: Vec<TxWitness>) -> Result<Vec<Tx>> {
+    txs.into_iter()
+        .map(|tx| Ok(ProvedTx::execute(tx, vec![], vec![])?.public))
+        .collect()
+}
+
+/// A bundle of `txs` local txs of `zone_id`, each spending one note.
+fn local_bundle(
+    ledger: &mut LedgerState,
+    zone_id: ZoneId,
+    txs: usize,
+    mut rng: impl RngCore,
+) -> Result<Bundle> {
+    let txs = (0..txs)
+        .map(|_| transfer(ledger, zone_id, 1, 2, &mut rng))
+        .collect();
+    Ok(ProvedBundle::execute(execute_txs(txs)?, vec![])?.public)
+}
+
+pub fn tx(inputs: usize, outputs: usize) -> Result<Record> {
+    let mut rng = rng();
+    let mut ledger = LedgerState::default();
+    let tx = transfer(&mut ledger, zone(0), inputs, outputs, &mut rng);
+
+    let executed = ProvedTx::execute(tx, vec![], vec![])?;
+    Ok(record(
+        "tx",
+        &[("inputs", inputs as u64), ("outputs", outputs as u64)],
+        &executed.stats,
+    ))
+}
+
+pub fn bundle(txs: usize) -> Result<Record> {
+    let mut rng = rng();
+    let mut ledger = LedgerState::default();
+    let txs_witness = (0..txs)
+        .map(|_| transfer(&mut ledger, zone(0), 1, 2, &mut rng))
+        .collect();
+
+    let executed = ProvedBundle::execute(execute_txs(txs_witness)?, vec![])?;
+    Ok(record("bundle", &[("txs", txs as u64)], &executed.stats))
+}
+
+pub fn ledger(txs: usize, nullifiers: usize) -> Result<Record> {
+    let mut rng = rng();
+    let mut ledger = LedgerState::default();
+    fill_nfs(&mut ledger, nullifiers, &mut rng);
+    let bundle = local_bundle(&mut ledger, zone(0), txs, &mut rng)?;
+
+    let executed = ProvedLedgerTransition::execute(&mut ledger, zone(0), vec![bundle], &[])?;
+    Ok(record(
+        "ledger",
+        &[("txs", txs as u64), ("nullifiers", nullifiers as u64)],
+        &executed.stats,
+    ))
+}
+
+pub fn stf_nop() -> Result<Record> {
+    let mut rng = rng();
+    let mut ledger = LedgerState::default();
+    let old = StfProof::nop_zone_state(&ledger);
+    transfer(&mut ledger, zone(0), 1, 1, &mut rng);
+    let new = StfProof::nop_zone_state(&ledger);
+
+    let executed = StfProof::execute_nop(StfPublic { old, new })?;
+    Ok(record("stf_nop", &[], &executed.stats))
+}
+
+/// A batch updating `zones` zones, each settling a bundle of a single tx.
+pub fn batch(zones: usize) -> Result<Record> {
+    let mut rng = rng();
+    let mut updates = Vec::with_capacity(zones);
+    let mut ledgers = BTreeMap::<ZoneId, LedgerProofPublic>::new();
+    let mut stfs = BTreeMap::new();
+
+    for i in 0..zones {
+        let zone_id = zone(i);
+        let mut ledger = LedgerState::default();
+        let bundle = local_bundle(&mut ledger, zone_id, 1, &mut rng)?;
+
+        let old = StfProof::nop_zone_state(&ledger);
+        let transition = ProvedLedgerTransition::execute(&mut ledger, zone_id, vec![bundle], &[])?;
+        let new = StfProof::nop_zone_state(&ledger);
+
+        updates.push(Update { zone_id, old, new });
+        ledgers.insert(zone_id, transition.public);
+        stfs.insert(zone_id, (ProofSystem::Risc0, StfProof::nop_image()));
+    }
+
+    let executed = ProvedBatch::execute(&BatchUpdate { updates }, &ledgers, &stfs)?;
+    Ok(record("batch", &[("zones", zones as u64)], &executed.stats))
+}
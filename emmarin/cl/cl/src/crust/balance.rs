@@ -93,7 +93,7 @@ impl Balance {
     pub fn insert_positive(&mut self, unit: Unit, value: Value) {
         for unit_bal in self.balances.iter_mut() {
             if unit_bal.unit == unit {
-                unit_bal.pos += value;
+                unit_bal.pos = unit_bal.pos.checked_add(value).expect("balance overflow");
                 return;
             }
         }
@@ -109,7 +109,7 @@ impl Balance {
     pub fn insert_negative(&mut self, unit: Unit, value: Value) {
         for unit_bal in self.balances.iter_mut() {
             if unit_bal.unit == unit {
-                unit_bal.neg += value;
+                unit_bal.neg = unit_bal.neg.checked_add(value).expect("balance overflow");
                 return;
             }
         }
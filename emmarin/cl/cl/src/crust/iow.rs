@@ -59,8 +59,16 @@ impl InputWitness {
         }
     }
 
+    /// The note this note becomes once migrated to `to_zone`, only the zone
+    /// and the nonce change.
+    pub fn migrate(&self, to_zone: ZoneId) -> OutputWitness {
+        let mut domain = b"NOMOS_MIGRATE".to_vec();
+        domain.extend(to_zone);
+        self.evolve_output(&domain).set_zone(to_zone)
+    }
+
     pub fn nullifier(&self) -> Nullifier {
-        Nullifier::new(&self.zone_id, self.nf_sk, self.note_commitment())
+        Nullifier::new(self.nf_sk, self.note_commitment())
     }
 
     pub fn note_commitment(&self) -> NoteCommitment {
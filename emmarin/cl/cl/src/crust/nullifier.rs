@@ -73,9 +73,11 @@ impl NullifierCommitment {
 }
 
 impl Nullifier {
-    pub fn new(tag: &dyn AsRef<[u8]>, sk: NullifierSecret, note_cm: NoteCommitment) -> Self {
+    // The zone of the note is bound through the note commitment, the same
+    // note migrated to another zone gets a new commitment and nullifier.
+    pub fn new(sk: NullifierSecret, note_cm: NoteCommitment) -> Self {
         let mut hasher = Hash::new();
-        hasher.update(tag.as_ref());
+        hasher.update(b"NOMOS_CL_NULLIFIER");
         hasher.update(sk.0);
         hasher.update(note_cm.0);
 
@@ -4,14 +4,14 @@ use std::collections::BTreeMap;
 
 use crate::{
     crust::{
-        Balance, BurnWitness, InputWitness, MintWitness, NoteCommitment, Nullifier, OutputWitness,
-        Unit,
+        balance::Value, Balance, BurnWitness, InputWitness, MintWitness, NoteCommitment, Nullifier,
+        NullifierCommitment, OutputWitness, Unit,
     },
     ds::{
         merkle,
         mmr::{MMRProof, Root, MMR},
     },
-    mantle::ZoneId,
+    mantle::{Domain, ZoneId},
 };
 
 /// An identifier of a transaction
@@ -19,7 +19,7 @@ use crate::{
 pub struct TxRoot(pub [u8; 32]);
 
 /// An identifier of a bundle
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct BundleRoot(pub [u8; 32]);
 
 impl From<[u8; 32]> for TxRoot {
@@ -46,12 +46,115 @@ impl TxRoot {
     }
 }
 
+/// An amount a tx leaves unbalanced for whoever builds the bundle including it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fee {
+    pub unit: Unit,
+    pub amount: Value,
+}
+
+impl Fee {
+    fn to_bytes(self) -> [u8; 40] {
+        let mut bytes = [0; 40];
+        bytes[..32].copy_from_slice(&self.unit);
+        bytes[32..].copy_from_slice(&self.amount.to_le_bytes());
+        bytes
+    }
+}
+
+/// Bundle heights of the ledger at which a tx may be included, both bounds
+/// are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Validity {
+    pub valid_from: Option<u64>,
+    pub valid_until: Option<u64>,
+}
+
+impl Validity {
+    pub fn contains(&self, height: u64) -> bool {
+        self.valid_from.is_none_or(|from| from <= height)
+            && self.valid_until.is_none_or(|until| height <= until)
+    }
+
+    /// No height is valid, e.g. the intersection of disjoint windows.
+    pub fn is_empty(&self) -> bool {
+        self.valid_from
+            .zip(self.valid_until)
+            .is_some_and(|(from, until)| from > until)
+    }
+
+    /// The heights at which both windows are valid.
+    pub fn intersect(self, other: Self) -> Self {
+        Self {
+            valid_from: self.valid_from.max(other.valid_from),
+            valid_until: match (self.valid_until, other.valid_until) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 18] {
+        let mut bytes = [0; 18];
+        let (chunks, _) = bytes.as_chunks_mut::<9>();
+        for (chunk, bound) in chunks.iter_mut().zip([self.valid_from, self.valid_until]) {
+            if let Some(bound) = bound {
+                chunk[0] = 1;
+                chunk[1..].copy_from_slice(&bound.to_le_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+/// A note spent in zone `from` and recreated in zone `to`, see
+/// [`InputWitness::migrate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Migration {
+    pub from: ZoneId,
+    pub to: ZoneId,
+    pub nf: Nullifier,
+    pub cm: NoteCommitment,
+}
+
+impl Migration {
+    fn to_bytes(self) -> [u8; 128] {
+        let mut bytes = [0; 128];
+        bytes[..32].copy_from_slice(&self.from);
+        bytes[32..64].copy_from_slice(&self.to);
+        bytes[64..96].copy_from_slice(&self.nf.0);
+        bytes[96..].copy_from_slice(&self.cm.0);
+        bytes
+    }
+
+    /// Check that this zone's side of the migration is part of `updates`.
+    pub fn is_settled_by(&self, zone_id: ZoneId, updates: &[LedgerUpdate]) -> bool {
+        (self.from != zone_id || updates.iter().any(|u| u.has_input(&self.nf)))
+            && (self.to != zone_id || updates.iter().any(|u| u.has_output(&self.cm)))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Tx {
     pub root: TxRoot,
+    /// Balance of the tx, net of the fee
     pub balance: Balance,
     pub updates: BTreeMap<ZoneId, LedgerUpdate>,
     pub data: Vec<u8>,
+    pub fee: Option<Fee>,
+    pub validity: Validity,
+    pub domain: Domain,
+    pub migrations: Vec<Migration>,
+}
+
+impl Tx {
+    /// The fee this tx pays in `unit`, zero if it pays none or pays in another unit.
+    pub fn fee_in(&self, unit: Unit) -> Value {
+        match self.fee {
+            Some(fee) if fee.unit == unit => fee.amount,
+            _ => 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -62,6 +165,13 @@ pub struct TxWitness {
     pub mints: Vec<MintWitness>,
     pub burns: Vec<BurnWitness>,
     pub frontier_paths: Vec<(MMR, MMRProof)>,
+    /// Paid out of the surplus of the tx in the fee unit
+    pub fee: Option<Fee>,
+    pub validity: Validity,
+    /// The tx is rejected by ledgers of other domains
+    pub domain: Domain,
+    /// Pairs an input with the output it migrates to, by index
+    pub migrations: Vec<(usize, usize)>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -131,6 +241,35 @@ impl TxWitness {
         self
     }
 
+    /// Spend `input` and recreate it in `to_zone`.
+    pub fn migrate(
+        mut self,
+        input: InputWitness,
+        input_cm_proof: (MMR, MMRProof),
+        to_zone: ZoneId,
+    ) -> Self {
+        self = self.add_input(input, input_cm_proof);
+        self.outputs.push((input.migrate(to_zone), vec![]));
+        self.migrations
+            .push((self.inputs.len() - 1, self.outputs.len() - 1));
+        self
+    }
+
+    pub fn with_fee(mut self, fee: Fee) -> Self {
+        self.fee = Some(fee);
+        self
+    }
+
+    pub fn with_validity(mut self, validity: Validity) -> Self {
+        self.validity = validity;
+        self
+    }
+
+    pub fn with_domain(mut self, domain: Domain) -> Self {
+        self.domain = domain;
+        self
+    }
+
     pub fn compute_updates(&self, inputs: &[InputDerivedFields]) -> BTreeMap<ZoneId, LedgerUpdate> {
         let mut updates: BTreeMap<ZoneId, LedgerUpdate> = Default::default();
 
@@ -199,6 +338,33 @@ impl TxWitness {
         merkle::node(mint_root, burn_root)
     }
 
+    /// Panics if a migrated output is not the migration of its input to
+    /// another zone.
+    pub fn migrations(&self, inputs: &[InputDerivedFields]) -> Vec<Migration> {
+        self.migrations
+            .iter()
+            .map(|&(i, o)| {
+                let input = &self.inputs[i];
+                let (output, _) = &self.outputs[o];
+                assert_ne!(
+                    input.zone_id, output.zone_id,
+                    "note migrated to its own zone"
+                );
+                assert_eq!(
+                    *output,
+                    input.migrate(output.zone_id),
+                    "migrated note does not match the spent note"
+                );
+                Migration {
+                    from: input.zone_id,
+                    to: output.zone_id,
+                    nf: inputs[i].nf,
+                    cm: output.note_commitment(),
+                }
+            })
+            .collect()
+    }
+
     fn io_balance(&self) -> Balance {
         let mut balance = Balance::zero();
         for input in &self.inputs {
@@ -210,16 +376,34 @@ impl TxWitness {
         balance
     }
 
-    pub fn root(&self, update_root: [u8; 32], mint_burn_root: [u8; 32]) -> TxRoot {
+    pub fn root(
+        &self,
+        update_root: [u8; 32],
+        mint_burn_root: [u8; 32],
+        migrations: &[Migration],
+    ) -> TxRoot {
         let data_root = merkle::leaf(&self.data);
+        let fee_root = merkle::leaf(&self.fee.map(|f| f.to_bytes().to_vec()).unwrap_or_default());
+        let validity_root = merkle::leaf(&self.validity.to_bytes());
+        let domain_root = merkle::leaf(&self.domain);
+        let migration_root = merkle::root(&merkle::padded_leaves(
+            migrations.iter().map(|m| m.to_bytes()),
+        ));
         let root = merkle::root(&merkle::padded_leaves([
             update_root,
             mint_burn_root,
             data_root,
+            fee_root,
+            validity_root,
+            domain_root,
+            migration_root,
         ]));
         TxRoot(root)
     }
 
+    /// Balance of the tx net of its fee.
+    ///
+    /// Panics if the fee is not covered by the surplus of the tx in the fee unit.
     pub fn balance(&self, mints: &[MintAmount], burns: &[BurnAmount]) -> Balance {
         let mut mint_burn_balance = Balance::zero();
         for MintAmount { unit, amount, .. } in mints {
@@ -228,7 +412,28 @@ impl TxWitness {
         for BurnAmount { unit, amount, .. } in burns {
             mint_burn_balance.insert_negative(*unit, *amount);
         }
-        Balance::combine(&[mint_burn_balance, self.io_balance()])
+        let mut balance = Balance::combine(&[mint_burn_balance, self.io_balance()]);
+
+        let Some(fee) = self.fee else {
+            return balance;
+        };
+        let surplus = balance
+            .balances
+            .iter_mut()
+            .find(|b| b.unit == fee.unit)
+            .filter(|b| {
+                fee.amount > 0
+                    && b.neg
+                        .checked_add(fee.amount)
+                        .is_some_and(|owed| b.pos >= owed)
+            })
+            .expect("fee is not covered by the tx surplus");
+        surplus.pos = surplus
+            .pos
+            .checked_sub(fee.amount)
+            .expect("fee is not covered by the tx surplus");
+        balance.clear_zeros();
+        balance
     }
 
     // inputs, mints and burns are provided as a separate argument to allow code reuse
@@ -247,7 +452,8 @@ impl TxWitness {
                 .iter()
                 .map(|(zone_id, update)| update.root(*zone_id)),
         ));
-        let root = self.root(update_root, mint_burn_root);
+        let migrations = self.migrations(inputs);
+        let root = self.root(update_root, mint_burn_root, &migrations);
         let balance = self.balance(mints, burns);
 
         Tx {
@@ -255,6 +461,10 @@ impl TxWitness {
             balance,
             updates,
             data: self.data.clone(),
+            fee: self.fee,
+            validity: self.validity,
+            domain: self.domain,
+            migrations,
         }
     }
 }
@@ -263,24 +473,129 @@ impl TxWitness {
 pub struct Bundle {
     pub updates: BTreeMap<ZoneId, Vec<LedgerUpdate>>,
     pub root: BundleRoot,
+    /// Fees paid by the txs of the bundle
+    pub fees: Balance,
+    /// Builder of the bundle, owning the outputs that claim the fees. `None`
+    /// if the bundle pays no fees.
+    pub fee_recipient: Option<NullifierCommitment>,
+    /// Intersection of the validity windows of the txs
+    pub validity: Validity,
+    pub domain: Domain,
+    /// Notes migrated between zones by the txs
+    pub migrations: Vec<Migration>,
+}
+
+impl Bundle {
+    /// Check that the migrations from or to `zone_id` spend and recreate their
+    /// notes in this zone's updates.
+    pub fn migrations_settled_by(&self, zone_id: ZoneId) -> bool {
+        let updates = self
+            .updates
+            .get(&zone_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        self.migrations
+            .iter()
+            .all(|m| m.is_settled_by(zone_id, updates))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BundleWitness {
     pub txs: Vec<Tx>,
+    /// Openings of the outputs of the txs claiming the fees for the builder
+    pub fee_outputs: Vec<OutputWitness>,
 }
 
 impl BundleWitness {
+    /// A bundle of `txs` paying no fees.
+    pub fn new(txs: Vec<Tx>) -> Self {
+        Self {
+            txs,
+            fee_outputs: Vec::new(),
+        }
+    }
+
+    pub fn with_fee_outputs(mut self, fee_outputs: Vec<OutputWitness>) -> Self {
+        self.fee_outputs = fee_outputs;
+        self
+    }
+
     pub fn root(&self) -> BundleRoot {
         BundleRoot(merkle::root(&merkle::padded_leaves(
             self.txs.iter().map(|tx| tx.root.0),
         )))
     }
 
+    /// Total fees paid by the txs, as a positive balance per unit.
+    pub fn fees(&self) -> Balance {
+        let mut fees = Balance::zero();
+        for fee in self.txs.iter().filter_map(|tx| tx.fee) {
+            fees.insert_positive(fee.unit, fee.amount);
+        }
+        fees
+    }
+
+    /// The builder claiming the fees: every fee output is an output of a tx
+    /// of the bundle owned by the same recipient, and together they claim
+    /// exactly `fees`.
+    fn fee_recipient(&self, fees: &Balance) -> Option<NullifierCommitment> {
+        let mut unclaimed = fees.clone();
+        for (i, output) in self.fee_outputs.iter().enumerate() {
+            let cm = output.note_commitment();
+            assert!(
+                self.fee_outputs[..i]
+                    .iter()
+                    .all(|other| other.note_commitment() != cm),
+                "fee output claimed twice"
+            );
+            assert!(
+                self.txs.iter().any(|tx| tx
+                    .updates
+                    .get(&output.zone_id)
+                    .is_some_and(|update| update.has_output(&cm))),
+                "fee output is not an output of the bundle"
+            );
+            assert_eq!(
+                output.nf_pk, self.fee_outputs[0].nf_pk,
+                "fees are claimed for several recipients"
+            );
+            unclaimed.insert_negative(output.unit, output.value);
+        }
+        assert!(
+            Balance::combine([&unclaimed]).is_zero(),
+            "fee outputs do not claim exactly the fees"
+        );
+        self.fee_outputs.first().map(|output| output.nf_pk)
+    }
+
+    /// The txs must balance once the fees are accounted for, and the fees go
+    /// to the builder through the fee outputs, see [`Bundle::fee_recipient`].
+    ///
+    /// All txs must be bound to the same domain.
     pub fn commit(self) -> Bundle {
-        assert!(Balance::combine(self.txs.iter().map(|tx| &tx.balance)).is_zero());
+        let fees = self.fees();
+        assert!(
+            Balance::combine(self.txs.iter().map(|tx| &tx.balance).chain([&fees])).is_zero(),
+            "bundle is not balanced"
+        );
+        let fee_recipient = self.fee_recipient(&fees);
+
+        let domain = self.txs.first().map(|tx| tx.domain).unwrap_or_default();
+        assert!(
+            self.txs.iter().all(|tx| tx.domain == domain),
+            "bundle mixes txs of different domains"
+        );
+        let validity = self.txs.iter().fold(Validity::default(), |validity, tx| {
+            validity.intersect(tx.validity)
+        });
 
         let root = self.root();
+        let migrations = self
+            .txs
+            .iter()
+            .flat_map(|tx| tx.migrations.iter().copied())
+            .collect();
 
         let updates = self
             .txs
@@ -292,7 +607,15 @@ impl BundleWitness {
                 updates
             });
 
-        Bundle { updates, root }
+        Bundle {
+            updates,
+            root,
+            fees,
+            fee_recipient,
+            validity,
+            domain,
+            migrations,
+        }
     }
 }
 
@@ -9,7 +9,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NullifierTree {
     leaves: Vec<Leaf>,
 }
@@ -46,6 +46,12 @@ impl NullifierTree {
         merkle::root(&self.hashed_leaves())
     }
 
+    /// Whether `value` can be inserted, i.e. it is not in the tree yet and
+    /// lies strictly between the sentinels.
+    pub fn can_insert(&self, value: Nullifier) -> bool {
+        self.leaves.iter().any(|l| in_interval(*l, value))
+    }
+
     pub fn insert(&mut self, value: Nullifier) -> UpdateProof {
         let (idx, &low_nf) = self
             .leaves
@@ -79,10 +85,17 @@ impl NullifierTree {
 
     pub fn insert_batch(&mut self, mut values: Vec<Nullifier>) -> BatchUpdateProof {
         values.sort();
+        self.insert_sorted(&values)
+    }
+
+    /// Insert values already sorted in strictly increasing order, e.g. by a
+    /// [`NullifierOrder`].
+    pub fn insert_sorted(&mut self, values: &[Nullifier]) -> BatchUpdateProof {
+        debug_assert!(values.windows(2).all(|w| w[0] < w[1]));
 
         let mut low_nfs_idx = <BTreeMap<_, Vec<_>>>::new();
 
-        for value in &values {
+        for value in values {
             let idx = self
                 .leaves
                 .iter()
@@ -135,6 +148,66 @@ impl NullifierTree {
     }
 }
 
+/// A nullifier spent more than once in the same batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuplicateNullifier(pub Nullifier);
+
+/// Witness sorting a list of nullifiers, the i-th sorted nullifier is the
+/// `order[i]`-th nullifier of the list.
+///
+/// The host sorts, the guest only checks the witness in linear time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullifierOrder {
+    pub(crate) order: Vec<u32>,
+}
+
+impl NullifierOrder {
+    pub fn new(values: &[Nullifier]) -> Result<Self, DuplicateNullifier> {
+        let mut order: Vec<u32> = (0..values.len() as u32).collect();
+        order.sort_unstable_by_key(|i| values[*i as usize]);
+
+        if let Some(w) = order
+            .windows(2)
+            .find(|w| values[w[0] as usize] == values[w[1] as usize])
+        {
+            return Err(DuplicateNullifier(values[w[0] as usize]));
+        }
+
+        Ok(Self { order })
+    }
+
+    /// Sort `values` following this order.
+    ///
+    /// Panics if the order is not a permutation of `values` or does not sort
+    /// them in strictly increasing order, so duplicates are rejected.
+    pub fn sorted(&self, values: &[Nullifier]) -> Vec<Nullifier> {
+        assert_eq!(self.order.len(), values.len(), "nullifier order length");
+
+        let mut seen = vec![false; values.len()];
+        let mut sorted = Vec::with_capacity(values.len());
+        for &i in &self.order {
+            let i = i as usize;
+            assert!(
+                i < values.len() && !seen[i],
+                "nullifier order is not a permutation"
+            );
+            seen[i] = true;
+
+            let value = values[i];
+            if let Some(prev) = sorted.last() {
+                assert!(
+                    *prev != value,
+                    "duplicate nullifier {}",
+                    hex::encode(value.0)
+                );
+                assert!(*prev < value, "nullifiers are not sorted");
+            }
+            sorted.push(value);
+        }
+        sorted
+    }
+}
+
 #[derive(Clone, Debug, Copy, Serialize, Deserialize)]
 pub struct Leaf {
     value: Nullifier,
@@ -395,6 +468,47 @@ mod tests {
         tree.insert_batch(vec![Nullifier([0; 32])]);
     }
 
+    #[test]
+    fn test_nullifier_order() {
+        let values = vec![Nullifier([3; 32]), Nullifier([1; 32]), Nullifier([2; 32])];
+        let order = NullifierOrder::new(&values).unwrap();
+        let sorted = order.sorted(&values);
+        assert_eq!(
+            sorted,
+            vec![Nullifier([1; 32]), Nullifier([2; 32]), Nullifier([3; 32])]
+        );
+
+        let mut tree = NullifierTree::new();
+        let proof = tree.insert_sorted(&sorted);
+        assert_eq!(
+            proof.verify(&sorted, NullifierTree::new().root()),
+            tree.root()
+        );
+    }
+
+    #[test]
+    fn test_nullifier_order_rejects_duplicates() {
+        let values = vec![Nullifier([2; 32]), Nullifier([1; 32]), Nullifier([2; 32])];
+        assert_eq!(
+            NullifierOrder::new(&values),
+            Err(DuplicateNullifier(Nullifier([2; 32])))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate nullifier")]
+    fn test_nullifier_order_checks_duplicates() {
+        let values = vec![Nullifier([2; 32]), Nullifier([2; 32])];
+        NullifierOrder { order: vec![0, 1] }.sorted(&values);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a permutation")]
+    fn test_nullifier_order_checks_permutation() {
+        let values = vec![Nullifier([1; 32]), Nullifier([2; 32])];
+        NullifierOrder { order: vec![0, 0] }.sorted(&values);
+    }
+
     #[test]
     fn test_insert() {
         let mut tree = NullifierTree::new();
@@ -221,6 +221,56 @@ impl MMR {
         MMRProof { path }
     }
 
+    /// Number of elements pushed to the MMR
+    pub fn len(&self) -> u64 {
+        self.roots.iter().map(|r| 1u64 << (r.height - 1)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// Check that the roots could have been built by [`Self::push`]: their
+    /// heights are strictly decreasing and within the heights of
+    /// [`EMPTY_ROOTS`]. [`Self::len`] and [`Self::frontier_root`] panic on an
+    /// MMR that is not, e.g. one deserialized from an untrusted peer.
+    pub fn is_well_formed(&self) -> bool {
+        self.roots
+            .iter()
+            .all(|r| (1..=EMPTY_ROOTS.len()).contains(&(r.height as usize)))
+            && self.roots.windows(2).all(|w| w[0].height > w[1].height)
+    }
+
+    /// Build the proof for the element at `index` from the full list of elements
+    /// pushed to the MMR, for hosts that keep the MMR history around.
+    pub fn prove_from_elems(elems: &[impl AsRef<[u8]>], index: usize) -> Option<MMRProof> {
+        if index >= elems.len() {
+            return None;
+        }
+
+        // peaks cover the elements from left to right in decreasing height
+        let mut start = 0;
+        for height in (0..usize::BITS).rev() {
+            let size = 1usize << height;
+            if elems.len() & size == 0 {
+                continue;
+            }
+            if index < start + size {
+                let leaves = Vec::from_iter(
+                    elems[start..start + size]
+                        .iter()
+                        .map(|e| merkle::leaf(e.as_ref())),
+                );
+                return Some(MMRProof {
+                    path: merkle::path(&leaves, index - start),
+                });
+            }
+            start += size;
+        }
+
+        unreachable!("index is within the MMR")
+    }
+
     pub fn verify_proof(&self, elem: &[u8], proof: &MMRProof) -> bool {
         let path_len = proof.path.len();
         let root = proof.root(elem);
@@ -271,6 +321,28 @@ mod test {
     use super::*;
     use proptest_macro::property_test;
 
+    #[test]
+    fn test_well_formed() {
+        let mut mmr = MMR::new();
+        for i in 0..7u8 {
+            mmr.push(&[i]);
+            assert!(mmr.is_well_formed());
+        }
+
+        let root = |height| Root {
+            root: [0; 32],
+            height,
+        };
+        for roots in [
+            vec![root(0)],
+            vec![root(65)],
+            vec![root(1), root(2)],
+            vec![root(2), root(2)],
+        ] {
+            assert!(!MMR { roots }.is_well_formed());
+        }
+    }
+
     #[test]
     fn test_empty_roots() {
         assert_eq!(EMPTY_ROOTS.len(), 32);
@@ -299,6 +371,7 @@ mod test {
         let mut mmr = MMR::new();
         let proof = mmr.push(b"hello");
 
+        assert_eq!(mmr.len(), 1);
         assert_eq!(mmr.roots.len(), 1);
         assert_eq!(mmr.roots[0].height, 1);
         assert_eq!(mmr.roots[0].root, merkle::leaf(b"hello"));
@@ -316,6 +389,7 @@ mod test {
 
         let proof = mmr.push(b"!");
 
+        assert_eq!(mmr.len(), 3);
         assert_eq!(mmr.roots.len(), 2);
         assert_eq!(mmr.roots[0].height, 2);
         assert_eq!(
@@ -340,6 +414,21 @@ mod test {
         assert!(mmr.verify_proof(b"!", &proof));
     }
 
+    #[test]
+    fn test_mmr_prove_from_elems() {
+        let elems = Vec::from_iter(('a'..='z').map(|x| [x as u8]));
+        let mut mmr = MMR::new();
+        for elem in &elems {
+            mmr.push(elem);
+        }
+
+        for (i, elem) in elems.iter().enumerate() {
+            let proof = MMR::prove_from_elems(&elems, i).unwrap();
+            assert!(mmr.verify_proof(elem, &proof));
+        }
+        assert!(MMR::prove_from_elems(&elems, elems.len()).is_none());
+    }
+
     #[test]
     fn test_mmr_proof_update() {
         let mut mmr = MMR::new();
@@ -1,6 +1,8 @@
 pub mod crust;
 pub mod ds;
 pub mod mantle;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 
 pub use risc0_zkvm::sha::rust_crypto::{Digest, Sha256};
 use serde::{de::DeserializeOwned, Serialize};
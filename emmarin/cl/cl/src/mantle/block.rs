@@ -0,0 +1,36 @@
+use crate::{mantle::update::BatchUpdate, Digest, Hash};
+use serde::{Deserialize, Serialize};
+
+/// Identifies a block by the hash of its header
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct BlockHash(pub [u8; 32]);
+
+impl BlockHash {
+    /// The parent of the first block built on the genesis zone states
+    pub const GENESIS: Self = Self([0; 32]);
+
+    pub fn hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+/// Orders a batch of zone updates after the block it builds on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub parent: BlockHash,
+    pub slot: u64,
+    pub batch: BatchUpdate,
+}
+
+impl BlockHeader {
+    pub fn hash(&self) -> BlockHash {
+        let mut hasher = Hash::new();
+        hasher.update(b"NOMOS_MANTLE_BLOCK");
+        hasher.update(self.parent.0);
+        hasher.update(self.slot.to_le_bytes());
+        hasher.update(crate::serialize(&self.batch));
+        BlockHash(hasher.finalize().into())
+    }
+}
@@ -1,9 +1,13 @@
 use crate::{
-    crust::{BundleRoot, NoteCommitment, Nullifier},
+    crust::{Bundle, BundleRoot, NoteCommitment, Nullifier},
     ds::{
-        indexed::{BatchUpdateProof, NullifierTree},
+        indexed::{BatchUpdateProof, DuplicateNullifier, NullifierOrder, NullifierTree},
         mmr::{MMRProof, MMR},
     },
+    mantle::{
+        sync::{PendingSyncs, SettledSync, SyncError, SyncLog},
+        Domain, ZoneId,
+    },
 };
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +16,8 @@ pub struct Ledger {
     pub cm_root: [u8; 32],
     pub nf_root: [u8; 32],
     pub bundles_root: [u8; 32],
+    pub sync_root: [u8; 32],
+    pub domain: Domain,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,6 +25,8 @@ pub struct LedgerWitness {
     pub commitments: MMR,
     pub nf_root: [u8; 32],
     pub bundles: MMR,
+    pub syncs: PendingSyncs,
+    pub domain: Domain,
 }
 
 impl LedgerWitness {
@@ -27,6 +35,8 @@ impl LedgerWitness {
             cm_root: self.commitments.commit(),
             nf_root: self.nf_root,
             bundles_root: self.bundles.commit(),
+            sync_root: self.syncs.commit(),
+            domain: self.domain,
         }
     }
 
@@ -38,6 +48,20 @@ impl LedgerWitness {
         self.commitments.push(&cm.0);
     }
 
+    /// Check that `bundle` can be added next, i.e. it is bound to the domain
+    /// of this ledger and its validity window contains the bundle height.
+    pub fn assert_bundle_valid(&self, bundle: &Bundle) {
+        assert_eq!(
+            bundle.domain, self.domain,
+            "bundle is bound to another domain"
+        );
+        let height = self.bundles.len();
+        assert!(
+            bundle.validity.contains(height),
+            "bundle is not valid at bundle height {height}"
+        );
+    }
+
     pub fn add_bundle(&mut self, bundle_root: BundleRoot) {
         self.bundles.push(&bundle_root.0);
     }
@@ -48,19 +72,109 @@ impl LedgerWitness {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct LedgerState {
     pub commitments: MMR,
     pub nullifiers: NullifierTree,
     pub bundles: MMR,
+    pub syncs: PendingSyncs,
+    pub domain: Domain,
+    /// Present only in archival mode
+    pub archive: Option<LedgerArchive>,
+}
+
+/// How much history a host ledger keeps.
+///
+/// Both modes produce the same ledger roots, only archival ledgers can serve
+/// inclusion proofs for commitments and bundles pushed in the past.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LedgerMode {
+    /// Keep only what is needed to prove new transitions
+    #[default]
+    Pruned,
+    /// Keep every commitment and bundle root ever added
+    Archival,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerArchive {
+    pub commitments: Vec<NoteCommitment>,
+    pub bundles: Vec<BundleRoot>,
 }
 
 impl LedgerState {
+    pub fn new(mode: LedgerMode) -> Self {
+        let archive = match mode {
+            LedgerMode::Pruned => None,
+            LedgerMode::Archival => Some(LedgerArchive::default()),
+        };
+        Self {
+            archive,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_domain(mut self, domain: Domain) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    pub fn mode(&self) -> LedgerMode {
+        match self.archive {
+            Some(_) => LedgerMode::Archival,
+            None => LedgerMode::Pruned,
+        }
+    }
+
+    /// Drop the archived history, switching the ledger to pruned mode.
+    pub fn prune(&mut self) {
+        self.archive = None;
+    }
+
+    /// Check that the archived history rebuilds the ledger MMRs.
+    pub fn archive_is_consistent(&self) -> bool {
+        let Some(archive) = &self.archive else {
+            return true;
+        };
+
+        let mut commitments = MMR::new();
+        for cm in &archive.commitments {
+            commitments.push(&cm.0);
+        }
+        let mut bundles = MMR::new();
+        for bundle in &archive.bundles {
+            bundles.push(&bundle.0);
+        }
+
+        commitments == self.commitments && bundles == self.bundles
+    }
+
+    /// Proof of inclusion of `cm` against the current commitments MMR, only
+    /// available in archival mode.
+    pub fn commitment_proof(&self, cm: &NoteCommitment) -> Option<MMRProof> {
+        let archive = self.archive.as_ref()?;
+        let index = archive.commitments.iter().position(|c| c == cm)?;
+        MMR::prove_from_elems(
+            &Vec::from_iter(archive.commitments.iter().map(|c| c.0)),
+            index,
+        )
+    }
+
+    /// Proof of inclusion of `bundle_root` against the current bundles MMR, only
+    /// available in archival mode.
+    pub fn bundle_proof(&self, bundle_root: &BundleRoot) -> Option<MMRProof> {
+        let archive = self.archive.as_ref()?;
+        let index = archive.bundles.iter().position(|b| b == bundle_root)?;
+        MMR::prove_from_elems(&Vec::from_iter(archive.bundles.iter().map(|b| b.0)), index)
+    }
+
     pub fn to_witness(&self) -> LedgerWitness {
         LedgerWitness {
             commitments: self.commitments.clone(),
             nf_root: self.nf_root(),
             bundles: self.bundles.clone(),
+            syncs: self.syncs.clone(),
+            domain: self.domain,
         }
     }
 
@@ -70,6 +184,9 @@ impl LedgerState {
 
     pub fn add_commitment(&mut self, cm: &NoteCommitment) -> (MMR, MMRProof) {
         let proof = self.commitments.push(&cm.0);
+        if let Some(archive) = &mut self.archive {
+            archive.commitments.push(*cm);
+        }
         (self.commitments.clone(), proof)
     }
 
@@ -77,8 +194,225 @@ impl LedgerState {
         self.nullifiers.insert_batch(nfs)
     }
 
+    /// Insert nullifiers sorted in strictly increasing order.
+    pub fn add_sorted_nullifiers(&mut self, nfs: &[Nullifier]) -> BatchUpdateProof {
+        self.nullifiers.insert_sorted(nfs)
+    }
+
     pub fn add_bundle(&mut self, bundle_root: BundleRoot) -> (MMR, MMRProof) {
         let proof = self.bundles.push(&bundle_root.0);
+        if let Some(archive) = &mut self.archive {
+            archive.bundles.push(bundle_root);
+        }
         (self.bundles.clone(), proof)
     }
 }
+
+/// The changes a single ledger transition makes to a zone ledger.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransitionDelta {
+    pub bundles: Vec<BundleDelta>,
+    /// Cross zone bundles acknowledged by all zones involved in this transition
+    pub synced: Vec<BundleRoot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleDelta {
+    pub root: BundleRoot,
+    /// All zones touched by the bundle
+    pub zones: Vec<ZoneId>,
+    pub nullifiers: Vec<Nullifier>,
+    pub outputs: Vec<NoteCommitment>,
+}
+
+impl TransitionDelta {
+    pub fn new(zone_id: ZoneId, bundles: &[Bundle], synced: &[BundleRoot]) -> Self {
+        let bundles = bundles
+            .iter()
+            .map(|bundle| {
+                let updates = bundle
+                    .updates
+                    .get(&zone_id)
+                    .expect("bundle does not involve this zone");
+                BundleDelta {
+                    root: bundle.root,
+                    zones: bundle.updates.keys().copied().collect(),
+                    nullifiers: updates.iter().flat_map(|u| u.inputs.clone()).collect(),
+                    outputs: updates
+                        .iter()
+                        .flat_map(|u| u.outputs.iter().map(|(cm, _data)| *cm))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Self {
+            bundles,
+            synced: synced.to_vec(),
+        }
+    }
+
+    pub fn nullifiers(&self) -> Vec<Nullifier> {
+        self.bundles
+            .iter()
+            .flat_map(|b| b.nullifiers.iter().copied())
+            .collect()
+    }
+
+    pub fn sync_logs(&self) -> Vec<SyncLog> {
+        self.settled_syncs().into_iter().map(|s| s.log).collect()
+    }
+
+    /// The cross zone bundles of the transition, with the outputs held back
+    /// until all their zones acknowledge them.
+    pub fn settled_syncs(&self) -> Vec<SettledSync> {
+        self.bundles
+            .iter()
+            .filter(|b| b.zones.len() > 1)
+            .map(|b| SettledSync {
+                log: SyncLog {
+                    bundle: b.root,
+                    zones: b.zones.clone(),
+                },
+                outputs: b.outputs.clone(),
+            })
+            .collect()
+    }
+
+    /// The order sorting the nullifiers spent by this transition, rejecting
+    /// nullifiers spent twice across its bundles.
+    pub fn nullifier_order(&self) -> Result<NullifierOrder, DuplicateNullifier> {
+        NullifierOrder::new(&self.nullifiers())
+    }
+
+    /// Apply the transition to the ledger, returning the proof of the nullifier
+    /// tree update.
+    ///
+    /// Panics if a nullifier is spent twice, see [`Self::nullifier_order`].
+    pub fn apply(&self, ledger: &mut LedgerState) -> Result<BatchUpdateProof, SyncError> {
+        let order = self
+            .nullifier_order()
+            .expect("nullifier spent twice in the transition");
+        self.apply_sorted(ledger, &order)
+    }
+
+    /// Apply the transition to the ledger with the nullifiers sorted by `order`.
+    ///
+    /// The outputs of cross zone bundles are only added once all their zones
+    /// acknowledge them, after the outputs of the local bundles. The ledger is
+    /// left untouched if its pending syncs reject the transition.
+    pub fn apply_sorted(
+        &self,
+        ledger: &mut LedgerState,
+        order: &NullifierOrder,
+    ) -> Result<BatchUpdateProof, SyncError> {
+        let released = ledger
+            .syncs
+            .transition(self.settled_syncs(), &self.synced)?;
+
+        let nf_proofs = ledger.add_sorted_nullifiers(&order.sorted(&self.nullifiers()));
+
+        for bundle in &self.bundles {
+            if bundle.zones.len() == 1 {
+                for cm in &bundle.outputs {
+                    ledger.add_commitment(cm);
+                }
+            }
+            ledger.add_bundle(bundle.root);
+        }
+        for cm in released.iter().flat_map(|s| &s.outputs) {
+            ledger.add_commitment(cm);
+        }
+
+        Ok(nf_proofs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils;
+
+    fn transition(seed: u8) -> TransitionDelta {
+        test_utils::transition([0; 32], seed)
+    }
+
+    #[test]
+    fn test_archival_and_pruned_roots_match() {
+        let mut pruned = LedgerState::new(LedgerMode::Pruned);
+        let mut archival = LedgerState::new(LedgerMode::Archival);
+
+        for seed in (1..=20).map(|i| i * 2) {
+            let delta = transition(seed);
+            delta.apply(&mut pruned).unwrap();
+            delta.apply(&mut archival).unwrap();
+            assert_eq!(pruned.to_witness(), archival.to_witness());
+        }
+        assert!(archival.archive_is_consistent());
+
+        archival.prune();
+        assert_eq!(archival.mode(), LedgerMode::Pruned);
+        assert_eq!(pruned.to_witness(), archival.to_witness());
+    }
+
+    #[test]
+    fn test_archival_historical_proofs() {
+        let mut pruned = LedgerState::new(LedgerMode::Pruned);
+        let mut archival = LedgerState::new(LedgerMode::Archival);
+        for seed in (1..=20).map(|i| i * 2) {
+            transition(seed).apply(&mut pruned).unwrap();
+            transition(seed).apply(&mut archival).unwrap();
+        }
+
+        for seed in (1..=20).map(|i| i * 2) {
+            let cm = NoteCommitment([seed; 32]);
+            let proof = archival.commitment_proof(&cm).unwrap();
+            assert!(archival.commitments.verify_proof(&cm.0, &proof));
+            assert!(pruned.commitment_proof(&cm).is_none());
+
+            let bundle = BundleRoot([seed; 32]);
+            let proof = archival.bundle_proof(&bundle).unwrap();
+            assert!(archival.bundles.verify_proof(&bundle.0, &proof));
+        }
+
+        assert!(archival
+            .commitment_proof(&NoteCommitment([255; 32]))
+            .is_none());
+    }
+
+    #[test]
+    fn test_cross_zone_outputs_wait_for_the_ack() {
+        use crate::mantle::sync::SYNC_WINDOW;
+
+        let cross = |seed| {
+            let mut delta = transition(seed);
+            delta.bundles[0].zones.push([1; 32]);
+            delta
+        };
+        let held = NoteCommitment([2; 32]);
+
+        // the counterpart settles its half later, the outputs are added on the ack
+        let mut ledger = LedgerState::new(LedgerMode::Archival);
+        cross(2).apply(&mut ledger).unwrap();
+        assert!(ledger.commitment_proof(&held).is_none());
+        let ack = TransitionDelta {
+            bundles: vec![],
+            synced: vec![BundleRoot([2; 32])],
+        };
+        ack.apply(&mut ledger).unwrap();
+        assert!(ledger.commitment_proof(&held).is_some());
+
+        // the counterpart never settles its half, the outputs are voided
+        let mut ledger = LedgerState::new(LedgerMode::Archival);
+        cross(2).apply(&mut ledger).unwrap();
+        for seed in 0..SYNC_WINDOW as u8 {
+            transition(10 + 2 * seed).apply(&mut ledger).unwrap();
+        }
+        assert!(!ledger.syncs.is_pending(&BundleRoot([2; 32])));
+        assert!(ledger.commitment_proof(&held).is_none());
+        assert!(matches!(
+            ack.apply(&mut ledger),
+            Err(SyncError::UnexpectedAck(root)) if root == BundleRoot([2; 32])
+        ));
+    }
+}
@@ -1,5 +1,7 @@
+pub mod block;
 pub mod ledger;
+pub mod sync;
 pub mod update;
 pub mod zone;
 
-pub use zone::{Stf, ZoneId, ZoneState};
+pub use zone::{Domain, ProofSystem, Stf, ZoneId, ZoneState};
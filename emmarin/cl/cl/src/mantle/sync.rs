@@ -0,0 +1,261 @@
+use crate::{
+    crust::{BundleRoot, NoteCommitment},
+    mantle::ZoneId,
+    Digest, Hash,
+};
+use serde::{Deserialize, Serialize};
+
+/// Number of ledger transitions of a zone within which the counterpart zones
+/// of a cross zone bundle must settle it as well.
+///
+/// A batch updates a zone at most once, and a zone left out of a batch does
+/// not move, so this counts the batches the zone takes part in.
+///
+/// A bundle not acknowledged by the last transition of its window expires:
+/// the zone drops it from its pending syncs and voids the outputs it held
+/// back for it, so a counterpart withholding its half can't halt the zone.
+/// The nullifiers the bundle spent in the zone stay spent.
+pub const SYNC_WINDOW: u64 = 8;
+
+/// A cross zone bundle together with all the zones it touches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncLog {
+    pub bundle: BundleRoot,
+    pub zones: Vec<ZoneId>,
+}
+
+/// The half of a cross zone bundle a zone settles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettledSync {
+    pub log: SyncLog,
+    /// Outputs of the bundle in this zone, they are only added to the zone
+    /// commitments once all zones acknowledge the bundle.
+    pub outputs: Vec<NoteCommitment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSync {
+    pub sync: SettledSync,
+    /// Number of further transitions of this zone within which all
+    /// counterpart zones must acknowledge the bundle.
+    pub ttl: u64,
+}
+
+/// A ledger transition settling or acknowledging cross zone bundles in a way
+/// the pending syncs of the zone do not allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncError {
+    /// The bundle is already pending, or settled twice in the transition
+    SettledTwice(BundleRoot),
+    /// The bundle is neither pending nor settled by the transition, or it is
+    /// acknowledged twice
+    UnexpectedAck(BundleRoot),
+}
+
+impl SyncError {
+    pub fn bundle(&self) -> BundleRoot {
+        match self {
+            Self::SettledTwice(bundle) | Self::UnexpectedAck(bundle) => *bundle,
+        }
+    }
+}
+
+/// Cross zone bundles settled by a zone but not yet by all of its counterparts.
+///
+/// A zone settling its half of a cross zone bundle records it here, holding
+/// back the outputs of the bundle. The entry is released once every zone
+/// involved acknowledges the bundle in the same batch, which they can only do
+/// once they all settled it, and its outputs are then added to the zone. An
+/// entry outliving its window expires, see [`SYNC_WINDOW`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSyncs {
+    pub pending: Vec<PendingSync>,
+}
+
+impl PendingSyncs {
+    pub fn commit(&self) -> [u8; 32] {
+        let mut hasher = Hash::new();
+        hasher.update(b"NOMOS_PENDING_SYNCS");
+        for PendingSync {
+            sync: SettledSync { log, outputs },
+            ttl,
+        } in &self.pending
+        {
+            hasher.update(log.bundle.0);
+            hasher.update((log.zones.len() as u32).to_le_bytes());
+            for zone in &log.zones {
+                hasher.update(zone);
+            }
+            hasher.update((outputs.len() as u32).to_le_bytes());
+            for cm in outputs {
+                hasher.update(cm.0);
+            }
+            hasher.update(ttl.to_le_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    pub fn is_pending(&self, bundle: &BundleRoot) -> bool {
+        self.pending.iter().any(|p| &p.sync.log.bundle == bundle)
+    }
+
+    /// Check that [`Self::transition`] accepts the transition.
+    pub fn check_transition(
+        &self,
+        settled: &[SyncLog],
+        acks: &[BundleRoot],
+    ) -> Result<(), SyncError> {
+        let mut bundles: Vec<BundleRoot> = self.pending.iter().map(|p| p.sync.log.bundle).collect();
+        for log in settled {
+            if bundles.contains(&log.bundle) {
+                return Err(SyncError::SettledTwice(log.bundle));
+            }
+            bundles.push(log.bundle);
+        }
+        for (i, ack) in acks.iter().enumerate() {
+            if !bundles.contains(ack) || acks[..i].contains(ack) {
+                return Err(SyncError::UnexpectedAck(*ack));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply a ledger transition that settled the cross zone bundles in
+    /// `settled`, while all the zones involved in the bundles in `acks`
+    /// agree they are now fully settled.
+    ///
+    /// Returns the cross zone bundles released by this transition, whose
+    /// outputs the zone must now add. The syncs are left untouched if the
+    /// transition is rejected.
+    pub fn transition(
+        &mut self,
+        settled: Vec<SettledSync>,
+        acks: &[BundleRoot],
+    ) -> Result<Vec<SettledSync>, SyncError> {
+        let logs = Vec::from_iter(settled.iter().map(|s| s.log.clone()));
+        self.check_transition(&logs, acks)?;
+        let mut released = Vec::new();
+
+        // age the syncs left pending by previous transitions, the ones
+        // reaching the end of their window expire with their outputs
+        for PendingSync { sync, ttl } in std::mem::take(&mut self.pending) {
+            if acks.contains(&sync.log.bundle) {
+                released.push(sync);
+            } else if ttl > 1 {
+                self.pending.push(PendingSync { sync, ttl: ttl - 1 });
+            }
+        }
+
+        for sync in settled {
+            if acks.contains(&sync.log.bundle) {
+                // all counterparts settle it in this same batch
+                released.push(sync);
+            } else {
+                self.pending.push(PendingSync {
+                    sync,
+                    ttl: SYNC_WINDOW,
+                });
+            }
+        }
+
+        Ok(released)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZONE_A: ZoneId = [0; 32];
+    const ZONE_B: ZoneId = [1; 32];
+
+    fn cross_sync(bundle: u8) -> SettledSync {
+        SettledSync {
+            log: SyncLog {
+                bundle: BundleRoot([bundle; 32]),
+                zones: vec![ZONE_A, ZONE_B],
+            },
+            outputs: vec![NoteCommitment([bundle; 32])],
+        }
+    }
+
+    #[test]
+    fn test_settled_by_all_zones_in_same_batch() {
+        let mut syncs = PendingSyncs::default();
+        let sync = cross_sync(1);
+
+        let released = syncs
+            .transition(vec![sync.clone()], &[sync.log.bundle])
+            .unwrap();
+
+        assert_eq!(released, vec![sync]);
+        assert_eq!(syncs, PendingSyncs::default());
+    }
+
+    #[test]
+    fn test_counterpart_settles_later() {
+        let mut syncs = PendingSyncs::default();
+        let sync = cross_sync(1);
+
+        assert_eq!(syncs.transition(vec![sync.clone()], &[]), Ok(vec![]));
+        assert!(syncs.is_pending(&sync.log.bundle));
+        let pending_root = syncs.commit();
+
+        for _ in 1..SYNC_WINDOW {
+            assert_eq!(syncs.transition(vec![], &[]), Ok(vec![]));
+            assert_ne!(syncs.commit(), pending_root);
+        }
+
+        assert_eq!(syncs.transition(vec![], &[sync.log.bundle]), Ok(vec![sync]));
+        assert_eq!(syncs.commit(), PendingSyncs::default().commit());
+    }
+
+    #[test]
+    fn test_withheld_half_expires() {
+        let mut syncs = PendingSyncs::default();
+        let sync = cross_sync(1);
+
+        // the counterpart zone never settles its half of the bundle
+        syncs.transition(vec![sync.clone()], &[]).unwrap();
+        for _ in 1..SYNC_WINDOW {
+            assert_eq!(syncs.transition(vec![], &[]), Ok(vec![]));
+        }
+        assert!(syncs.is_pending(&sync.log.bundle));
+
+        // the zone moves past the window, the bundle is dropped without
+        // releasing its outputs
+        assert_eq!(syncs.transition(vec![cross_sync(2)], &[]), Ok(vec![]));
+        assert!(!syncs.is_pending(&sync.log.bundle));
+        assert!(syncs.is_pending(&cross_sync(2).log.bundle));
+
+        // and it can no longer be acknowledged
+        assert_eq!(
+            syncs.transition(vec![], &[sync.log.bundle]),
+            Err(SyncError::UnexpectedAck(sync.log.bundle))
+        );
+    }
+
+    #[test]
+    fn test_ack_unknown_bundle() {
+        let mut syncs = PendingSyncs::default();
+        let bundle = BundleRoot([1; 32]);
+        assert_eq!(
+            syncs.transition(vec![], &[bundle]),
+            Err(SyncError::UnexpectedAck(bundle))
+        );
+    }
+
+    #[test]
+    fn test_settle_pending_bundle_again() {
+        let mut syncs = PendingSyncs::default();
+        syncs.transition(vec![cross_sync(1)], &[]).unwrap();
+        let pending = syncs.clone();
+
+        assert_eq!(
+            syncs.transition(vec![cross_sync(1)], &[]),
+            Err(SyncError::SettledTwice(cross_sync(1).log.bundle))
+        );
+        // the rejected transition left the syncs untouched
+        assert_eq!(syncs, pending);
+    }
+}
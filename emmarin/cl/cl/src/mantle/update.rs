@@ -1,4 +1,4 @@
-use crate::mantle::ZoneState;
+use crate::mantle::{ZoneId, ZoneState};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -8,6 +8,7 @@ pub struct BatchUpdate {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Update {
+    pub zone_id: ZoneId,
     pub old: ZoneState,
     pub new: ZoneState,
 }
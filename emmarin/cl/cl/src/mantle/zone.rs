@@ -1,4 +1,5 @@
 use super::ledger::Ledger;
+use crate::{Digest, Hash};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -11,3 +12,60 @@ pub struct ZoneState {
 pub type Stf = [u8; 32];
 pub type ZoneId = [u8; 32];
 pub type ZoneData = [u8; 32];
+/// Identifies the network a ledger belongs to, txs are bound to a single domain
+pub type Domain = [u8; 32];
+
+/// The proof system a zone proves its state transitions with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ProofSystem {
+    /// risc0 receipts of the stf image
+    Risc0,
+    /// risc0 receipts of the stf image compressed to a groth16 seal
+    Groth16,
+}
+
+impl ProofSystem {
+    fn tag(&self) -> &'static [u8] {
+        match self {
+            ProofSystem::Risc0 => b"RISC0",
+            ProofSystem::Groth16 => b"GROTH16",
+        }
+    }
+
+    /// Derive the identifier of the stf proven by this system with the given
+    /// program, e.g. the risc0 image id.
+    ///
+    /// The same program proven by two systems yields two distinct stfs.
+    pub fn stf(&self, program_id: &[u8; 32]) -> Stf {
+        let mut hasher = Hash::new();
+        hasher.update(b"NOMOS_STF");
+        hasher.update((self.tag().len() as u32).to_le_bytes());
+        hasher.update(self.tag());
+        hasher.update(program_id);
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stf_is_bound_to_proof_system() {
+        let image = [7u8; 32];
+
+        assert_eq!(
+            ProofSystem::Risc0.stf(&image),
+            ProofSystem::Risc0.stf(&image)
+        );
+        assert_ne!(
+            ProofSystem::Risc0.stf(&image),
+            ProofSystem::Groth16.stf(&image)
+        );
+        assert_ne!(
+            ProofSystem::Risc0.stf(&image),
+            ProofSystem::Risc0.stf(&[8u8; 32])
+        );
+        assert_ne!(ProofSystem::Risc0.stf(&image), image);
+    }
+}
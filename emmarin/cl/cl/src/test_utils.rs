@@ -0,0 +1,103 @@
+use rand_core::RngCore;
+
+use crate::{
+    crust::{
+        tx::Validity, BundleRoot, MintWitness, NoteCommitment, Nullifier, NullifierSecret,
+        OutputWitness, Tx, TxWitness, UnitWitness,
+    },
+    ds::indexed::NullifierOrder,
+    mantle::{
+        ledger::{BundleDelta, LedgerState, TransitionDelta},
+        ZoneId,
+    },
+};
+
+/// The NMO unit, its no-op covenants need no covenant proofs.
+pub fn nmo() -> UnitWitness {
+    UnitWitness::nop(b"NMO")
+}
+
+/// The MEM unit, its no-op covenants need no covenant proofs.
+pub fn mem() -> UnitWitness {
+    UnitWitness::nop(b"MEM")
+}
+
+/// A unit with no-op covenants and a random arg, so no covenant proofs are
+/// needed to move it.
+pub fn arbitrary_unit(mut rng: impl RngCore) -> UnitWitness {
+    let mut arg = [0; 32];
+    rng.fill_bytes(&mut arg);
+    UnitWitness {
+        arg,
+        ..UnitWitness::nop(b"ANY")
+    }
+}
+
+/// A tx minting `minted` NMO into an output of `value` NMO.
+pub fn mint(minted: u64, value: u64, mut rng: impl RngCore) -> TxWitness {
+    let pk = NullifierSecret::random(&mut rng).commit();
+    let mut tx = TxWitness::default();
+    if minted > 0 {
+        tx.mints.push(MintWitness {
+            amount: minted,
+            unit: nmo(),
+            salt: [0; 16],
+        });
+    }
+    tx.add_output(
+        OutputWitness::new(value, nmo().unit(), pk, [0; 32], &mut rng),
+        "",
+    )
+}
+
+/// Commit to `tx` with the amounts and input fields derived from its witness.
+pub fn commit(tx: &TxWitness) -> Tx {
+    tx.commit(
+        &tx.mint_amounts(),
+        &tx.burn_amounts(),
+        &tx.inputs_derived_fields(),
+    )
+}
+
+pub fn window(valid_from: Option<u64>, valid_until: Option<u64>) -> Validity {
+    Validity {
+        valid_from,
+        valid_until,
+    }
+}
+
+/// Grow the nullifier tree of `ledger` by `amount` random nullifiers.
+pub fn fill_nfs(ledger: &mut LedgerState, amount: usize, mut rng: impl RngCore) {
+    ledger.add_nullifiers(
+        std::iter::repeat_with(|| {
+            let mut nf = [0; 32];
+            rng.fill_bytes(&mut nf);
+            Nullifier(nf)
+        })
+        .take(amount)
+        .collect(),
+    );
+}
+
+/// A nullifier order taken as is, e.g. one sorting duplicates, which
+/// [`NullifierOrder::new`] refuses to build.
+pub fn nullifier_order(order: Vec<u32>) -> NullifierOrder {
+    NullifierOrder { order }
+}
+
+/// A transition of `zone_id` settling a single local bundle, its root,
+/// nullifiers and outputs are derived from `seed`.
+///
+/// The nullifiers are `seed` and `seed + 1`, so transitions of distinct even
+/// seeds never conflict.
+pub fn transition(zone_id: ZoneId, seed: u8) -> TransitionDelta {
+    TransitionDelta {
+        bundles: vec![BundleDelta {
+            root: BundleRoot([seed; 32]),
+            zones: vec![zone_id],
+            nullifiers: vec![Nullifier([seed; 32]), Nullifier([seed + 1; 32])],
+            outputs: vec![NoteCommitment([seed; 32]), NoteCommitment([seed + 1; 32])],
+        }],
+        synced: vec![],
+    }
+}
@@ -0,0 +1,114 @@
+use cl::{
+    crust::{
+        tx::Fee, Balance, BundleWitness, NullifierCommitment, NullifierSecret, OutputWitness, Tx,
+        TxWitness,
+    },
+    test_utils::{commit, mem, mint, nmo},
+};
+
+/// A builder tx claiming `value` NMO for `pk`, with the opening of its output.
+fn claim(value: u64, pk: NullifierCommitment) -> (Tx, OutputWitness) {
+    let output = OutputWitness::new(value, nmo().unit(), pk, [0; 32], rand::thread_rng());
+    (commit(&TxWitness::default().add_output(output, "")), output)
+}
+
+fn builder() -> NullifierCommitment {
+    NullifierSecret::random(rand::thread_rng()).commit()
+}
+
+fn fee(amount: u64) -> Fee {
+    Fee {
+        unit: nmo().unit(),
+        amount,
+    }
+}
+
+#[test]
+fn test_fee_is_committed_and_netted_from_balance() {
+    let tx = commit(&mint(10, 9, rand::thread_rng()).with_fee(fee(1)));
+    assert!(tx.balance.is_zero());
+    assert_eq!(tx.fee_in(nmo().unit()), 1);
+    assert_eq!(tx.fee_in(mem().unit()), 0);
+
+    // the fee is bound to the tx root
+    let witness = mint(10, 8, rand::thread_rng()).with_fee(fee(1));
+    let higher_fee = witness.clone().with_fee(fee(2));
+    assert_ne!(commit(&witness).root, commit(&higher_fee).root);
+}
+
+#[test]
+#[should_panic(expected = "fee is not covered")]
+fn test_fee_exceeding_surplus_is_rejected() {
+    commit(&mint(10, 9, rand::thread_rng()).with_fee(fee(2)));
+}
+
+#[test]
+#[should_panic(expected = "fee is not covered")]
+fn test_overflowing_fee_is_rejected() {
+    commit(&mint(10, 9, rand::thread_rng()).with_fee(fee(u64::MAX)));
+}
+
+#[test]
+#[should_panic(expected = "balance overflow")]
+fn test_overflowing_balance_is_rejected() {
+    let mut balance = Balance::zero();
+    balance.insert_positive(nmo().unit(), u64::MAX);
+    balance.insert_positive(nmo().unit(), 1);
+}
+
+#[test]
+fn test_bundle_builder_claims_fees() {
+    let user = commit(&mint(10, 7, rand::thread_rng()).with_fee(fee(3)));
+    // the builder pays itself the fee with a tx of its own
+    let pk = builder();
+    let (claim, output) = claim(3, pk);
+
+    let bundle = BundleWitness::new(vec![user, claim])
+        .with_fee_outputs(vec![output])
+        .commit();
+    assert_eq!(bundle.fees.unit_balance(nmo().unit()).pos, 3);
+    assert_eq!(bundle.fee_recipient, Some(pk));
+
+    let free = BundleWitness::new(vec![commit(&mint(5, 5, rand::thread_rng()))]).commit();
+    assert_eq!(free.fee_recipient, None);
+}
+
+#[test]
+#[should_panic(expected = "bundle is not balanced")]
+fn test_unclaimed_fees_are_rejected() {
+    BundleWitness::new(vec![commit(
+        &mint(10, 7, rand::thread_rng()).with_fee(fee(3)),
+    )])
+    .commit();
+}
+
+#[test]
+#[should_panic(expected = "fee outputs do not claim exactly the fees")]
+fn test_fees_must_be_claimed_for_the_builder() {
+    // the fee is balanced by an output, but nothing attributes it to a builder
+    let user = commit(&mint(10, 7, rand::thread_rng()).with_fee(fee(3)));
+    let (claim, _) = claim(3, builder());
+    BundleWitness::new(vec![user, claim]).commit();
+}
+
+#[test]
+#[should_panic(expected = "fee output is not an output of the bundle")]
+fn test_fee_outputs_belong_to_the_bundle() {
+    let user = commit(&mint(10, 7, rand::thread_rng()).with_fee(fee(3)));
+    let (claim_tx, _) = claim(3, builder());
+    let (_, elsewhere) = claim(3, builder());
+    BundleWitness::new(vec![user, claim_tx])
+        .with_fee_outputs(vec![elsewhere])
+        .commit();
+}
+
+#[test]
+#[should_panic(expected = "several recipients")]
+fn test_fees_go_to_a_single_builder() {
+    let user = commit(&mint(10, 6, rand::thread_rng()).with_fee(fee(4)));
+    let (claim_a, output_a) = claim(2, builder());
+    let (claim_b, output_b) = claim(2, builder());
+    BundleWitness::new(vec![user, claim_a, claim_b])
+        .with_fee_outputs(vec![output_a, output_b])
+        .commit();
+}
@@ -0,0 +1,103 @@
+use cl::{
+    crust::{
+        tx::Migration, BundleWitness, InputWitness, NullifierSecret, OutputWitness, TxWitness,
+        UnitWitness,
+    },
+    ds::mmr::MMR,
+    mantle::ZoneId,
+    test_utils::{arbitrary_unit, commit},
+};
+use rand::Rng;
+
+const ZONE_A: ZoneId = [0u8; 32];
+const ZONE_B: ZoneId = [1u8; 32];
+
+/// A note of `unit` in zone A along with its inclusion proof.
+fn note(unit: UnitWitness, value: u64) -> (InputWitness, (MMR, cl::ds::mmr::MMRProof)) {
+    let mut rng = rand::thread_rng();
+    let sk = NullifierSecret::random(&mut rng);
+    let output = OutputWitness::new(value, unit.unit(), sk.commit(), ZONE_A, &mut rng);
+    let mut mmr = MMR::new();
+    let proof = mmr.push(&output.note_commitment().0);
+    (InputWitness::from_output(output, sk, unit), (mmr, proof))
+}
+
+#[test]
+fn test_migrated_note_only_changes_zone_and_nonce() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..8 {
+        let (input, _) = note(arbitrary_unit(&mut rng), rng.gen_range(1..1000));
+        let migrated = input.migrate(ZONE_B);
+
+        assert_eq!(migrated.zone_id, ZONE_B);
+        assert_eq!(
+            migrated.set_zone(ZONE_A),
+            OutputWitness {
+                nonce: migrated.nonce,
+                ..input.to_output()
+            }
+        );
+        assert_ne!(migrated.nonce, input.nonce);
+        assert_ne!(migrated.note_commitment(), input.note_commitment());
+
+        // the migrated note is spent with the same secret, under a new nullifier
+        let spent = InputWitness::from_output(migrated, input.nf_sk, input.unit_witness);
+        assert_ne!(spent.nullifier(), input.nullifier());
+    }
+}
+
+#[test]
+fn test_migration_is_committed_and_settled_by_both_zones() {
+    let mut rng = rand::thread_rng();
+    let (input, proof) = note(arbitrary_unit(&mut rng), 10);
+    let witness = TxWitness::default().migrate(input, proof, ZONE_B);
+
+    let tx = commit(&witness);
+    assert!(tx.balance.is_zero());
+    assert_eq!(
+        tx.migrations,
+        vec![Migration {
+            from: ZONE_A,
+            to: ZONE_B,
+            nf: input.nullifier(),
+            cm: input.migrate(ZONE_B).note_commitment(),
+        }]
+    );
+    assert!(tx.updates[&ZONE_A].has_input(&input.nullifier()));
+    assert!(tx.updates[&ZONE_B].has_output(&input.migrate(ZONE_B).note_commitment()));
+
+    // the pairing is bound to the tx root
+    let mut unpaired = witness.clone();
+    unpaired.migrations.clear();
+    assert_ne!(commit(&unpaired).root, tx.root);
+
+    let mut bundle = BundleWitness::new(vec![tx]).commit();
+    assert!(bundle.migrations_settled_by(ZONE_A));
+    assert!(bundle.migrations_settled_by(ZONE_B));
+    assert!(bundle.migrations_settled_by([2; 32]));
+
+    // zone B withholds the migrated note
+    bundle.updates.get_mut(&ZONE_B).unwrap()[0].outputs.clear();
+    assert!(bundle.migrations_settled_by(ZONE_A));
+    assert!(!bundle.migrations_settled_by(ZONE_B));
+}
+
+#[test]
+#[should_panic(expected = "migrated note does not match the spent note")]
+fn test_migration_cannot_change_the_note() {
+    let mut rng = rand::thread_rng();
+    let (input, proof) = note(arbitrary_unit(&mut rng), 10);
+    let mut witness = TxWitness::default().migrate(input, proof, ZONE_B);
+    witness.outputs[0].0.value = 9;
+
+    commit(&witness);
+}
+
+#[test]
+#[should_panic(expected = "note migrated to its own zone")]
+fn test_migration_must_change_zone() {
+    let mut rng = rand::thread_rng();
+    let (input, proof) = note(arbitrary_unit(&mut rng), 10);
+
+    commit(&TxWitness::default().migrate(input, proof, ZONE_A));
+}
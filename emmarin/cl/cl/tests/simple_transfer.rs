@@ -1,4 +1,6 @@
-use cl::crust::{InputWitness, Nonce, NullifierSecret, OutputWitness, TxWitness, UnitWitness};
+use cl::crust::{
+    tx::Validity, InputWitness, Nonce, NullifierSecret, OutputWitness, TxWitness, UnitWitness,
+};
 
 fn nmo_unit() -> UnitWitness {
     UnitWitness {
@@ -55,6 +57,10 @@ fn test_simple_transfer() {
         mints: vec![],
         burns: vec![],
         frontier_paths: vec![],
+        fee: None,
+        validity: Validity::default(),
+        domain: [0; 32],
+        migrations: vec![],
     };
 
     assert!(tx_witness
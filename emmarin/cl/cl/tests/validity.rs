@@ -0,0 +1,56 @@
+use cl::{
+    crust::{tx::Validity, BundleWitness},
+    test_utils::{commit, mint, window},
+};
+
+#[test]
+fn test_validity_bounds_are_inclusive() {
+    let validity = window(Some(2), Some(4));
+    assert!(!validity.contains(1));
+    assert!(validity.contains(2));
+    assert!(validity.contains(4));
+    assert!(!validity.contains(5));
+
+    assert!(Validity::default().contains(0));
+    assert!(window(None, Some(0)).contains(0));
+    assert!(window(Some(3), None).contains(u64::MAX));
+}
+
+#[test]
+fn test_validity_and_domain_are_committed() {
+    let witness = mint(10, 10, rand::thread_rng());
+    let tx = commit(&witness.clone().with_validity(window(Some(1), Some(5))));
+    assert_eq!(tx.validity, window(Some(1), Some(5)));
+
+    let root = commit(&witness.clone()).root;
+    assert_ne!(tx.root, root);
+    assert_ne!(
+        commit(&witness.clone().with_validity(window(None, Some(5)))).root,
+        tx.root
+    );
+    assert_ne!(commit(&witness.with_domain([1; 32])).root, root);
+}
+
+#[test]
+fn test_bundle_validity_is_the_intersection() {
+    let bundle = BundleWitness::new(vec![
+        commit(&mint(1, 1, rand::thread_rng()).with_validity(window(Some(2), Some(9)))),
+        commit(&mint(2, 2, rand::thread_rng()).with_validity(window(None, Some(6)))),
+        commit(&mint(3, 3, rand::thread_rng()).with_validity(window(Some(4), None))),
+        commit(&mint(4, 4, rand::thread_rng())),
+    ])
+    .commit();
+
+    assert_eq!(bundle.validity, window(Some(4), Some(6)));
+    assert_eq!(bundle.domain, [0; 32]);
+}
+
+#[test]
+#[should_panic(expected = "bundle mixes txs of different domains")]
+fn test_bundle_rejects_mixed_domains() {
+    BundleWitness::new(vec![
+        commit(&mint(1, 1, rand::thread_rng()).with_domain([1; 32])),
+        commit(&mint(2, 2, rand::thread_rng()).with_domain([2; 32])),
+    ])
+    .commit();
+}
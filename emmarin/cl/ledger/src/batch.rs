@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use cl::mantle::{ProofSystem, ZoneId};
+use ledger_proof_statements::{
+    batch::{BatchProofPrivate, BatchProofPublic, BatchZoneWitness},
+    ledger::LedgerProofPublic,
+    stf::StfPublic,
+};
+
+use crate::{
+    error::{Error, Result},
+    prover::{assumed_claim, execute, Executed, ProverContext},
+    update::ProvedBatchUpdate,
+    verifier::Verifier,
+};
+
+/// A single succinct proof of a whole batch of zone updates.
+#[derive(Debug, Clone)]
+pub struct ProvedBatch {
+    pub risc0_receipt: risc0_zkvm::Receipt,
+}
+
+impl ProvedBatch {
+    /// Fails if a zone of the batch is missing its ledger or stf proof, or
+    /// proves its stf with another system than risc0, or if the batch guest
+    /// fails to prove.
+    pub fn prove(ctx: &ProverContext, proved_batch: &ProvedBatchUpdate) -> Result<Self> {
+        let mut env = risc0_zkvm::ExecutorEnv::builder();
+        let mut zones = Vec::with_capacity(proved_batch.batch.updates.len());
+
+        for update in &proved_batch.batch.updates {
+            let ledger_proof = proved_batch
+                .ledger_proofs
+                .get(&update.zone_id)
+                .ok_or(Error::MissingLedgerPublic(update.zone_id))?;
+            let stf_proof = proved_batch
+                .stf_proofs
+                .get(&update.zone_id)
+                .ok_or(Error::MissingStf(update.zone_id))?;
+            check_stf_system(update.zone_id, stf_proof.system)?;
+
+            env.add_assumption(ledger_proof.risc0_receipt.clone());
+            env.add_assumption(stf_proof.risc0_receipt.clone());
+
+            zones.push(BatchZoneWitness {
+                update: update.clone(),
+                ledger: ledger_proof.public(),
+                stf_system: stf_proof.system,
+                stf_image: stf_proof.risc0_id,
+            });
+        }
+
+        let witness = BatchProofPrivate { zones };
+        let env = env
+            .write(&witness)
+            .map_err(|_| Error::Risc0ProofFailed)?
+            .build()
+            .map_err(|_| Error::Risc0ProofFailed)?;
+
+        let receipt = ctx.prove(
+            "batch",
+            risc0_images::BATCH_IMAGE_ID,
+            risc0_images::BATCH_ELF,
+            &witness,
+            env,
+        )?;
+
+        Ok(Self {
+            risc0_receipt: receipt,
+        })
+    }
+
+    /// Run the batch guest without proving it, the ledger and stf proofs of
+    /// each zone are assumed. `stfs` holds the proof system and image of the
+    /// stf of each zone, a zone missing from either fails the execution.
+    pub fn execute(
+        batch: &BatchProofPublic,
+        ledgers: &BTreeMap<ZoneId, LedgerProofPublic>,
+        stfs: &BTreeMap<ZoneId, (ProofSystem, [u8; 32])>,
+    ) -> Result<Executed<BatchProofPublic>> {
+        let mut env = risc0_zkvm::ExecutorEnv::builder();
+        let mut zones = Vec::with_capacity(batch.updates.len());
+
+        for update in &batch.updates {
+            let ledger = ledgers
+                .get(&update.zone_id)
+                .ok_or(Error::MissingLedgerPublic(update.zone_id))?;
+            let (stf_system, stf_image) = *stfs
+                .get(&update.zone_id)
+                .ok_or(Error::MissingStf(update.zone_id))?;
+            check_stf_system(update.zone_id, stf_system)?;
+
+            env.add_assumption(assumed_claim(risc0_images::LEDGER_IMAGE_ID, ledger));
+            env.add_assumption(assumed_claim(
+                stf_image,
+                &StfPublic {
+                    old: update.old,
+                    new: update.new,
+                },
+            ));
+
+            zones.push(BatchZoneWitness {
+                update: update.clone(),
+                ledger: ledger.clone(),
+                stf_system,
+                stf_image,
+            });
+        }
+
+        let env = env
+            .write(&BatchProofPrivate { zones })
+            .map_err(|_| Error::Risc0ExecutionFailed)?
+            .build()
+            .map_err(|_| Error::Risc0ExecutionFailed)?;
+
+        execute("batch", env, risc0_images::BATCH_ELF)
+    }
+
+    /// Panics on a malformed journal, use [`Verifier::batch`] on untrusted proofs.
+    pub fn public(&self) -> BatchProofPublic {
+        self.risc0_receipt.journal.decode().unwrap()
+    }
+
+    pub fn verify(&self) -> bool {
+        Verifier::default().batch(self).is_ok()
+    }
+}
+
+/// The batch guest verifies the stf proofs as risc0 assumptions, which only
+/// resolve against succinct receipts, not groth16 ones.
+fn check_stf_system(zone_id: ZoneId, system: ProofSystem) -> Result<()> {
+    match system {
+        ProofSystem::Risc0 => Ok(()),
+        ProofSystem::Groth16 => Err(Error::UnbatchableStf { zone_id, system }),
+    }
+}
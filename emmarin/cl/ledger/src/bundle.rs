@@ -1,7 +1,10 @@
-use crate::tx::ProvedTx;
-use cl::crust::{Bundle, BundleWitness};
-
-use hex::FromHex;
+use crate::{
+    error::{Error, Result},
+    prover::{assumed_claim, execute, Executed, ProverContext},
+    tx::ProvedTx,
+    verifier::Verifier,
+};
+use cl::crust::{Bundle, BundleWitness, OutputWitness, Tx};
 
 #[derive(Debug, Clone)]
 pub struct ProvedBundle {
@@ -9,50 +12,67 @@ pub struct ProvedBundle {
 }
 
 impl ProvedBundle {
-    pub fn prove(txs: Vec<ProvedTx>) -> Self {
+    /// `fee_outputs` open the outputs of `txs` claiming the fees for the
+    /// bundle builder, see [`BundleWitness::fee_outputs`].
+    pub fn prove(
+        ctx: &ProverContext,
+        txs: Vec<ProvedTx>,
+        fee_outputs: Vec<OutputWitness>,
+    ) -> Result<Self> {
         //show that all ptx's are individually valid, and balance to 0
         let mut env = risc0_zkvm::ExecutorEnv::builder();
 
-        let bundle = BundleWitness {
-            txs: txs.iter().map(|tx| tx.public()).collect(),
-        };
+        let bundle = BundleWitness::new(txs.iter().map(|tx| tx.public()).collect())
+            .with_fee_outputs(fee_outputs);
 
         for proved_tx in txs {
             env.add_assumption(proved_tx.risc0_receipt);
         }
 
-        let env = env.write(&bundle).unwrap().build().unwrap();
+        let env = env
+            .write(&bundle)
+            .map_err(|_| Error::Risc0ProofFailed)?
+            .build()
+            .map_err(|_| Error::Risc0ProofFailed)?;
 
-        let prover = risc0_zkvm::default_prover();
+        let receipt = ctx.prove(
+            "bundle",
+            risc0_images::BUNDLE_IMAGE_ID,
+            risc0_images::BUNDLE_ELF,
+            &bundle,
+            env,
+        )?;
 
-        let start_t = std::time::Instant::now();
+        Ok(Self {
+            risc0_receipt: receipt,
+        })
+    }
 
-        let opts = risc0_zkvm::ProverOpts::succinct();
-        let prove_info = prover
-            .prove_with_opts(env, risc0_images::BUNDLE_ELF, &opts)
-            .unwrap();
+    /// Run the bundle guest over the tx publics without proving it, the tx
+    /// proofs are assumed.
+    pub fn execute(txs: Vec<Tx>, fee_outputs: Vec<OutputWitness>) -> Result<Executed<Bundle>> {
+        let mut env = risc0_zkvm::ExecutorEnv::builder();
 
-        println!(
-            "STARK 'bundle' prover time: {:.2?}, user_cycles: {}, total_cycles: {}",
-            start_t.elapsed(),
-            prove_info.stats.user_cycles,
-            prove_info.stats.total_cycles
-        );
+        for tx in &txs {
+            env.add_assumption(assumed_claim(risc0_images::TX_IMAGE_ID, tx));
+        }
 
-        let receipt = prove_info.receipt;
+        let bundle = BundleWitness::new(txs).with_fee_outputs(fee_outputs);
+        let env = env
+            .write(&bundle)
+            .map_err(|_| Error::Risc0ExecutionFailed)?
+            .build()
+            .map_err(|_| Error::Risc0ExecutionFailed)?;
 
-        Self {
-            risc0_receipt: receipt,
-        }
+        execute("bundle", env, risc0_images::BUNDLE_ELF)
     }
 
+    /// Panics on a malformed journal, use [`Verifier::bundle`] on untrusted proofs.
     pub fn public(&self) -> Bundle {
         self.risc0_receipt.journal.decode().unwrap()
     }
 
     pub fn verify(&self) -> bool {
-        self.risc0_receipt
-            .verify(<[u8; 32]>::from_hex(risc0_images::BUNDLE_ID).unwrap())
-            .is_ok()
+        Verifier::default().bundle(self).is_ok()
     }
 }
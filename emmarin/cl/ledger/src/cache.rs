@@ -0,0 +1,73 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use cl::{Digest, Hash};
+use risc0_zkvm::{ProverOpts, Receipt, VerifierContext};
+use serde::Serialize;
+
+/// Identifies a proof by the guest, its input and the options it was proven with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CacheKey(pub [u8; 32]);
+
+impl CacheKey {
+    /// `witness` must determine everything the guest reads, including the
+    /// claims of the receipts it composes.
+    pub fn new(image_id: [u8; 32], witness: &impl Serialize, opts: &ProverOpts) -> Self {
+        let mut hasher = Hash::new();
+        hasher.update(b"NOMOS_RECEIPT_CACHE");
+        hasher.update(image_id);
+        hasher.update(cl::serialize(witness));
+        hasher.update(cl::serialize(opts));
+        Self(hasher.finalize().into())
+    }
+}
+
+/// Content addressed receipt store on local disk.
+#[derive(Debug, Clone)]
+pub struct ReceiptCache {
+    dir: PathBuf,
+}
+
+impl ReceiptCache {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.receipt", hex::encode(key.0)))
+    }
+
+    /// The receipt stored under `key`, if it is still a valid proof of the guest.
+    ///
+    /// Unreadable or invalid entries are treated as missing and get replaced on
+    /// the next `put`.
+    pub fn get(&self, key: &CacheKey, image_id: [u8; 32], opts: &ProverOpts) -> Option<Receipt> {
+        let bytes = fs::read(self.path(key)).ok()?;
+        let receipt: Receipt = bincode::deserialize(&bytes).ok()?;
+
+        let verifier = VerifierContext::default().with_dev_mode(opts.dev_mode());
+        receipt
+            .verify_with_context(&verifier, image_id)
+            .ok()
+            .map(|_| receipt)
+    }
+
+    pub fn put(&self, key: &CacheKey, receipt: &Receipt) -> io::Result<()> {
+        let path = self.path(key);
+        // write to a temporary file first so concurrent readers never see a
+        // partially written receipt
+        static TMP_ID: AtomicU64 = AtomicU64::new(0);
+        let tmp = path.with_extension(format!(
+            "tmp-{}-{}",
+            std::process::id(),
+            TMP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp, cl::serialize(receipt))?;
+        fs::rename(tmp, path)
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cl::mantle::{
+    block::{BlockHash, BlockHeader},
+    ZoneId, ZoneState,
+};
+
+use crate::{batch::ProvedBatch, verifier::Verifier};
+
+pub use crate::error::ChainError;
+
+/// A mantle block along with the proof of its batch of zone updates.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub proof: ProvedBatch,
+}
+
+impl Block {
+    pub fn hash(&self) -> BlockHash {
+        self.header.hash()
+    }
+}
+
+/// How the head of the chain moved after inserting a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainUpdate {
+    /// The block extends the head
+    Extended,
+    /// The block is stored on a fork no longer than the head
+    Forked,
+    /// The fork of the block became the head. Zones roll back through the
+    /// `reverted` blocks, newest first, then apply the `applied` blocks,
+    /// oldest first.
+    Reorg {
+        reverted: Vec<BlockHash>,
+        applied: Vec<BlockHash>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct ChainEntry {
+    parent: BlockHash,
+    height: u64,
+    /// Zone states once the block is applied
+    zones: BTreeMap<ZoneId, ZoneState>,
+    /// None for the genesis
+    block: Option<Block>,
+}
+
+/// Host side store of mantle blocks, tracking the zone states at every block
+/// so that forks can be followed and reorgs roll zones back.
+///
+/// The head is the highest block, the first block seen wins ties.
+#[derive(Debug, Clone)]
+pub struct ChainStore {
+    verifier: Verifier,
+    entries: BTreeMap<BlockHash, ChainEntry>,
+    head: BlockHash,
+}
+
+impl ChainStore {
+    pub fn new(verifier: Verifier, genesis: BTreeMap<ZoneId, ZoneState>) -> Self {
+        let entry = ChainEntry {
+            parent: BlockHash::GENESIS,
+            height: 0,
+            zones: genesis,
+            block: None,
+        };
+        Self {
+            verifier,
+            entries: BTreeMap::from_iter([(BlockHash::GENESIS, entry)]),
+            head: BlockHash::GENESIS,
+        }
+    }
+
+    pub fn head(&self) -> BlockHash {
+        self.head
+    }
+
+    /// Number of blocks from the genesis to the head
+    pub fn height(&self) -> u64 {
+        self.entries[&self.head].height
+    }
+
+    pub fn contains(&self, hash: &BlockHash) -> bool {
+        self.entries.contains_key(hash)
+    }
+
+    pub fn block(&self, hash: &BlockHash) -> Option<&Block> {
+        self.entries.get(hash)?.block.as_ref()
+    }
+
+    /// Zone states at the head
+    pub fn zone_states(&self) -> &BTreeMap<ZoneId, ZoneState> {
+        &self.entries[&self.head].zones
+    }
+
+    pub fn zone_state(&self, zone_id: &ZoneId) -> Option<&ZoneState> {
+        self.zone_states().get(zone_id)
+    }
+
+    /// Zone states once the block `hash` is applied
+    pub fn zone_states_at(&self, hash: &BlockHash) -> Option<&BTreeMap<ZoneId, ZoneState>> {
+        Some(&self.entries.get(hash)?.zones)
+    }
+
+    /// Validate `block` against the zone states of its parent and store it,
+    /// switching the head if its fork becomes the longest.
+    pub fn insert(&mut self, block: Block) -> Result<ChainUpdate, ChainError> {
+        let hash = block.hash();
+        if self.contains(&hash) {
+            return Err(ChainError::DuplicateBlock(hash));
+        }
+        let header = &block.header;
+        let parent = self
+            .entries
+            .get(&header.parent)
+            .ok_or(ChainError::UnknownParent(header.parent))?;
+
+        if let Some(parent_block) = &parent.block {
+            if header.slot <= parent_block.header.slot {
+                return Err(ChainError::SlotNotIncreasing {
+                    slot: header.slot,
+                    parent_slot: parent_block.header.slot,
+                });
+            }
+        }
+
+        let batch = self
+            .verifier
+            .batch(&block.proof)
+            .map_err(ChainError::InvalidProof)?;
+        if batch != header.batch {
+            return Err(ChainError::BatchMismatch);
+        }
+
+        let mut zones = parent.zones.clone();
+        let mut updated = BTreeSet::new();
+        for update in &header.batch.updates {
+            if !updated.insert(update.zone_id) {
+                return Err(ChainError::DuplicateZone(update.zone_id));
+            }
+            let state = zones
+                .get_mut(&update.zone_id)
+                .ok_or(ChainError::UnknownZone(update.zone_id))?;
+            if *state != update.old {
+                return Err(ChainError::ZoneStateMismatch(update.zone_id));
+            }
+            *state = update.new;
+        }
+
+        let entry = ChainEntry {
+            parent: header.parent,
+            height: parent.height + 1,
+            zones,
+            block: Some(block),
+        };
+        let height = entry.height;
+        let parent = entry.parent;
+        self.entries.insert(hash, entry);
+
+        if height <= self.height() {
+            return Ok(ChainUpdate::Forked);
+        }
+        let old_head = std::mem::replace(&mut self.head, hash);
+        if parent == old_head {
+            return Ok(ChainUpdate::Extended);
+        }
+
+        let (reverted, mut applied) = self.fork_paths(old_head, hash);
+        applied.reverse();
+        Ok(ChainUpdate::Reorg { reverted, applied })
+    }
+
+    /// Blocks from `a` and from `b` down to, excluding, their common ancestor,
+    /// newest first.
+    fn fork_paths(&self, mut a: BlockHash, mut b: BlockHash) -> (Vec<BlockHash>, Vec<BlockHash>) {
+        let (mut from_a, mut from_b) = (vec![], vec![]);
+        while self.entries[&a].height > self.entries[&b].height {
+            from_a.push(a);
+            a = self.entries[&a].parent;
+        }
+        while self.entries[&b].height > self.entries[&a].height {
+            from_b.push(b);
+            b = self.entries[&b].parent;
+        }
+        while a != b {
+            from_a.push(a);
+            from_b.push(b);
+            a = self.entries[&a].parent;
+            b = self.entries[&b].parent;
+        }
+        (from_a, from_b)
+    }
+}
@@ -0,0 +1,203 @@
+use risc0_zkvm::{InnerReceipt, Receipt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    batch::ProvedBatch, bundle::ProvedBundle, ledger::ProvedLedgerTransition, stf::StfProof,
+    tx::ProvedTx,
+};
+
+pub use crate::error::EnvelopeError;
+
+/// Version of the envelope wire format, bumped on any change to its layout.
+pub const ENVELOPE_VERSION: u16 = 1;
+
+/// The statement committed to in the journal of the enveloped receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalKind {
+    Tx,
+    Bundle,
+    Ledger,
+    Stf,
+    Batch,
+}
+
+/// The kind of seal carried by the enveloped receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SealKind {
+    Composite,
+    Succinct,
+    Groth16,
+    /// Dev mode receipt, carries no proof
+    Fake,
+}
+
+impl SealKind {
+    pub fn of(receipt: &Receipt) -> Option<Self> {
+        match receipt.inner {
+            InnerReceipt::Composite(_) => Some(Self::Composite),
+            InnerReceipt::Succinct(_) => Some(Self::Succinct),
+            InnerReceipt::Groth16(_) => Some(Self::Groth16),
+            InnerReceipt::Fake(_) => Some(Self::Fake),
+            _ => None,
+        }
+    }
+}
+
+/// Self describing wire format for the proofs of this crate.
+///
+/// Decoding an envelope only checks that it is well formed and describes the
+/// expected proof, the receipt itself still has to be verified.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofEnvelope {
+    pub image_id: [u8; 32],
+    pub journal: JournalKind,
+    pub seal: SealKind,
+    /// bincode encoded `risc0_zkvm::Receipt`
+    pub receipt: Vec<u8>,
+}
+
+impl ProofEnvelope {
+    /// Fails on receipts with a seal this version of the envelope can not
+    /// describe.
+    pub fn new(
+        image_id: [u8; 32],
+        journal: JournalKind,
+        receipt: &Receipt,
+    ) -> Result<Self, EnvelopeError> {
+        Ok(Self {
+            image_id,
+            journal,
+            seal: SealKind::of(receipt).ok_or(EnvelopeError::UnsupportedSeal)?,
+            receipt: cl::serialize(receipt),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = ENVELOPE_VERSION.to_le_bytes().to_vec();
+        bytes.extend(cl::serialize(self));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        let (version, body) = bytes
+            .split_first_chunk::<2>()
+            .ok_or(EnvelopeError::Truncated)?;
+        let version = u16::from_le_bytes(*version);
+        if version != ENVELOPE_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+        Ok(bincode::deserialize(body)?)
+    }
+
+    /// Decode the enveloped receipt, checking it matches the envelope header.
+    pub fn receipt(&self) -> Result<Receipt, EnvelopeError> {
+        let receipt: Receipt = bincode::deserialize(&self.receipt)?;
+        let seal = SealKind::of(&receipt);
+        if seal != Some(self.seal) {
+            return Err(EnvelopeError::SealMismatch {
+                expected: self.seal,
+                actual: seal,
+            });
+        }
+        Ok(receipt)
+    }
+}
+
+/// A proof that can be sent over the wire in a [`ProofEnvelope`].
+pub trait Enveloped: Sized {
+    const JOURNAL: JournalKind;
+
+    fn image_id(&self) -> [u8; 32];
+
+    fn receipt(&self) -> &Receipt;
+
+    /// Rebuild the proof from an envelope whose header was checked against
+    /// `JOURNAL`.
+    fn from_envelope_receipt(image_id: [u8; 32], receipt: Receipt) -> Result<Self, EnvelopeError>;
+
+    fn to_envelope(&self) -> Result<ProofEnvelope, EnvelopeError> {
+        ProofEnvelope::new(self.image_id(), Self::JOURNAL, self.receipt())
+    }
+
+    fn from_envelope(envelope: &ProofEnvelope) -> Result<Self, EnvelopeError> {
+        if envelope.journal != Self::JOURNAL {
+            return Err(EnvelopeError::JournalMismatch {
+                expected: Self::JOURNAL,
+                actual: envelope.journal,
+            });
+        }
+        Self::from_envelope_receipt(envelope.image_id, envelope.receipt()?)
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, EnvelopeError> {
+        Ok(self.to_envelope()?.to_bytes())
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, EnvelopeError> {
+        Self::from_envelope(&ProofEnvelope::from_bytes(bytes)?)
+    }
+}
+
+fn expect_image(expected: [u8; 32], actual: [u8; 32]) -> Result<(), EnvelopeError> {
+    if expected != actual {
+        return Err(EnvelopeError::ImageMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+macro_rules! impl_enveloped {
+    ($name:ident, $journal:ident, $image_id:path) => {
+        impl Enveloped for $name {
+            const JOURNAL: JournalKind = JournalKind::$journal;
+
+            fn image_id(&self) -> [u8; 32] {
+                $image_id
+            }
+
+            fn receipt(&self) -> &Receipt {
+                &self.risc0_receipt
+            }
+
+            fn from_envelope_receipt(
+                image_id: [u8; 32],
+                risc0_receipt: Receipt,
+            ) -> Result<Self, EnvelopeError> {
+                expect_image($image_id, image_id)?;
+                Ok(Self { risc0_receipt })
+            }
+        }
+    };
+}
+
+impl_enveloped!(ProvedTx, Tx, risc0_images::TX_IMAGE_ID);
+impl_enveloped!(ProvedBundle, Bundle, risc0_images::BUNDLE_IMAGE_ID);
+impl_enveloped!(
+    ProvedLedgerTransition,
+    Ledger,
+    risc0_images::LEDGER_IMAGE_ID
+);
+impl_enveloped!(ProvedBatch, Batch, risc0_images::BATCH_IMAGE_ID);
+
+// each zone runs its own stf, so any image id is accepted here. The envelope
+// carries no proof system, groth16 seals are taken to be groth16 stfs.
+impl Enveloped for StfProof {
+    const JOURNAL: JournalKind = JournalKind::Stf;
+
+    fn image_id(&self) -> [u8; 32] {
+        self.risc0_id
+    }
+
+    fn receipt(&self) -> &Receipt {
+        &self.risc0_receipt
+    }
+
+    fn from_envelope_receipt(
+        image_id: [u8; 32],
+        risc0_receipt: Receipt,
+    ) -> Result<Self, EnvelopeError> {
+        Ok(match SealKind::of(&risc0_receipt) {
+            Some(SealKind::Groth16) => StfProof::from_groth16(image_id, risc0_receipt),
+            _ => StfProof::from_risc0(image_id, risc0_receipt),
+        })
+    }
+}
@@ -1,3 +1,11 @@
+use cl::{
+    crust::{BundleRoot, Nullifier},
+    ds::indexed::DuplicateNullifier,
+    mantle::{block::BlockHash, sync::SyncError, Domain, ProofSystem, ZoneId},
+};
+
+use crate::envelope::{JournalKind, SealKind};
+use ledger_proof_statements::ledger::SyncLogMismatch;
 use thiserror::Error;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -8,4 +16,262 @@ pub enum Error {
     Risc0Serde(#[from] risc0_zkvm::serde::Error),
     #[error("risc0 failed to prove execution of the zkvm")]
     Risc0ProofFailed,
+    #[error("risc0 failed to execute the zkvm guest")]
+    Risc0ExecutionFailed,
+    #[error("nullifier {} is spent twice in the same ledger transition", hex::encode(.0))]
+    DuplicateNullifier(Nullifier),
+    #[error(
+        "bundle {} is bound to domain {} but the ledger is in domain {}",
+        hex::encode(.bundle.0),
+        hex::encode(.actual),
+        hex::encode(.expected)
+    )]
+    DomainMismatch {
+        bundle: BundleRoot,
+        expected: Domain,
+        actual: Domain,
+    },
+    #[error("bundle {} is not valid at bundle height {height}", hex::encode(.bundle.0))]
+    OutsideValidity { bundle: BundleRoot, height: u64 },
+    #[error(
+        "bundle {} migrates a note through zone {} without settling it there",
+        hex::encode(.bundle.0),
+        hex::encode(.zone_id)
+    )]
+    UnsettledMigration { bundle: BundleRoot, zone_id: ZoneId },
+    #[error(
+        "cross zone bundle {} is settled or acknowledged out of order",
+        hex::encode(.0.bundle().0)
+    )]
+    InvalidSync(SyncError),
+    #[error("zone {} is transitioned more than once in the pipeline", hex::encode(.0))]
+    DuplicateZone(ZoneId),
+    #[error(
+        "zone {} settles bundle {bundle} which is not part of the pipeline",
+        hex::encode(.zone_id)
+    )]
+    UnknownBundle { zone_id: ZoneId, bundle: usize },
+    #[error("a prover worker of the pipeline panicked")]
+    WorkerPanicked,
+    #[error(
+        "bundle {} does not involve zone {}",
+        hex::encode(.bundle.0),
+        hex::encode(.zone_id)
+    )]
+    ForeignBundle { bundle: BundleRoot, zone_id: ZoneId },
+    #[error("missing ledger proof for zone {} of the batch", hex::encode(.0))]
+    MissingLedgerPublic(ZoneId),
+    #[error("missing stf proof for zone {} of the batch", hex::encode(.0))]
+    MissingStf(ZoneId),
+    #[error(
+        "zone {} proves its stf with {system:?}, only risc0 stfs can be batched",
+        hex::encode(.zone_id)
+    )]
+    UnbatchableStf {
+        zone_id: ZoneId,
+        system: ProofSystem,
+    },
+}
+
+impl From<DuplicateNullifier> for Error {
+    fn from(DuplicateNullifier(nf): DuplicateNullifier) -> Self {
+        Self::DuplicateNullifier(nf)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum BatchVerifyError {
+    #[error("zone {} appears more than once in the batch", hex::encode(.0))]
+    DuplicateZone(ZoneId),
+    #[error("missing ledger proof for zone {}", hex::encode(.0))]
+    MissingLedgerProof(ZoneId),
+    #[error("missing stf proof for zone {}", hex::encode(.0))]
+    MissingStfProof(ZoneId),
+    #[error("ledger proof for zone {} which is not part of the batch", hex::encode(.0))]
+    UnexpectedLedgerProof(ZoneId),
+    #[error("stf proof for zone {} which is not part of the batch", hex::encode(.0))]
+    UnexpectedStfProof(ZoneId),
+    #[error("invalid ledger proof for zone {}", hex::encode(.0))]
+    InvalidLedgerProof(ZoneId, #[source] VerifyError),
+    #[error("invalid stf proof for zone {}", hex::encode(.0))]
+    InvalidStfProof(ZoneId, #[source] VerifyError),
+    #[error(
+        "ledger proof filed under zone {} proves zone {}",
+        hex::encode(.expected),
+        hex::encode(.actual)
+    )]
+    LedgerZoneMismatch { expected: ZoneId, actual: ZoneId },
+    #[error("ledger proof for zone {} does not match the old zone ledger", hex::encode(.0))]
+    OldLedgerMismatch(ZoneId),
+    #[error("ledger proof for zone {} does not match the new zone ledger", hex::encode(.0))]
+    NewLedgerMismatch(ZoneId),
+    #[error(
+        "stf proof for zone {} was produced by stf {} but the zone runs {}",
+        hex::encode(.zone_id),
+        hex::encode(.actual),
+        hex::encode(.expected)
+    )]
+    StfMismatch {
+        zone_id: ZoneId,
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+    #[error("stf proof for zone {} does not match the zone update", hex::encode(.0))]
+    StfStateMismatch(ZoneId),
+    #[error(
+        "bundle {} was expected to be settled by {} zone(s) but {} settled it",
+        hex::encode(.bundle.0),
+        .expected.len(),
+        .actual.len()
+    )]
+    SyncLogMismatch {
+        bundle: BundleRoot,
+        expected: Vec<ZoneId>,
+        actual: Vec<ZoneId>,
+    },
+}
+
+impl From<SyncLogMismatch> for BatchVerifyError {
+    fn from(
+        SyncLogMismatch {
+            bundle,
+            expected,
+            actual,
+        }: SyncLogMismatch,
+    ) -> Self {
+        Self::SyncLogMismatch {
+            bundle,
+            expected,
+            actual,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("unsupported snapshot version {0}")]
+    UnsupportedVersion(u16),
+    #[error("snapshot is truncated")]
+    Truncated,
+    #[error("failed to decode snapshot")]
+    Decode(#[from] bincode::Error),
+    #[error("zone ledger holds a malformed MMR")]
+    MalformedMmr,
+    #[error("zone ledger does not match the expected ledger commitment")]
+    LedgerMismatch,
+    #[error("archived ledger history does not match the zone ledger")]
+    ArchiveMismatch,
+    #[error("zone state does not commit to the zone ledger")]
+    ZoneStateMismatch,
+    #[error("delta starts at bundles height {expected} but the ledger is at {actual}")]
+    HeightMismatch { expected: u64, actual: u64 },
+    #[error(
+        "expected a snapshot of zone {} but got zone {}",
+        hex::encode(.expected),
+        hex::encode(.actual)
+    )]
+    ZoneMismatch { expected: ZoneId, actual: ZoneId },
+    #[error("delta holds bundle {} which does not involve the zone", hex::encode(.0.0))]
+    ForeignBundle(BundleRoot),
+    #[error("delta spends nullifier {} twice in the same transition", hex::encode(.0))]
+    DuplicateNullifier(Nullifier),
+    #[error("delta spends nullifier {} which is already spent", hex::encode(.0))]
+    SpentNullifier(Nullifier),
+    #[error(
+        "delta settles or acknowledges cross zone bundle {} out of order",
+        hex::encode(.0.0)
+    )]
+    InvalidSync(BundleRoot),
+}
+
+#[derive(Error, Debug)]
+pub enum ChainError {
+    #[error("block {} is already in the chain", .0.hex())]
+    DuplicateBlock(BlockHash),
+    #[error("parent block {} is unknown", .0.hex())]
+    UnknownParent(BlockHash),
+    #[error("block slot {slot} does not follow its parent slot {parent_slot}")]
+    SlotNotIncreasing { slot: u64, parent_slot: u64 },
+    #[error("invalid batch proof")]
+    InvalidProof(#[source] VerifyError),
+    #[error("batch proof does not prove the batch of the block")]
+    BatchMismatch,
+    #[error("zone {} is updated more than once in the block", hex::encode(.0))]
+    DuplicateZone(ZoneId),
+    #[error("zone {} is unknown to the chain", hex::encode(.0))]
+    UnknownZone(ZoneId),
+    #[error("update of zone {} does not start from its state at the parent block", hex::encode(.0))]
+    ZoneStateMismatch(ZoneId),
+}
+
+#[derive(Error, Debug)]
+pub enum EnvelopeError {
+    #[error("unsupported envelope version {0}")]
+    UnsupportedVersion(u16),
+    #[error("envelope is truncated")]
+    Truncated,
+    #[error("failed to decode envelope")]
+    Decode(#[from] bincode::Error),
+    #[error("the receipt has a seal the envelope can not describe")]
+    UnsupportedSeal,
+    #[error("expected a {expected:?} proof but the envelope holds a {actual:?} proof")]
+    JournalMismatch {
+        expected: JournalKind,
+        actual: JournalKind,
+    },
+    #[error(
+        "expected image {} but the envelope holds image {}",
+        hex::encode(.expected),
+        hex::encode(.actual)
+    )]
+    ImageMismatch {
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+    #[error("envelope declares a {expected:?} receipt but holds {actual:?}")]
+    SealMismatch {
+        expected: SealKind,
+        actual: Option<SealKind>,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum VerifyError {
+    #[error(
+        "expected a proof of image {} but the receipt proves image {}",
+        hex::encode(.expected),
+        hex::encode(.actual)
+    )]
+    ImageMismatch {
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+    #[error("receipt failed to verify")]
+    Seal(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("failed to decode receipt journal")]
+    Journal(#[source] risc0_zkvm::serde::Error),
+    #[error(
+        "expected a proof of stf {} but the proof is for stf {}",
+        hex::encode(.expected),
+        hex::encode(.actual)
+    )]
+    StfMismatch {
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+    #[error("expected a {expected:?} receipt but got {actual:?}")]
+    SealKindMismatch {
+        expected: SealKind,
+        actual: Option<SealKind>,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum SnarkError {
+    #[error("expected a groth16 receipt but got {0:?}")]
+    NotGroth16(Option<SealKind>),
+    #[error("malformed verifier calldata")]
+    MalformedCalldata,
+    #[error("failed to decode verifying key")]
+    Decode(#[source] bincode::Error),
 }
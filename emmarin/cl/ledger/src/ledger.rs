@@ -2,10 +2,19 @@ use std::collections::BTreeMap;
 
 use ledger_proof_statements::ledger::{LedgerBundleWitness, LedgerProofPrivate, LedgerProofPublic};
 
-use crate::bundle::ProvedBundle;
-use cl::mantle::{ledger::LedgerState, zone::ZoneId};
-
-use hex::FromHex;
+use crate::{
+    bundle::ProvedBundle,
+    error::{Error, Result},
+    prover::{assumed_claim, execute, Executed, ProverContext},
+    verifier::Verifier,
+};
+use cl::{
+    crust::{Bundle, BundleRoot},
+    mantle::{
+        ledger::{LedgerState, TransitionDelta},
+        zone::ZoneId,
+    },
+};
 
 #[derive(Debug, Clone)]
 pub struct ProvedLedgerTransition {
@@ -13,90 +22,171 @@ pub struct ProvedLedgerTransition {
 }
 
 impl ProvedLedgerTransition {
-    pub fn prove(ledger: &mut LedgerState, zone_id: ZoneId, bundles: Vec<ProvedBundle>) -> Self {
+    /// Prove the transition of the `zone_id` ledger through `bundles`.
+    ///
+    /// `synced` lists the cross zone bundles that every zone involved
+    /// acknowledges as settled in the batch this transition is part of.
+    pub fn prove(
+        ctx: &ProverContext,
+        ledger: &mut LedgerState,
+        zone_id: ZoneId,
+        bundles: Vec<ProvedBundle>,
+        synced: &[BundleRoot],
+    ) -> Result<Self> {
         let mut env = risc0_zkvm::ExecutorEnv::builder();
-        let mut w_bundles = Vec::new();
-        let mut nullifiers = Vec::new();
-        // prepare the sparse merkle tree nullifier proofs
         for proved_bundle in &bundles {
             env.add_assumption(proved_bundle.risc0_receipt.clone());
-
-            let bundle = proved_bundle.public();
-
-            let zone_ledger_updates = bundle
-                .updates
-                .get(&zone_id)
-                .expect("why are we proving this bundle for this zone if it's not involved?");
-
-            let mut cm_root_proofs = BTreeMap::new();
-            for zone_ledger_update in zone_ledger_updates {
-                cm_root_proofs.extend(
-                    zone_ledger_update
-                        .frontier_nodes
-                        .iter()
-                        .map(|root| (root.root, vec![])),
-                );
-                nullifiers.extend(zone_ledger_update.inputs.clone());
-            }
-
-            let ledger_bundle = LedgerBundleWitness {
-                bundle,
-                cm_root_proofs,
-            };
-
-            w_bundles.push(ledger_bundle);
         }
 
-        let witness = LedgerProofPrivate {
-            bundles: w_bundles,
-            ledger: ledger.to_witness(),
-            id: zone_id,
-            nf_proofs: ledger.add_nullifiers(nullifiers),
-        };
-
-        for bundle in &witness.bundles {
-            let updates = bundle
-                .bundle
-                .updates
-                .get(&zone_id)
-                .expect("should have a bundle from the zone we are proofing for");
-
-            for update in updates {
-                for (cm, _data) in &update.outputs {
-                    ledger.add_commitment(cm);
-                }
-            }
+        let bundles = bundles.iter().map(|b| b.public()).collect();
+        // the host ledger only moves once the transition is proven
+        let mut next = ledger.clone();
+        let witness = Self::witness(&mut next, zone_id, bundles, synced)?;
+        witness
+            .write(&mut env)
+            .map_err(|_| Error::Risc0ProofFailed)?;
+        let env = env.build().map_err(|_| Error::Risc0ProofFailed)?;
+
+        // the nullifier proofs are determined by the old ledger and the bundles
+        let cache_key = (
+            &witness.ledger,
+            witness.id,
+            &witness.bundles,
+            &witness.synced,
+        );
+        let receipt = ctx.prove(
+            "ledger",
+            risc0_images::LEDGER_IMAGE_ID,
+            risc0_images::LEDGER_ELF,
+            &cache_key,
+            env,
+        )?;
+
+        *ledger = next;
+        Ok(Self {
+            risc0_receipt: receipt,
+        })
+    }
 
-            ledger.add_bundle(bundle.bundle.root);
-        }
+    /// Run the ledger guest over the bundle publics without proving it, the
+    /// bundle proofs are assumed.
+    ///
+    /// The host ledger is updated the same way as in [`Self::prove`], and left
+    /// untouched if the guest rejects the transition.
+    pub fn execute(
+        ledger: &mut LedgerState,
+        zone_id: ZoneId,
+        bundles: Vec<Bundle>,
+        synced: &[BundleRoot],
+    ) -> Result<Executed<LedgerProofPublic>> {
+        let mut next = ledger.clone();
+        let witness = Self::witness(&mut next, zone_id, bundles, synced)?;
+        let executed = Self::execute_witness(&witness)?;
+        *ledger = next;
+        Ok(executed)
+    }
 
-        witness.write(&mut env);
-        let env = env.build().unwrap();
+    /// Run the ledger guest over `witness` as is, skipping the checks the host
+    /// makes while building it, to check the guest enforces them as well.
+    pub fn execute_witness(witness: &LedgerProofPrivate) -> Result<Executed<LedgerProofPublic>> {
+        let mut env = risc0_zkvm::ExecutorEnv::builder();
+        for w in &witness.bundles {
+            env.add_assumption(assumed_claim(risc0_images::BUNDLE_IMAGE_ID, &w.bundle));
+        }
+        witness
+            .write(&mut env)
+            .map_err(|_| Error::Risc0ExecutionFailed)?;
+        let env = env.build().map_err(|_| Error::Risc0ExecutionFailed)?;
 
-        // Obtain the default prover.
-        let prover = risc0_zkvm::default_prover();
+        execute("ledger", env, risc0_images::LEDGER_ELF)
+    }
 
-        let start_t = std::time::Instant::now();
+    fn witness(
+        ledger: &mut LedgerState,
+        zone_id: ZoneId,
+        bundles: Vec<Bundle>,
+        synced: &[BundleRoot],
+    ) -> Result<LedgerProofPrivate> {
+        // checked before touching the ledger, the guest rejects these too
+        Self::check_bundles(ledger, zone_id, &bundles)?;
+        let delta = TransitionDelta::new(zone_id, &bundles, synced);
+        let nf_order = delta.nullifier_order()?;
+        let old_ledger = ledger.to_witness();
+        // prepare the sparse merkle tree nullifier proofs
+        let nf_proofs = delta
+            .apply_sorted(ledger, &nf_order)
+            .map_err(Error::InvalidSync)?;
+
+        let w_bundles = bundles
+            .into_iter()
+            .map(|bundle| {
+                let zone_ledger_updates = bundle
+                    .updates
+                    .get(&zone_id)
+                    .expect("why are we proving this bundle for this zone if it's not involved?");
+
+                let mut cm_root_proofs = BTreeMap::new();
+                for zone_ledger_update in zone_ledger_updates {
+                    cm_root_proofs.extend(
+                        zone_ledger_update
+                            .frontier_nodes
+                            .iter()
+                            .map(|root| (root.root, vec![])),
+                    );
+                }
 
-        // Proof information by proving the specified ELF binary.
-        // This struct contains the receipt along with statistics about execution of the guest
-        let opts = risc0_zkvm::ProverOpts::succinct();
-        let prove_info = prover
-            .prove_with_opts(env, risc0_images::LEDGER_ELF, &opts)
-            .unwrap();
+                LedgerBundleWitness {
+                    bundle,
+                    cm_root_proofs,
+                }
+            })
+            .collect();
 
-        println!(
-            "STARK 'ledger' prover time: {:.2?}, user_cycles: {}, total_cycles: {}",
-            start_t.elapsed(),
-            prove_info.stats.user_cycles,
-            prove_info.stats.total_cycles
-        );
+        Ok(LedgerProofPrivate {
+            bundles: w_bundles,
+            ledger: old_ledger,
+            id: zone_id,
+            nf_proofs,
+            nf_order,
+            synced: synced.to_vec(),
+        })
+    }
 
-        Self {
-            risc0_receipt: prove_info.receipt,
+    /// Check that each bundle involves the zone, its domain and validity window
+    /// against the bundle height it is added at, and that the zone settles its
+    /// note migrations.
+    fn check_bundles(ledger: &LedgerState, zone_id: ZoneId, bundles: &[Bundle]) -> Result<()> {
+        for (height, bundle) in (ledger.bundles.len()..).zip(bundles) {
+            if !bundle.updates.contains_key(&zone_id) {
+                return Err(Error::ForeignBundle {
+                    bundle: bundle.root,
+                    zone_id,
+                });
+            }
+            if bundle.domain != ledger.domain {
+                return Err(Error::DomainMismatch {
+                    bundle: bundle.root,
+                    expected: ledger.domain,
+                    actual: bundle.domain,
+                });
+            }
+            if !bundle.validity.contains(height) {
+                return Err(Error::OutsideValidity {
+                    bundle: bundle.root,
+                    height,
+                });
+            }
+            if !bundle.migrations_settled_by(zone_id) {
+                return Err(Error::UnsettledMigration {
+                    bundle: bundle.root,
+                    zone_id,
+                });
+            }
         }
+        Ok(())
     }
 
+    /// Panics on a malformed journal, use [`Verifier::ledger`] on untrusted proofs.
     pub fn public(&self) -> LedgerProofPublic {
         self.risc0_receipt
             .journal
@@ -105,8 +195,6 @@ impl ProvedLedgerTransition {
     }
 
     pub fn verify(&self) -> bool {
-        self.risc0_receipt
-            .verify(<[u8; 32]>::from_hex(risc0_images::LEDGER_ID).unwrap())
-            .is_ok()
+        Verifier::default().ledger(self).is_ok()
     }
 }
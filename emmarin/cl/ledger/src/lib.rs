@@ -1,7 +1,18 @@
+pub mod batch;
 pub mod bundle;
+pub mod cache;
+pub mod chain;
 pub mod covenant;
+pub mod envelope;
 pub mod error;
 pub mod ledger;
+pub mod pipeline;
+pub mod prover;
+pub mod snapshot;
+pub mod snark;
 pub mod stf;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 pub mod tx;
 pub mod update;
+pub mod verifier;
@@ -0,0 +1,475 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    num::NonZeroUsize,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{mpsc, Mutex},
+};
+
+use cl::{
+    crust::{BundleRoot, OutputWitness, TxWitness},
+    mantle::{ledger::LedgerState, sync::SyncLog, ZoneId},
+};
+
+use crate::{
+    bundle::ProvedBundle,
+    covenant::{SpendingCovenantProof, SupplyCovenantProof},
+    error::{Error, Result},
+    ledger::ProvedLedgerTransition,
+    prover::ProverContext,
+    tx::ProvedTx,
+};
+
+/// A tx to prove along with the covenant proofs it depends on.
+#[derive(Debug, Clone)]
+pub struct TxJob {
+    pub witness: TxWitness,
+    pub supply_covenant_proofs: Vec<SupplyCovenantProof>,
+    pub spending_covenant_proofs: Vec<SpendingCovenantProof>,
+}
+
+impl From<TxWitness> for TxJob {
+    fn from(witness: TxWitness) -> Self {
+        Self {
+            witness,
+            supply_covenant_proofs: vec![],
+            spending_covenant_proofs: vec![],
+        }
+    }
+}
+
+/// The txs of a bundle along with the openings of the outputs claiming its fees.
+#[derive(Debug, Clone)]
+pub struct BundleJob {
+    pub txs: Vec<TxJob>,
+    pub fee_outputs: Vec<OutputWitness>,
+}
+
+impl From<Vec<TxJob>> for BundleJob {
+    fn from(txs: Vec<TxJob>) -> Self {
+        Self {
+            txs,
+            fee_outputs: vec![],
+        }
+    }
+}
+
+/// A zone ledger to transition through some of the bundles of the pipeline.
+#[derive(Debug)]
+pub struct ZoneJob<'a> {
+    pub zone_id: ZoneId,
+    pub ledger: &'a mut LedgerState,
+    /// Indices of the bundles to settle, in the order they are applied
+    pub bundles: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Tx { bundle: usize, tx: usize },
+    Bundle { bundle: usize },
+    Ledger { zone_id: ZoneId },
+}
+
+/// Reported each time a proof completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub stage: Stage,
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug)]
+pub struct PipelineOutput {
+    pub bundles: Vec<ProvedBundle>,
+    pub ledgers: BTreeMap<ZoneId, ProvedLedgerTransition>,
+}
+
+enum Job<'a> {
+    Tx {
+        bundle: usize,
+        tx: usize,
+        job: Box<TxJob>,
+    },
+    Bundle {
+        bundle: usize,
+        txs: Vec<ProvedTx>,
+        fee_outputs: Vec<OutputWitness>,
+    },
+    Ledger {
+        job: ZoneJob<'a>,
+        bundles: Vec<ProvedBundle>,
+        synced: Vec<BundleRoot>,
+    },
+}
+
+enum Done {
+    Tx {
+        bundle: usize,
+        tx: usize,
+        proof: Result<ProvedTx>,
+    },
+    Bundle {
+        bundle: usize,
+        proof: Result<ProvedBundle>,
+    },
+    Ledger {
+        zone_id: ZoneId,
+        proof: Result<ProvedLedgerTransition>,
+    },
+    /// The worker panicked while running a job
+    Panicked,
+}
+
+/// Proves txs, bundles and zone ledger transitions on a pool of worker threads.
+///
+/// Bundle proofs are started as soon as their txs are proven and ledger
+/// transitions as soon as their bundles are, downstream proofs are scheduled
+/// ahead of the remaining txs. At most `workers` proofs are in flight at once,
+/// txs are handed out in bundle order as workers free up, so only the bundles
+/// being proven hold tx proofs.
+pub struct Pipeline<'a> {
+    ctx: &'a ProverContext,
+    workers: usize,
+    progress: Option<ProgressFn<'a>>,
+}
+
+type ProgressFn<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+impl<'a> Pipeline<'a> {
+    pub fn new(ctx: &'a ProverContext) -> Self {
+        let workers = std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
+        Self {
+            ctx,
+            workers,
+            progress: None,
+        }
+    }
+
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        assert!(workers > 0, "pipeline needs at least one worker");
+        self.workers = workers;
+        self
+    }
+
+    pub fn with_progress(mut self, progress: impl FnMut(&Progress) + 'a) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Prove `bundles` and transition the `zones` ledgers through them, each
+    /// zone is transitioned once.
+    ///
+    /// A cross zone bundle is acknowledged as synced when every zone it touches
+    /// settles it in this pipeline or left it pending in an earlier batch, the
+    /// zones with pending syncs wait on all the bundles to find their
+    /// counterparts.
+    ///
+    /// A failing or panicking proof fails the pipeline, the ledgers of the
+    /// zones not yet transitioned are left untouched.
+    pub fn prove(
+        mut self,
+        bundles: Vec<BundleJob>,
+        zones: Vec<ZoneJob<'_>>,
+    ) -> Result<PipelineOutput> {
+        let mut seen = BTreeSet::new();
+        for zone in &zones {
+            if !seen.insert(zone.zone_id) {
+                return Err(Error::DuplicateZone(zone.zone_id));
+            }
+            if let Some(&bundle) = zone.bundles.iter().find(|&&b| b >= bundles.len()) {
+                return Err(Error::UnknownBundle {
+                    zone_id: zone.zone_id,
+                    bundle,
+                });
+            }
+        }
+
+        let n_txs: usize = bundles.iter().map(|b| b.txs.len()).sum();
+        let total = n_txs + bundles.len() + zones.len();
+
+        let settled: BTreeSet<(ZoneId, usize)> = zones
+            .iter()
+            .flat_map(|z| z.bundles.iter().map(|b| (z.zone_id, *b)))
+            .collect();
+        let pending: BTreeMap<ZoneId, Vec<SyncLog>> = zones
+            .iter()
+            .map(|z| {
+                let logs = z.ledger.syncs.pending.iter();
+                (z.zone_id, logs.map(|p| p.sync.log.clone()).collect())
+            })
+            .collect();
+        let mut zone_waiting = Vec::with_capacity(zones.len());
+        let mut zones_of_bundle = vec![Vec::new(); bundles.len()];
+        for (i, zone) in zones.iter().enumerate() {
+            // any bundle may settle the counterpart half of a pending sync
+            let waits_on = if pending[&zone.zone_id].is_empty() {
+                zone.bundles.clone()
+            } else {
+                Vec::from_iter(0..bundles.len())
+            };
+            for &b in &waits_on {
+                zones_of_bundle[b].push(i);
+            }
+            zone_waiting.push(waits_on.len());
+        }
+        let mut zones = Vec::from_iter(zones.into_iter().map(Some));
+
+        let mut tx_proofs = Vec::from_iter(bundles.iter().map(|b| vec![None; b.txs.len()]));
+        let mut tx_waiting = Vec::from_iter(bundles.iter().map(|b| b.txs.len()));
+        let mut fee_outputs = vec![Vec::new(); bundles.len()];
+        let mut bundle_proofs: Vec<Option<ProvedBundle>> = vec![None; bundles.len()];
+        let mut ledgers = BTreeMap::new();
+
+        let mut feed = TxFeed::new(bundles);
+        let mut ready = VecDeque::new();
+        for (zone, _) in zones
+            .iter_mut()
+            .zip(&zone_waiting)
+            .filter(|(_, &waiting)| waiting == 0)
+        {
+            let job = zone.take().unwrap();
+            let synced = synced(job.zone_id, &[], &bundle_proofs, &settled, &pending);
+            ready.push_front(Job::Ledger {
+                job,
+                bundles: vec![],
+                synced,
+            });
+        }
+
+        let (job_tx, job_rx) = mpsc::sync_channel::<Job>(self.workers);
+        let (done_tx, done_rx) = mpsc::channel::<Done>();
+        let job_rx = Mutex::new(job_rx);
+        let ctx = self.ctx;
+
+        std::thread::scope(|s| {
+            for _ in 0..self.workers {
+                let done_tx = done_tx.clone();
+                let job_rx = &job_rx;
+                s.spawn(move || loop {
+                    let Ok(job) = job_rx.lock().unwrap().recv() else {
+                        break;
+                    };
+                    // a panicking job is reported, leaving the scheduler
+                    // waiting on it would hang the pipeline
+                    let done =
+                        catch_unwind(AssertUnwindSafe(|| run(ctx, job))).unwrap_or(Done::Panicked);
+                    if done_tx.send(done).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(done_tx);
+
+            let mut schedule = || -> Result<()> {
+                let mut in_flight = 0;
+                for done in 0..total {
+                    while in_flight < self.workers {
+                        let Some(job) = ready.pop_front().or_else(|| feed.next(&mut fee_outputs))
+                        else {
+                            break;
+                        };
+                        job_tx.send(job).map_err(|_| Error::WorkerPanicked)?;
+                        in_flight += 1;
+                    }
+
+                    let result = done_rx.recv().map_err(|_| Error::WorkerPanicked)?;
+                    in_flight -= 1;
+
+                    let stage = match result {
+                        Done::Tx { bundle, tx, proof } => {
+                            tx_proofs[bundle][tx] = Some(proof?);
+                            tx_waiting[bundle] -= 1;
+                            if tx_waiting[bundle] == 0 {
+                                let txs = tx_proofs[bundle].drain(..).flatten().collect();
+                                ready.push_front(Job::Bundle {
+                                    bundle,
+                                    txs,
+                                    fee_outputs: std::mem::take(&mut fee_outputs[bundle]),
+                                });
+                            }
+                            Stage::Tx { bundle, tx }
+                        }
+                        Done::Bundle { bundle, proof } => {
+                            bundle_proofs[bundle] = Some(proof?);
+                            for &z in &zones_of_bundle[bundle] {
+                                zone_waiting[z] -= 1;
+                                if zone_waiting[z] == 0 {
+                                    let job = zones[z].take().unwrap();
+                                    let bundles = Vec::from_iter(
+                                        job.bundles
+                                            .iter()
+                                            .map(|b| bundle_proofs[*b].clone().unwrap()),
+                                    );
+                                    let synced = synced(
+                                        job.zone_id,
+                                        &job.bundles,
+                                        &bundle_proofs,
+                                        &settled,
+                                        &pending,
+                                    );
+                                    ready.push_front(Job::Ledger {
+                                        job,
+                                        bundles,
+                                        synced,
+                                    });
+                                }
+                            }
+                            Stage::Bundle { bundle }
+                        }
+                        Done::Ledger { zone_id, proof } => {
+                            ledgers.insert(zone_id, proof?);
+                            Stage::Ledger { zone_id }
+                        }
+                        Done::Panicked => return Err(Error::WorkerPanicked),
+                    };
+
+                    if let Some(progress) = &mut self.progress {
+                        progress(&Progress {
+                            stage,
+                            done: done + 1,
+                            total,
+                        });
+                    }
+                }
+
+                Ok(())
+            };
+
+            // workers exit once the job queue is closed, also on early errors
+            let result = schedule();
+            drop(job_tx);
+            result
+        })?;
+
+        Ok(PipelineOutput {
+            bundles: bundle_proofs.into_iter().map(Option::unwrap).collect(),
+            ledgers,
+        })
+    }
+}
+
+/// Hands out the tx jobs of the bundles one at a time, in bundle order.
+struct TxFeed {
+    bundles: std::iter::Enumerate<std::vec::IntoIter<BundleJob>>,
+    txs: Option<(usize, std::iter::Enumerate<std::vec::IntoIter<TxJob>>)>,
+}
+
+impl TxFeed {
+    fn new(bundles: Vec<BundleJob>) -> Self {
+        Self {
+            bundles: bundles.into_iter().enumerate(),
+            txs: None,
+        }
+    }
+
+    /// The next tx job, or the bundle job of a bundle without txs. The fee
+    /// outputs of a bundle are moved to `fee_outputs` until its txs are proven.
+    fn next<'a>(&mut self, fee_outputs: &mut [Vec<OutputWitness>]) -> Option<Job<'a>> {
+        loop {
+            if let Some((bundle, txs)) = &mut self.txs {
+                if let Some((tx, job)) = txs.next() {
+                    return Some(Job::Tx {
+                        bundle: *bundle,
+                        tx,
+                        job: Box::new(job),
+                    });
+                }
+            }
+            let (bundle, job) = self.bundles.next()?;
+            if job.txs.is_empty() {
+                self.txs = None;
+                return Some(Job::Bundle {
+                    bundle,
+                    txs: vec![],
+                    fee_outputs: job.fee_outputs,
+                });
+            }
+            fee_outputs[bundle] = job.fee_outputs;
+            self.txs = Some((bundle, job.txs.into_iter().enumerate()));
+        }
+    }
+}
+
+/// Cross zone bundles the zone acknowledges, those that every zone they touch
+/// either settles in the pipeline or left pending in an earlier batch.
+///
+/// `bundles` must hold the proofs of the bundles the zone settles, and of all
+/// the bundles if the zone has pending syncs.
+fn synced(
+    zone_id: ZoneId,
+    indices: &[usize],
+    bundles: &[Option<ProvedBundle>],
+    settled: &BTreeSet<(ZoneId, usize)>,
+    pending: &BTreeMap<ZoneId, Vec<SyncLog>>,
+) -> Vec<BundleRoot> {
+    let is_pending = |zone: &ZoneId, root: &BundleRoot| {
+        pending
+            .get(zone)
+            .is_some_and(|logs| logs.iter().any(|log| &log.bundle == root))
+    };
+    let publics = Vec::from_iter(bundles.iter().map(|b| b.as_ref().map(ProvedBundle::public)));
+
+    let mut synced = Vec::from_iter(
+        indices
+            .iter()
+            .map(|&idx| (idx, publics[idx].as_ref().unwrap()))
+            .filter(|(idx, bundle)| {
+                bundle.updates.len() > 1
+                    && bundle.updates.keys().all(|zone| {
+                        settled.contains(&(*zone, *idx)) || is_pending(zone, &bundle.root)
+                    })
+            })
+            .map(|(_, bundle)| bundle.root),
+    );
+
+    // the pending syncs whose counterparts settle their halves in the pipeline
+    for log in &pending[&zone_id] {
+        let settles = |zone: &ZoneId| {
+            publics.iter().enumerate().any(|(idx, bundle)| {
+                bundle.as_ref().is_some_and(|b| b.root == log.bundle)
+                    && settled.contains(&(*zone, idx))
+            })
+        };
+        if log
+            .zones
+            .iter()
+            .all(|zone| is_pending(zone, &log.bundle) || settles(zone))
+        {
+            synced.push(log.bundle);
+        }
+    }
+
+    synced
+}
+
+fn run(ctx: &ProverContext, job: Job) -> Done {
+    match job {
+        Job::Tx { bundle, tx, job } => Done::Tx {
+            bundle,
+            tx,
+            proof: ProvedTx::prove(
+                ctx,
+                job.witness,
+                job.supply_covenant_proofs,
+                job.spending_covenant_proofs,
+            ),
+        },
+        Job::Bundle {
+            bundle,
+            txs,
+            fee_outputs,
+        } => Done::Bundle {
+            bundle,
+            proof: ProvedBundle::prove(ctx, txs, fee_outputs),
+        },
+        Job::Ledger {
+            job,
+            bundles,
+            synced,
+        } => Done::Ledger {
+            zone_id: job.zone_id,
+            proof: ProvedLedgerTransition::prove(ctx, job.ledger, job.zone_id, bundles, &synced),
+        },
+    }
+}
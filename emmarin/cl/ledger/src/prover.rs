@@ -0,0 +1,231 @@
+use std::{
+    rc::Rc,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use risc0_zkvm::{sha::Digest, ExecutorEnv, Prover, ProverOpts, Receipt, ReceiptClaim};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    cache::{CacheKey, ReceiptCache},
+    error::{Error, Result},
+};
+
+pub use risc0_zkvm::ReceiptKind;
+
+/// Which risc0 prover to run the guests on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProverKind {
+    /// The prover selected by the risc0 environment (`RISC0_PROVER`), in-process
+    /// or through a local `r0vm`.
+    #[default]
+    Local,
+    /// Executes the guests and emits fake receipts, which only verify when
+    /// `RISC0_DEV_MODE` is set. Meant for tests and local development.
+    Dev,
+    /// Stand-in for a remote proving service, currently Bonsai configured
+    /// through `BONSAI_API_URL` and `BONSAI_API_KEY`.
+    Remote,
+}
+
+/// Statistics about a single proof generated through a [`ProverContext`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProveStats {
+    /// Name of the guest that was proven, e.g. "tx" or "ledger"
+    pub guest: &'static str,
+    pub receipt_kind: ReceiptKind,
+    pub elapsed: Duration,
+    pub segments: usize,
+    pub user_cycles: u64,
+    pub total_cycles: u64,
+    /// The receipt was served from the [`ReceiptCache`], cycle counts are zero
+    pub cached: bool,
+}
+
+/// Selects the prover and receipt kind used by every prover in this crate and
+/// collects the statistics of the proofs it generates.
+#[derive(Debug)]
+pub struct ProverContext {
+    pub kind: ProverKind,
+    pub receipt_kind: ReceiptKind,
+    pub cache: Option<ReceiptCache>,
+    stats: Mutex<Vec<ProveStats>>,
+}
+
+impl Default for ProverContext {
+    fn default() -> Self {
+        Self::new(ProverKind::Local, ReceiptKind::Succinct)
+    }
+}
+
+impl ProverContext {
+    pub fn new(kind: ProverKind, receipt_kind: ReceiptKind) -> Self {
+        Self {
+            kind,
+            receipt_kind,
+            cache: None,
+            stats: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Serve receipts from `cache` when the same guest input was proven before.
+    pub fn with_cache(mut self, cache: ReceiptCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Dev mode context, receipts are produced without proving.
+    pub fn dev() -> Self {
+        Self::new(ProverKind::Dev, ReceiptKind::Succinct)
+    }
+
+    /// The risc0 prover backing this context.
+    ///
+    /// A new prover is handed out on each call since risc0 provers are not
+    /// `Send`, this keeps the context shareable across threads.
+    pub fn prover(&self) -> Rc<dyn Prover> {
+        match self.kind {
+            ProverKind::Local | ProverKind::Dev => risc0_zkvm::default_prover(),
+            ProverKind::Remote => Rc::new(risc0_zkvm::BonsaiProver::new("bonsai")),
+        }
+    }
+
+    pub fn opts(&self) -> ProverOpts {
+        let opts = ProverOpts::default().with_receipt_kind(self.receipt_kind);
+        match self.kind {
+            ProverKind::Dev => opts.with_dev_mode(true),
+            ProverKind::Local | ProverKind::Remote => opts,
+        }
+    }
+
+    /// Prove `elf` on `env`, recording the statistics under `guest`.
+    ///
+    /// `witness` identifies the input written to `env` for the receipt cache.
+    pub fn prove(
+        &self,
+        guest: &'static str,
+        image_id: [u8; 32],
+        elf: &[u8],
+        witness: &impl Serialize,
+        env: ExecutorEnv<'_>,
+    ) -> Result<Receipt> {
+        let start_t = Instant::now();
+        let opts = self.opts();
+
+        let key = CacheKey::new(image_id, witness, &opts);
+        if let Some(receipt) = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get(&key, image_id, &opts))
+        {
+            self.record(ProveStats {
+                guest,
+                receipt_kind: self.receipt_kind,
+                elapsed: start_t.elapsed(),
+                segments: 0,
+                user_cycles: 0,
+                total_cycles: 0,
+                cached: true,
+            });
+            return Ok(receipt);
+        }
+
+        let prove_info = self
+            .prover()
+            .prove_with_opts(env, elf, &opts)
+            .map_err(|_| Error::Risc0ProofFailed)?;
+
+        // the cache only saves work, a failed write must not lose the proof
+        if let Some(cache) = &self.cache {
+            let _ = cache.put(&key, &prove_info.receipt);
+        }
+
+        self.record(ProveStats {
+            guest,
+            receipt_kind: self.receipt_kind,
+            elapsed: start_t.elapsed(),
+            segments: prove_info.stats.segments,
+            user_cycles: prove_info.stats.user_cycles,
+            total_cycles: prove_info.stats.total_cycles,
+            cached: false,
+        });
+
+        Ok(prove_info.receipt)
+    }
+
+    fn record(&self, stats: ProveStats) {
+        self.stats.lock().unwrap().push(stats);
+    }
+
+    /// Statistics of all proofs generated so far.
+    pub fn stats(&self) -> Vec<ProveStats> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Drain the statistics collected so far.
+    pub fn take_stats(&self) -> Vec<ProveStats> {
+        std::mem::take(&mut *self.stats.lock().unwrap())
+    }
+}
+
+/// Statistics about a single guest run through [`execute`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecuteStats {
+    pub guest: &'static str,
+    pub elapsed: Duration,
+    pub segments: usize,
+    /// Cycles spent in the guest, without continuation or padding overhead
+    pub user_cycles: u64,
+    /// Cycles the prover would have to prove, padded to segment sizes
+    pub total_cycles: u64,
+}
+
+/// The decoded journal of a guest run through [`execute`].
+#[derive(Debug, Clone)]
+pub struct Executed<T> {
+    pub public: T,
+    pub stats: ExecuteStats,
+}
+
+/// Run `elf` on `env` through the risc0 executor without proving it.
+///
+/// This checks the same guest logic as proving for a fraction of the cost,
+/// composed proofs may be stood in for with [`assumed_claim`].
+pub fn execute<T: DeserializeOwned>(
+    guest: &'static str,
+    env: ExecutorEnv<'_>,
+    elf: &[u8],
+) -> Result<Executed<T>> {
+    let start_t = Instant::now();
+
+    let session = risc0_zkvm::default_executor()
+        .execute(env, elf)
+        .map_err(|_| Error::Risc0ExecutionFailed)?;
+
+    let stats = ExecuteStats {
+        guest,
+        elapsed: start_t.elapsed(),
+        segments: session.segments.len(),
+        user_cycles: session.cycles(),
+        total_cycles: session.segments.iter().map(|s| 1u64 << s.po2).sum(),
+    };
+
+    Ok(Executed {
+        public: session.journal.decode()?,
+        stats,
+    })
+}
+
+/// Claim that the guest `image_id` ran successfully and committed `public`.
+///
+/// Added to an executor env in place of a receipt, the claim stays unresolved
+/// so it can only be used to execute, not to prove.
+pub fn assumed_claim(image_id: [u8; 32], public: &impl Serialize) -> ReceiptClaim {
+    let journal = risc0_zkvm::serde::to_vec(public)
+        .unwrap()
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+        .collect::<Vec<u8>>();
+    ReceiptClaim::ok(Digest::from(image_id), journal)
+}
@@ -0,0 +1,191 @@
+use cl::{
+    ds::indexed::DuplicateNullifier,
+    mantle::{
+        ledger::{Ledger, LedgerState, TransitionDelta},
+        zone::{ZoneId, ZoneState},
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub use crate::error::SnapshotError;
+
+/// Version of the encoding produced by `to_bytes`, bumped on any change to the
+/// serialized layout of the snapshot or delta types.
+pub const SNAPSHOT_VERSION: u16 = 1;
+
+/// A zone's host ledger at a given bundles height, enough for a new executor
+/// to bootstrap the zone without replaying its history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneSnapshot {
+    pub zone_id: ZoneId,
+    pub zone_state: ZoneState,
+    pub ledger: LedgerState,
+}
+
+impl ZoneSnapshot {
+    pub fn new(zone_id: ZoneId, zone_state: ZoneState, ledger: &LedgerState) -> Self {
+        Self {
+            zone_id,
+            zone_state,
+            ledger: ledger.clone(),
+        }
+    }
+
+    /// Number of bundles applied to the ledger at the time of the snapshot
+    pub fn height(&self) -> u64 {
+        self.ledger.bundles.len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let snapshot: Self = decode(bytes)?;
+        check_mmrs(&snapshot.ledger)?;
+        Ok(snapshot)
+    }
+
+    /// Check the snapshot of `zone_id` against a trusted ledger commitment (e.g.
+    /// taken from a verified batch update) and hand back the zone state and
+    /// host ledger.
+    pub fn import(
+        self,
+        zone_id: ZoneId,
+        expected: &Ledger,
+    ) -> Result<(ZoneState, LedgerState), SnapshotError> {
+        check_zone(zone_id, self.zone_id)?;
+        check_mmrs(&self.ledger)?;
+        if self.ledger.to_witness().commit() != *expected {
+            return Err(SnapshotError::LedgerMismatch);
+        }
+        if !self.ledger.archive_is_consistent() {
+            return Err(SnapshotError::ArchiveMismatch);
+        }
+        if self.zone_state.ledger != *expected {
+            return Err(SnapshotError::ZoneStateMismatch);
+        }
+        Ok((self.zone_state, self.ledger))
+    }
+}
+
+/// The transitions applied to a zone ledger since a snapshot, used by an
+/// executor to catch up from an older snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerDelta {
+    pub zone_id: ZoneId,
+    /// Ledger commitment the delta applies on top of
+    pub from: Ledger,
+    /// Bundles height the delta applies on top of
+    pub from_height: u64,
+    pub transitions: Vec<TransitionDelta>,
+    /// Zone state after all transitions are applied
+    pub zone_state: ZoneState,
+}
+
+impl LedgerDelta {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        decode(bytes)
+    }
+
+    /// Apply the delta to the `zone_id` ledger, returning the new zone state.
+    ///
+    /// The ledger is left untouched if the delta does not start from it, holds
+    /// a transition the ledger rejects, or does not end at the ledger committed
+    /// to in the zone state.
+    pub fn apply(
+        &self,
+        zone_id: ZoneId,
+        ledger: &mut LedgerState,
+    ) -> Result<ZoneState, SnapshotError> {
+        check_zone(zone_id, self.zone_id)?;
+        if ledger.bundles.len() != self.from_height {
+            return Err(SnapshotError::HeightMismatch {
+                expected: self.from_height,
+                actual: ledger.bundles.len(),
+            });
+        }
+        if ledger.to_witness().commit() != self.from {
+            return Err(SnapshotError::LedgerMismatch);
+        }
+
+        let mut next = ledger.clone();
+        for transition in &self.transitions {
+            self.check_transition(&next, transition)?;
+            transition
+                .apply(&mut next)
+                .map_err(|err| SnapshotError::InvalidSync(err.bundle()))?;
+        }
+
+        if next.to_witness().commit() != self.zone_state.ledger {
+            return Err(SnapshotError::ZoneStateMismatch);
+        }
+
+        *ledger = next;
+        Ok(self.zone_state)
+    }
+
+    /// The delta comes from an untrusted peer, reject the transitions that
+    /// [`TransitionDelta::apply`] would panic on.
+    fn check_transition(
+        &self,
+        ledger: &LedgerState,
+        transition: &TransitionDelta,
+    ) -> Result<(), SnapshotError> {
+        if let Some(bundle) = transition
+            .bundles
+            .iter()
+            .find(|bundle| !bundle.zones.contains(&self.zone_id))
+        {
+            return Err(SnapshotError::ForeignBundle(bundle.root));
+        }
+        transition
+            .nullifier_order()
+            .map_err(|DuplicateNullifier(nf)| SnapshotError::DuplicateNullifier(nf))?;
+        if let Some(nf) = transition
+            .nullifiers()
+            .into_iter()
+            .find(|nf| !ledger.nullifiers.can_insert(*nf))
+        {
+            return Err(SnapshotError::SpentNullifier(nf));
+        }
+        Ok(())
+    }
+}
+
+fn check_zone(expected: ZoneId, actual: ZoneId) -> Result<(), SnapshotError> {
+    if expected != actual {
+        return Err(SnapshotError::ZoneMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+/// The ledger MMRs come from an untrusted peer, reject the ones the ledger
+/// would panic on.
+fn check_mmrs(ledger: &LedgerState) -> Result<(), SnapshotError> {
+    if !ledger.commitments.is_well_formed() || !ledger.bundles.is_well_formed() {
+        return Err(SnapshotError::MalformedMmr);
+    }
+    Ok(())
+}
+
+fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = SNAPSHOT_VERSION.to_le_bytes().to_vec();
+    bytes.extend(bincode::serialize(value).unwrap());
+    bytes
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SnapshotError> {
+    let (version, body) = bytes
+        .split_first_chunk::<2>()
+        .ok_or(SnapshotError::Truncated)?;
+    let version = u16::from_le_bytes(*version);
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    Ok(bincode::deserialize(body)?)
+}
@@ -0,0 +1,195 @@
+use hex::FromHex;
+use risc0_zkvm::{
+    sha::Digestible, Groth16ReceiptVerifierParameters, InnerReceipt, ProverOpts, Receipt,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    batch::ProvedBatch,
+    bundle::ProvedBundle,
+    envelope::SealKind,
+    error::{Error, Result},
+    ledger::ProvedLedgerTransition,
+    prover::ProverContext,
+    verifier::{Verifier, VerifyError},
+};
+
+pub use crate::error::SnarkError;
+
+/// Groth16 proof over BN254 of a ledger statement, verifiable on chain.
+#[derive(Debug, Clone)]
+pub struct Groth16Proof {
+    pub image_id: [u8; 32],
+    pub risc0_receipt: Receipt,
+}
+
+impl Groth16Proof {
+    /// Compress a receipt of `image_id` to groth16 with the prover of `ctx`.
+    ///
+    /// Composite receipts are lifted to succinct first. This needs the risc0
+    /// groth16 prover, which only runs on x86 hosts.
+    pub fn wrap(ctx: &ProverContext, image_id: &str, receipt: &Receipt) -> Result<Self> {
+        let opts = ProverOpts::groth16().with_dev_mode(ctx.opts().dev_mode());
+        let risc0_receipt = ctx
+            .prover()
+            .compress(&opts, receipt)
+            .map_err(|_| Error::Risc0ProofFailed)?;
+
+        Ok(Self {
+            image_id: <[u8; 32]>::from_hex(image_id).unwrap(),
+            risc0_receipt,
+        })
+    }
+
+    pub fn ledger(ctx: &ProverContext, proof: &ProvedLedgerTransition) -> Result<Self> {
+        Self::wrap(ctx, risc0_images::LEDGER_ID, &proof.risc0_receipt)
+    }
+
+    pub fn bundle(ctx: &ProverContext, proof: &ProvedBundle) -> Result<Self> {
+        Self::wrap(ctx, risc0_images::BUNDLE_ID, &proof.risc0_receipt)
+    }
+
+    pub fn batch(ctx: &ProverContext, proof: &ProvedBatch) -> Result<Self> {
+        Self::wrap(ctx, risc0_images::BATCH_ID, &proof.risc0_receipt)
+    }
+
+    /// Verify the proof and decode the statement it proves.
+    ///
+    /// Unlike [`Verifier::verify_and_decode`], only groth16 seals are accepted.
+    pub fn verify_and_decode<T: DeserializeOwned>(
+        &self,
+        verifier: &Verifier,
+    ) -> core::result::Result<T, VerifyError> {
+        let seal = SealKind::of(&self.risc0_receipt);
+        if seal != Some(SealKind::Groth16) {
+            return Err(VerifyError::SealKindMismatch {
+                expected: SealKind::Groth16,
+                actual: seal,
+            });
+        }
+        verifier.verify_and_decode(self.image_id, &self.risc0_receipt)
+    }
+
+    /// The arguments of `IRiscZeroVerifier.verify` for this proof.
+    pub fn calldata(&self) -> core::result::Result<Calldata, SnarkError> {
+        let InnerReceipt::Groth16(ref groth16) = self.risc0_receipt.inner else {
+            return Err(SnarkError::NotGroth16(SealKind::of(&self.risc0_receipt)));
+        };
+
+        // the verifier router dispatches on the first bytes of the verifier
+        // parameters digest
+        let mut seal = groth16.verifier_parameters.as_bytes()[..4].to_vec();
+        seal.extend(&groth16.seal);
+
+        Ok(Calldata {
+            seal,
+            image_id: self.image_id,
+            journal_digest: self.risc0_receipt.journal.digest().into(),
+        })
+    }
+}
+
+/// Arguments of `verify(bytes seal, bytes32 imageId, bytes32 journalDigest)`
+/// on the risc0 groth16 verifier contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Calldata {
+    /// 4 byte verifier selector followed by the groth16 seal
+    pub seal: Vec<u8>,
+    pub image_id: [u8; 32],
+    /// sha256 of the journal
+    pub journal_digest: [u8; 32],
+}
+
+const WORD: usize = 32;
+
+impl Calldata {
+    /// Solidity ABI encoding of the arguments, without a function selector.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(5 * WORD + self.seal.len().next_multiple_of(WORD));
+        bytes.extend(abi_uint(3 * WORD));
+        bytes.extend(self.image_id);
+        bytes.extend(self.journal_digest);
+        bytes.extend(abi_uint(self.seal.len()));
+        bytes.extend(&self.seal);
+        bytes.resize(bytes.len().next_multiple_of(WORD), 0);
+        bytes
+    }
+
+    pub fn abi_decode(bytes: &[u8]) -> core::result::Result<Self, SnarkError> {
+        // offsets are read from the calldata, so they may overflow
+        let word = |i: usize| {
+            i.checked_add(WORD)
+                .and_then(|end| bytes.get(i..end))
+                .map(|w| <[u8; WORD]>::try_from(w).unwrap())
+                .ok_or(SnarkError::MalformedCalldata)
+        };
+
+        let offset = from_abi_uint(word(0)?)?;
+        let image_id = word(WORD)?;
+        let journal_digest = word(2 * WORD)?;
+        let len = from_abi_uint(word(offset)?)?;
+        let seal = offset
+            .checked_add(WORD)
+            .and_then(|start| bytes.get(start..))
+            .and_then(|tail| tail.get(..len))
+            .ok_or(SnarkError::MalformedCalldata)?
+            .to_vec();
+
+        Ok(Self {
+            seal,
+            image_id,
+            journal_digest,
+        })
+    }
+}
+
+fn abi_uint(value: usize) -> [u8; WORD] {
+    let mut word = [0u8; WORD];
+    word[WORD - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+fn from_abi_uint(word: [u8; WORD]) -> core::result::Result<usize, SnarkError> {
+    if word[..WORD - 8].iter().any(|b| *b != 0) {
+        return Err(SnarkError::MalformedCalldata);
+    }
+    usize::try_from(u64::from_be_bytes(word[WORD - 8..].try_into().unwrap()))
+        .map_err(|_| SnarkError::MalformedCalldata)
+}
+
+/// What an on-chain verifier of [`Groth16Proof`]s is deployed with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Groth16VerifyingKey {
+    pub control_root: [u8; 32],
+    pub bn254_control_id: [u8; 32],
+    /// Digest of the verifier parameters, its first 4 bytes prefix every seal
+    pub verifier_parameters: [u8; 32],
+    /// The BN254 verifying key of the risc0 recursion circuit
+    pub verifying_key: risc0_groth16::VerifyingKey,
+}
+
+impl Groth16VerifyingKey {
+    pub fn selector(&self) -> [u8; 4] {
+        self.verifier_parameters[..4].try_into().unwrap()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        cl::serialize(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> core::result::Result<Self, SnarkError> {
+        bincode::deserialize(bytes).map_err(SnarkError::Decode)
+    }
+}
+
+/// The verifying key of the groth16 proofs produced by the risc0 version this
+/// crate is built against.
+pub fn verifying_key() -> Groth16VerifyingKey {
+    let params = Groth16ReceiptVerifierParameters::default();
+    Groth16VerifyingKey {
+        control_root: params.control_root.into(),
+        bn254_control_id: params.bn254_control_id.into(),
+        verifier_parameters: params.digest().into(),
+        verifying_key: params.verifying_key,
+    }
+}
@@ -1,70 +1,201 @@
-use cl::mantle::zone::Stf;
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+
+use cl::mantle::{
+    ledger::LedgerState,
+    zone::{ProofSystem, Stf, ZoneState},
+};
 use ledger_proof_statements::stf::StfPublic;
 
-use hex::FromHex;
+use crate::{
+    envelope::SealKind,
+    error::{Error, Result},
+    prover::{execute, Executed, ProverContext},
+    verifier::{Verifier, VerifyError},
+};
+
+use risc0_zkvm::sha::Digest;
 
 #[derive(Debug, Clone)]
 pub struct StfProof {
+    pub system: ProofSystem,
     pub risc0_id: [u8; 32],
-    pub public: StfPublic,
     pub risc0_receipt: risc0_zkvm::Receipt,
 }
 
-pub fn risc0_stf(risc0_id: [u32; 8]) -> Stf {
-    // TODO: hash
-
-    unsafe { core::mem::transmute::<[u32; 8], [u8; 32]>(risc0_id) }
+/// The stf of a zone proven with risc0 receipts of the given image.
+pub fn risc0_stf(risc0_id: impl Into<Digest>) -> Stf {
+    ProofSystem::Risc0.stf(&risc0_id.into().into())
 }
 
 impl StfProof {
     pub fn from_risc0(risc0_id: [u8; 32], risc0_receipt: risc0_zkvm::Receipt) -> Self {
         Self {
+            system: ProofSystem::Risc0,
             risc0_id,
-            public: risc0_receipt.journal.decode().unwrap(),
             risc0_receipt,
         }
     }
 
+    pub fn from_groth16(risc0_id: [u8; 32], risc0_receipt: risc0_zkvm::Receipt) -> Self {
+        Self {
+            system: ProofSystem::Groth16,
+            risc0_id,
+            risc0_receipt,
+        }
+    }
+
+    /// Panics on a malformed journal, use [`Verifier::stf`] on untrusted proofs.
+    pub fn public(&self) -> StfPublic {
+        self.risc0_receipt.journal.decode().unwrap()
+    }
+
     pub fn stf(&self) -> Stf {
-        self.risc0_id
+        self.system.stf(&self.risc0_id)
     }
-    pub fn verify(&self) -> bool {
-        self.risc0_receipt.verify(self.risc0_id).is_ok()
+
+    /// Check the proof against the stf the zone is expected to run, rather
+    /// than the one the proof claims, using the backend registered for it.
+    pub fn verify(&self, stf: Stf) -> bool {
+        Verifier::default().stf(stf, self).is_ok()
+    }
+
+    pub fn nop_image() -> [u8; 32] {
+        risc0_images::STF_NOP_IMAGE_ID
     }
 
-    pub fn nop_stf() -> [u8; 32] {
-        FromHex::from_hex(risc0_images::STF_NOP_ID).unwrap()
+    pub fn nop_stf() -> Stf {
+        risc0_stf(Self::nop_image())
+    }
+
+    /// State of a zone running the nop stf over `ledger`.
+    pub fn nop_zone_state(ledger: &LedgerState) -> ZoneState {
+        ZoneState {
+            stf: Self::nop_stf(),
+            zone_data: [0; 32],
+            ledger: ledger.to_witness().commit(),
+        }
     }
 
-    pub fn prove_nop(public: StfPublic) -> Self {
+    pub fn prove_nop(ctx: &ProverContext, public: StfPublic) -> Result<Self> {
         let env = risc0_zkvm::ExecutorEnv::builder()
             .write(&public)
-            .unwrap()
+            .map_err(|_| Error::Risc0ProofFailed)?
             .build()
-            .unwrap();
+            .map_err(|_| Error::Risc0ProofFailed)?;
 
-        let prover = risc0_zkvm::default_prover();
+        let receipt = ctx.prove(
+            "stf",
+            risc0_images::STF_NOP_IMAGE_ID,
+            risc0_images::STF_NOP_ELF,
+            &public,
+            env,
+        )?;
 
-        let start_t = std::time::Instant::now();
+        Ok(Self::from_risc0(Self::nop_image(), receipt))
+    }
 
-        let opts = risc0_zkvm::ProverOpts::succinct();
-        let prove_info = prover
-            .prove_with_opts(env, risc0_images::STF_NOP_ELF, &opts)
-            .unwrap();
+    /// Run the nop stf guest without proving it.
+    pub fn execute_nop(public: StfPublic) -> Result<Executed<StfPublic>> {
+        let env = risc0_zkvm::ExecutorEnv::builder()
+            .write(&public)
+            .map_err(|_| Error::Risc0ExecutionFailed)?
+            .build()
+            .map_err(|_| Error::Risc0ExecutionFailed)?;
 
-        println!(
-            "STARK 'stf' prover time: {:.2?}, user_cycles: {}, total_cycles: {}",
-            start_t.elapsed(),
-            prove_info.stats.user_cycles,
-            prove_info.stats.total_cycles
-        );
+        execute("stf", env, risc0_images::STF_NOP_ELF)
+    }
+}
 
-        let receipt = prove_info.receipt;
+/// Verifies the proofs of an stf.
+pub trait StfBackend: Debug + Send + Sync {
+    /// Implementations must reject proofs of a program other than `stf`.
+    fn verify(
+        &self,
+        verifier: &Verifier,
+        stf: Stf,
+        proof: &StfProof,
+    ) -> core::result::Result<StfPublic, VerifyError>;
+}
 
-        Self {
-            risc0_id: FromHex::from_hex(risc0_images::STF_NOP_ID).unwrap(),
-            public,
-            risc0_receipt: receipt,
+/// Checks that the system and image named by the proof hash to `stf`.
+fn check_stf(stf: Stf, proof: &StfProof) -> core::result::Result<(), VerifyError> {
+    if proof.stf() != stf {
+        return Err(VerifyError::StfMismatch {
+            expected: stf,
+            actual: proof.stf(),
+        });
+    }
+    Ok(())
+}
+
+/// Accepts any risc0 receipt of the image.
+#[derive(Debug, Clone, Copy)]
+pub struct Risc0Backend;
+
+impl StfBackend for Risc0Backend {
+    fn verify(
+        &self,
+        verifier: &Verifier,
+        stf: Stf,
+        proof: &StfProof,
+    ) -> core::result::Result<StfPublic, VerifyError> {
+        check_stf(stf, proof)?;
+        verifier.verify_and_decode(proof.risc0_id, &proof.risc0_receipt)
+    }
+}
+
+/// Only accepts risc0 receipts of the image carrying a groth16 seal.
+#[derive(Debug, Clone, Copy)]
+pub struct Groth16Backend;
+
+impl StfBackend for Groth16Backend {
+    fn verify(
+        &self,
+        verifier: &Verifier,
+        stf: Stf,
+        proof: &StfProof,
+    ) -> core::result::Result<StfPublic, VerifyError> {
+        check_stf(stf, proof)?;
+        let seal = SealKind::of(&proof.risc0_receipt);
+        if seal != Some(SealKind::Groth16) {
+            return Err(VerifyError::SealKindMismatch {
+                expected: SealKind::Groth16,
+                actual: seal,
+            });
+        }
+        verifier.verify_and_decode(proof.risc0_id, &proof.risc0_receipt)
+    }
+}
+
+/// Maps stfs to the backend their proofs are verified with.
+///
+/// Stfs derived through [`ProofSystem::stf`] need no registration, the proof
+/// names its system and program and is checked to hash to the expected stf.
+/// Stfs with other identifiers must be registered with their own backend,
+/// which is handed the expected stf to bind the proof to.
+#[derive(Debug, Clone, Default)]
+pub struct StfRegistry {
+    backends: BTreeMap<Stf, Arc<dyn StfBackend>>,
+}
+
+impl StfRegistry {
+    pub fn register(&mut self, stf: Stf, backend: impl StfBackend + 'static) {
+        self.backends.insert(stf, Arc::new(backend));
+    }
+
+    pub fn verify(
+        &self,
+        verifier: &Verifier,
+        stf: Stf,
+        proof: &StfProof,
+    ) -> core::result::Result<StfPublic, VerifyError> {
+        if let Some(backend) = self.backends.get(&stf) {
+            return backend.verify(verifier, stf, proof);
+        }
+
+        match proof.system {
+            ProofSystem::Risc0 => Risc0Backend.verify(verifier, stf, proof),
+            ProofSystem::Groth16 => Groth16Backend.verify(verifier, stf, proof),
         }
     }
 }
@@ -0,0 +1,18 @@
+use cl::mantle::{ledger::LedgerState, ZoneState};
+use risc0_zkvm::{sha::Digest, FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+
+use crate::stf::StfProof;
+
+pub use cl::test_utils::*;
+
+/// A receipt claiming `image_id` produced `journal`, only accepted by
+/// verifiers in dev mode.
+pub fn fake_receipt(image_id: [u8; 32], journal: Vec<u8>) -> Receipt {
+    let claim = ReceiptClaim::ok(Digest::from(image_id), journal.clone());
+    Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal)
+}
+
+/// State of a zone running the nop stf over `ledger`.
+pub fn zone_state(ledger: &LedgerState) -> ZoneState {
+    StfProof::nop_zone_state(ledger)
+}
@@ -1,9 +1,11 @@
 use crate::{
     covenant::{SpendingCovenantProof, SupplyCovenantProof},
     error::{Error, Result},
+    prover::{execute, Executed, ProverContext},
+    verifier::Verifier,
 };
 use cl::crust::{Tx, TxWitness};
-use hex::FromHex;
+use risc0_zkvm::sha::Digestible;
 
 #[derive(Debug, Clone)]
 pub struct ProvedTx {
@@ -12,10 +14,60 @@ pub struct ProvedTx {
 
 impl ProvedTx {
     pub fn prove(
+        ctx: &ProverContext,
         tx_witness: TxWitness,
         supply_covenant_proofs: Vec<SupplyCovenantProof>,
         spending_covenant_proofs: Vec<SpendingCovenantProof>,
     ) -> Result<ProvedTx> {
+        // the covenant receipts are composed into the proof, their claims are
+        // part of the input as much as the witness
+        let assumptions = supply_covenant_proofs
+            .iter()
+            .map(|p| &p.risc0_receipt)
+            .chain(spending_covenant_proofs.iter().map(|p| &p.risc0_receipt))
+            .map(|receipt| Ok(<[u8; 32]>::from(receipt.claim()?.digest())))
+            .collect::<risc0_zkvm::Result<Vec<_>>>()
+            .map_err(|_| Error::Risc0ProofFailed)?;
+
+        let env = Self::env(
+            &tx_witness,
+            supply_covenant_proofs,
+            spending_covenant_proofs,
+        )
+        .ok_or(Error::Risc0ProofFailed)?;
+        let receipt = ctx.prove(
+            "tx",
+            risc0_images::TX_IMAGE_ID,
+            risc0_images::TX_ELF,
+            &(&tx_witness, assumptions),
+            env,
+        )?;
+
+        Ok(Self {
+            risc0_receipt: receipt,
+        })
+    }
+
+    /// Run the tx guest without proving it.
+    pub fn execute(
+        tx_witness: TxWitness,
+        supply_covenant_proofs: Vec<SupplyCovenantProof>,
+        spending_covenant_proofs: Vec<SpendingCovenantProof>,
+    ) -> Result<Executed<Tx>> {
+        let env = Self::env(
+            &tx_witness,
+            supply_covenant_proofs,
+            spending_covenant_proofs,
+        )
+        .ok_or(Error::Risc0ExecutionFailed)?;
+        execute("tx", env, risc0_images::TX_ELF)
+    }
+
+    fn env(
+        tx_witness: &TxWitness,
+        supply_covenant_proofs: Vec<SupplyCovenantProof>,
+        spending_covenant_proofs: Vec<SpendingCovenantProof>,
+    ) -> Option<risc0_zkvm::ExecutorEnv<'static>> {
         let mut env = risc0_zkvm::ExecutorEnv::builder();
 
         for proof in spending_covenant_proofs {
@@ -26,39 +78,15 @@ impl ProvedTx {
             env.add_assumption(proof.risc0_receipt);
         }
 
-        let env = env.write(&tx_witness).unwrap().build().unwrap();
-
-        // Obtain the default prover.
-        let prover = risc0_zkvm::default_prover();
-
-        let start_t = std::time::Instant::now();
-
-        // Proof information by proving the specified ELF binary.
-        // This struct contains the receipt along with statistics about execution of the guest
-        let opts = risc0_zkvm::ProverOpts::succinct();
-        let prove_info = prover
-            .prove_with_opts(env, risc0_images::TX_ELF, &opts)
-            .map_err(|_| Error::Risc0ProofFailed)?;
-
-        println!(
-            "STARK 'tx' prover time: {:.2?}, user_cycles: {}, total_cycles: {}",
-            start_t.elapsed(),
-            prove_info.stats.user_cycles,
-            prove_info.stats.total_cycles
-        );
-
-        Ok(Self {
-            risc0_receipt: prove_info.receipt,
-        })
+        env.write(tx_witness).ok()?.build().ok()
     }
 
+    /// Panics on a malformed journal, use [`Verifier::tx`] on untrusted proofs.
     pub fn public(&self) -> Tx {
         self.risc0_receipt.journal.decode().unwrap()
     }
 
     pub fn verify(&self) -> bool {
-        self.risc0_receipt
-            .verify(<[u8; 32]>::from_hex(risc0_images::TX_ID).unwrap())
-            .is_ok()
+        Verifier::default().tx(self).is_ok()
     }
 }
@@ -1,56 +1,82 @@
-pub use crate::error::{Error, Result};
-use crate::{ledger::ProvedLedgerTransition, stf::StfProof};
-use cl::mantle::update::BatchUpdate;
-use std::collections::{HashMap, HashSet};
+pub use crate::error::{BatchVerifyError, Error, Result};
+use crate::{ledger::ProvedLedgerTransition, stf::StfProof, verifier::Verifier};
+use cl::mantle::{update::BatchUpdate, ZoneId};
+use ledger_proof_statements::ledger::check_sync_logs;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Debug, Clone)]
 pub struct ProvedBatchUpdate {
     pub batch: BatchUpdate,
-    pub ledger_proofs: Vec<ProvedLedgerTransition>,
-    pub stf_proofs: Vec<StfProof>,
+    pub ledger_proofs: BTreeMap<ZoneId, ProvedLedgerTransition>,
+    pub stf_proofs: BTreeMap<ZoneId, StfProof>,
 }
 
 impl ProvedBatchUpdate {
-    pub fn verify(&self) -> bool {
-        let mut expected_zones = HashMap::new();
-        let mut actual_zones = HashMap::new();
-        for proof in &self.ledger_proofs {
-            if !proof.verify() {
-                return false;
+    pub fn verify(&self) -> core::result::Result<(), BatchVerifyError> {
+        let mut zones = BTreeSet::new();
+        for update in &self.batch.updates {
+            if !zones.insert(update.zone_id) {
+                return Err(BatchVerifyError::DuplicateZone(update.zone_id));
             }
+        }
 
-            for bundle in &proof.public().sync_logs {
-                expected_zones.insert(bundle.bundle.0, HashSet::from_iter(bundle.zones.clone()));
-                actual_zones
-                    .entry(bundle.bundle.0)
-                    .or_insert_with(HashSet::new)
-                    .insert(proof.public().id);
-            }
+        if let Some(zone_id) = self.ledger_proofs.keys().find(|z| !zones.contains(*z)) {
+            return Err(BatchVerifyError::UnexpectedLedgerProof(*zone_id));
         }
+        if let Some(zone_id) = self.stf_proofs.keys().find(|z| !zones.contains(*z)) {
+            return Err(BatchVerifyError::UnexpectedStfProof(*zone_id));
+        }
+
+        let verifier = Verifier::default();
+        let mut ledger_publics = Vec::with_capacity(self.batch.updates.len());
 
-        for (bundle, expected) in expected_zones.iter() {
-            if let Some(actual) = actual_zones.get(bundle) {
-                if actual != expected {
-                    panic!("{:?} | {:?}", actual, expected);
-                }
-            } else {
-                panic!();
+        for update in &self.batch.updates {
+            let zone_id = update.zone_id;
+
+            let ledger_proof = self
+                .ledger_proofs
+                .get(&zone_id)
+                .ok_or(BatchVerifyError::MissingLedgerProof(zone_id))?;
+            let ledger_public = verifier
+                .ledger(ledger_proof)
+                .map_err(|e| BatchVerifyError::InvalidLedgerProof(zone_id, e))?;
+
+            if ledger_public.id != zone_id {
+                return Err(BatchVerifyError::LedgerZoneMismatch {
+                    expected: zone_id,
+                    actual: ledger_public.id,
+                });
+            }
+            if ledger_public.old_ledger != update.old.ledger {
+                return Err(BatchVerifyError::OldLedgerMismatch(zone_id));
+            }
+            if ledger_public.ledger != update.new.ledger {
+                return Err(BatchVerifyError::NewLedgerMismatch(zone_id));
+            }
+
+            let stf_proof = self
+                .stf_proofs
+                .get(&zone_id)
+                .ok_or(BatchVerifyError::MissingStfProof(zone_id))?;
+            if stf_proof.stf() != update.old.stf {
+                return Err(BatchVerifyError::StfMismatch {
+                    zone_id,
+                    expected: update.old.stf,
+                    actual: stf_proof.stf(),
+                });
+            }
+            let stf_public = verifier
+                .stf(update.old.stf, stf_proof)
+                .map_err(|e| BatchVerifyError::InvalidStfProof(zone_id, e))?;
+            if stf_public.old != update.old || stf_public.new != update.new {
+                return Err(BatchVerifyError::StfStateMismatch(zone_id));
             }
-        }
 
-        for ((update, stf_proof), ledger_proof) in self
-            .batch
-            .updates
-            .iter()
-            .zip(self.stf_proofs.iter())
-            .zip(self.ledger_proofs.iter())
-        {
-            assert_eq!(ledger_proof.public().old_ledger, update.old.ledger);
-            assert_eq!(ledger_proof.public().ledger, update.new.ledger);
-            assert_eq!(stf_proof.public.old, update.old);
-            assert_eq!(stf_proof.public.new, update.new);
+            ledger_publics.push(ledger_public);
         }
 
-        true
+        check_sync_logs(&ledger_publics)?;
+
+        Ok(())
     }
 }
@@ -0,0 +1,108 @@
+use cl::{
+    crust::{Bundle, Tx},
+    mantle::zone::Stf,
+};
+use ledger_proof_statements::{batch::BatchProofPublic, ledger::LedgerProofPublic, stf::StfPublic};
+use risc0_zkvm::{sha::Digestible, Receipt, VerifierContext};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    batch::ProvedBatch,
+    bundle::ProvedBundle,
+    ledger::ProvedLedgerTransition,
+    stf::{StfProof, StfRegistry},
+    tx::ProvedTx,
+};
+
+pub use crate::error::VerifyError;
+
+/// Verifies receipts against the image ids this crate was built with.
+///
+/// Unlike the `public()` accessors on the proofs, nothing here panics, so it
+/// is safe to use on receipts received from untrusted peers.
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    pub tx: [u8; 32],
+    pub bundle: [u8; 32],
+    pub ledger: [u8; 32],
+    pub batch: [u8; 32],
+    pub stfs: StfRegistry,
+    dev_mode: bool,
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Self {
+            tx: risc0_images::TX_IMAGE_ID,
+            bundle: risc0_images::BUNDLE_IMAGE_ID,
+            ledger: risc0_images::LEDGER_IMAGE_ID,
+            batch: risc0_images::BATCH_IMAGE_ID,
+            stfs: StfRegistry::default(),
+            dev_mode: VerifierContext::default().dev_mode(),
+        }
+    }
+}
+
+impl Verifier {
+    /// Accept fake receipts produced by dev mode provers.
+    pub fn with_dev_mode(mut self, dev_mode: bool) -> Self {
+        self.dev_mode = dev_mode;
+        self
+    }
+
+    /// Check that `receipt` is a valid proof of `image_id` and decode its journal.
+    pub fn verify_and_decode<T: DeserializeOwned>(
+        &self,
+        image_id: [u8; 32],
+        receipt: &Receipt,
+    ) -> Result<T, VerifyError> {
+        // look at the claimed image first so a proof of the wrong guest is
+        // reported as such rather than as a bad seal
+        let claim = receipt.claim().map_err(|e| VerifyError::Seal(e.into()))?;
+        if let Ok(claim) = claim.as_value() {
+            let actual: [u8; 32] = claim.pre.digest().into();
+            if actual != image_id {
+                return Err(VerifyError::ImageMismatch {
+                    expected: image_id,
+                    actual,
+                });
+            }
+        }
+
+        let ctx = VerifierContext::default().with_dev_mode(self.dev_mode);
+        receipt
+            .verify_with_context(&ctx, image_id)
+            .map_err(|e| VerifyError::Seal(e.into()))?;
+
+        // the risc0 decoder panics on journals that are not a whole number of
+        // words
+        if !receipt.journal.bytes.len().is_multiple_of(4) {
+            return Err(VerifyError::Journal(
+                risc0_zkvm::serde::Error::DeserializeUnexpectedEnd,
+            ));
+        }
+        receipt.journal.decode().map_err(VerifyError::Journal)
+    }
+
+    pub fn tx(&self, proof: &ProvedTx) -> Result<Tx, VerifyError> {
+        self.verify_and_decode(self.tx, &proof.risc0_receipt)
+    }
+
+    pub fn bundle(&self, proof: &ProvedBundle) -> Result<Bundle, VerifyError> {
+        self.verify_and_decode(self.bundle, &proof.risc0_receipt)
+    }
+
+    pub fn ledger(&self, proof: &ProvedLedgerTransition) -> Result<LedgerProofPublic, VerifyError> {
+        self.verify_and_decode(self.ledger, &proof.risc0_receipt)
+    }
+
+    pub fn batch(&self, proof: &ProvedBatch) -> Result<BatchProofPublic, VerifyError> {
+        self.verify_and_decode(self.batch, &proof.risc0_receipt)
+    }
+
+    /// Stfs are chosen by each zone, the caller provides the stf the zone is
+    /// expected to run instead of trusting the one on the proof.
+    pub fn stf(&self, stf: Stf, proof: &StfProof) -> Result<StfPublic, VerifyError> {
+        self.stfs.verify(self, stf, proof)
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use cl::mantle::{
+    ledger::LedgerState,
+    update::{BatchUpdate, Update},
+    ProofSystem, ZoneId, ZoneState,
+};
+use ledger::{
+    batch::ProvedBatch,
+    error::Error,
+    prover::ProverContext,
+    stf::StfProof,
+    update::{BatchVerifyError, ProvedBatchUpdate},
+};
+use ledger_proof_statements::ledger::LedgerProofPublic;
+
+const ZONE_A: ZoneId = [0u8; 32];
+
+fn zone_update(zone_id: ZoneId) -> Update {
+    let state = ZoneState {
+        stf: StfProof::nop_stf(),
+        zone_data: [0; 32],
+        ledger: LedgerState::default().to_witness().commit(),
+    };
+    Update {
+        zone_id,
+        old: state,
+        new: state,
+    }
+}
+
+#[test]
+fn batch_rejects_duplicate_zone() {
+    let proved_batch = ProvedBatchUpdate {
+        batch: BatchUpdate {
+            updates: vec![zone_update(ZONE_A), zone_update(ZONE_A)],
+        },
+        ledger_proofs: BTreeMap::new(),
+        stf_proofs: BTreeMap::new(),
+    };
+
+    assert!(matches!(
+        proved_batch.verify(),
+        Err(BatchVerifyError::DuplicateZone(ZONE_A))
+    ));
+}
+
+#[test]
+fn batch_rejects_missing_ledger_proof() {
+    let proved_batch = ProvedBatchUpdate {
+        batch: BatchUpdate {
+            updates: vec![zone_update(ZONE_A)],
+        },
+        ledger_proofs: BTreeMap::new(),
+        stf_proofs: BTreeMap::new(),
+    };
+
+    assert!(matches!(
+        proved_batch.verify(),
+        Err(BatchVerifyError::MissingLedgerProof(ZONE_A))
+    ));
+    assert!(matches!(
+        ProvedBatch::prove(&ProverContext::dev(), &proved_batch),
+        Err(Error::MissingLedgerPublic(ZONE_A))
+    ));
+}
+
+#[test]
+fn batch_execute_rejects_missing_ledger_public() {
+    let batch = BatchUpdate {
+        updates: vec![zone_update(ZONE_A)],
+    };
+
+    assert!(matches!(
+        ProvedBatch::execute(&batch, &BTreeMap::new(), &BTreeMap::new()),
+        Err(Error::MissingLedgerPublic(ZONE_A))
+    ));
+}
+
+#[test]
+fn batch_rejects_groth16_stfs() {
+    let update = zone_update(ZONE_A);
+    let ledger = update.old.ledger;
+    let batch = BatchUpdate {
+        updates: vec![update],
+    };
+    let ledgers = BTreeMap::from([(
+        ZONE_A,
+        LedgerProofPublic {
+            old_ledger: ledger,
+            ledger,
+            id: ZONE_A,
+            sync_logs: vec![],
+            synced: vec![],
+            outputs: vec![],
+        },
+    )]);
+    let stfs = BTreeMap::from([(ZONE_A, (ProofSystem::Groth16, StfProof::nop_image()))]);
+
+    assert!(matches!(
+        ProvedBatch::execute(&batch, &ledgers, &stfs),
+        Err(Error::UnbatchableStf {
+            zone_id: ZONE_A,
+            system: ProofSystem::Groth16,
+        })
+    ));
+}
@@ -0,0 +1,101 @@
+use cl::crust::{InputWitness, NullifierSecret, OutputWitness, TxWitness};
+use cl::mantle::ledger::LedgerState;
+use ledger::{
+    cache::{CacheKey, ReceiptCache},
+    covenant::SpendingCovenantProof,
+    prover::{ProverContext, ProverKind, ReceiptKind},
+    test_utils::{fake_receipt, nmo},
+    tx::ProvedTx,
+};
+
+const IMAGE_A: [u8; 32] = [1; 32];
+const IMAGE_B: [u8; 32] = [2; 32];
+
+fn cache_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("receipt-cache-{name}-{}", std::process::id()))
+}
+
+#[test]
+fn cache_key_covers_image_witness_and_opts() {
+    let succinct = ProverContext::default().opts();
+    let groth16 = ProverContext::new(ProverKind::Local, ReceiptKind::Groth16).opts();
+
+    let key = CacheKey::new(IMAGE_A, &[1u8; 32], &succinct);
+    assert_eq!(key, CacheKey::new(IMAGE_A, &[1u8; 32], &succinct));
+    assert_ne!(key, CacheKey::new(IMAGE_B, &[1u8; 32], &succinct));
+    assert_ne!(key, CacheKey::new(IMAGE_A, &[2u8; 32], &succinct));
+    assert_ne!(key, CacheKey::new(IMAGE_A, &[1u8; 32], &groth16));
+}
+
+#[test]
+fn cache_round_trip_verifies_receipts() {
+    let dir = cache_dir("round-trip");
+    let cache = ReceiptCache::new(&dir).unwrap();
+    let dev_opts = ProverContext::dev().opts();
+    let opts = ProverContext::default().opts();
+
+    let witness = [7u8; 32];
+    let key = CacheKey::new(IMAGE_A, &witness, &dev_opts);
+    assert!(cache.get(&key, IMAGE_A, &dev_opts).is_none());
+
+    let receipt = fake_receipt(IMAGE_A, vec![1, 2, 3, 4]);
+    cache.put(&key, &receipt).unwrap();
+
+    let cached = cache.get(&key, IMAGE_A, &dev_opts).unwrap();
+    assert_eq!(cached.journal.bytes, receipt.journal.bytes);
+
+    // fake receipts are only served to dev mode provers
+    assert!(cache.get(&key, IMAGE_A, &opts).is_none());
+    // receipts of another guest are never served
+    assert!(cache.get(&key, IMAGE_B, &dev_opts).is_none());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cache_ignores_corrupt_entries() {
+    let dir = cache_dir("corrupt");
+    let cache = ReceiptCache::new(&dir).unwrap();
+    let opts = ProverContext::dev().opts();
+
+    let key = CacheKey::new(IMAGE_A, &[0u8; 32], &opts);
+    std::fs::write(
+        dir.join(format!("{}.receipt", hex::encode(key.0))),
+        b"not a receipt",
+    )
+    .unwrap();
+
+    assert!(cache.get(&key, IMAGE_A, &opts).is_none());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn tx_cache_key_covers_covenant_receipts() {
+    let dir = cache_dir("covenants");
+    let ctx = ProverContext::dev().with_cache(ReceiptCache::new(&dir).unwrap());
+    let mut rng = rand::thread_rng();
+
+    let mut ledger = LedgerState::default();
+    let nf_sk = NullifierSecret::random(&mut rng);
+    let utxo = OutputWitness::new(10, nmo().unit(), nf_sk.commit(), [0; 32], &mut rng);
+    let cm_proof = ledger.add_commitment(&utxo.note_commitment());
+    let witness = TxWitness::default()
+        .add_input(InputWitness::from_output(utxo, nf_sk, nmo()), cm_proof)
+        .add_output(utxo, "");
+
+    let covenant =
+        |journal| SpendingCovenantProof::from_risc0([3; 8], fake_receipt([3; 32], journal));
+    let prove = |covenants| {
+        ProvedTx::prove(&ctx, witness.clone(), vec![], covenants).unwrap();
+        ctx.take_stats().pop().unwrap().cached
+    };
+
+    assert!(!prove(vec![covenant(vec![1, 0, 0, 0])]));
+    assert!(prove(vec![covenant(vec![1, 0, 0, 0])]));
+    // the same witness composing other covenant receipts is proven again
+    assert!(!prove(vec![covenant(vec![2, 0, 0, 0])]));
+    assert!(!prove(vec![]));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
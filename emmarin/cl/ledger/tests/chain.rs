@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+
+use cl::mantle::{
+    block::{BlockHash, BlockHeader},
+    ledger::LedgerState,
+    update::{BatchUpdate, Update},
+    ZoneId, ZoneState,
+};
+use ledger::{
+    batch::ProvedBatch,
+    chain::{Block, ChainError, ChainStore, ChainUpdate},
+    stf::StfProof,
+    verifier::Verifier,
+};
+use risc0_zkvm::{sha::Digest, FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+
+const ZONE_A: ZoneId = [0u8; 32];
+const ZONE_B: ZoneId = [1u8; 32];
+
+fn verifier() -> Verifier {
+    Verifier::default().with_dev_mode(true)
+}
+
+/// The `n`th state of a zone
+fn state(n: u8) -> ZoneState {
+    ZoneState {
+        stf: StfProof::nop_stf(),
+        zone_data: [n; 32],
+        ledger: LedgerState::default().to_witness().commit(),
+    }
+}
+
+fn prove(batch: &BatchUpdate) -> ProvedBatch {
+    let journal: Vec<u8> = risc0_zkvm::serde::to_vec(batch)
+        .unwrap()
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+        .collect();
+    let claim = ReceiptClaim::ok(Digest::from(verifier().batch), journal.clone());
+    ProvedBatch {
+        risc0_receipt: Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal),
+    }
+}
+
+/// A block moving each zone from its `old` to its `new` state
+fn block(parent: BlockHash, slot: u64, updates: &[(ZoneId, u8, u8)]) -> Block {
+    let batch = BatchUpdate {
+        updates: updates
+            .iter()
+            .map(|(zone_id, old, new)| Update {
+                zone_id: *zone_id,
+                old: state(*old),
+                new: state(*new),
+            })
+            .collect(),
+    };
+    Block {
+        proof: prove(&batch),
+        header: BlockHeader {
+            parent,
+            slot,
+            batch,
+        },
+    }
+}
+
+fn chain() -> ChainStore {
+    ChainStore::new(
+        verifier(),
+        BTreeMap::from_iter([(ZONE_A, state(0)), (ZONE_B, state(0))]),
+    )
+}
+
+#[test]
+fn blocks_chain_zone_states() {
+    let mut chain = chain();
+
+    let b1 = block(BlockHash::GENESIS, 1, &[(ZONE_A, 0, 1)]);
+    let b2 = block(b1.hash(), 2, &[(ZONE_A, 1, 2), (ZONE_B, 0, 1)]);
+    assert_eq!(chain.insert(b1.clone()).unwrap(), ChainUpdate::Extended);
+    assert_eq!(chain.insert(b2.clone()).unwrap(), ChainUpdate::Extended);
+
+    assert_eq!(chain.head(), b2.hash());
+    assert_eq!(chain.height(), 2);
+    assert_eq!(chain.zone_state(&ZONE_A), Some(&state(2)));
+    assert_eq!(chain.zone_state(&ZONE_B), Some(&state(1)));
+    assert_eq!(chain.zone_states_at(&b1.hash()).unwrap()[&ZONE_B], state(0));
+    assert_eq!(chain.block(&b1.hash()).unwrap().header, b1.header);
+}
+
+#[test]
+fn invalid_blocks_are_rejected() {
+    let mut chain = chain();
+    let b1 = block(BlockHash::GENESIS, 5, &[(ZONE_A, 0, 1)]);
+    chain.insert(b1.clone()).unwrap();
+
+    assert!(matches!(
+        chain.insert(b1.clone()),
+        Err(ChainError::DuplicateBlock(_))
+    ));
+    assert!(matches!(
+        chain.insert(block(BlockHash([9; 32]), 6, &[])),
+        Err(ChainError::UnknownParent(_))
+    ));
+    assert!(matches!(
+        chain.insert(block(b1.hash(), 5, &[])),
+        Err(ChainError::SlotNotIncreasing {
+            slot: 5,
+            parent_slot: 5
+        })
+    ));
+    // zone A is at state 1 after b1
+    assert!(matches!(
+        chain.insert(block(b1.hash(), 6, &[(ZONE_A, 0, 2)])),
+        Err(ChainError::ZoneStateMismatch(ZONE_A))
+    ));
+    assert!(matches!(
+        chain.insert(block(b1.hash(), 6, &[(ZONE_A, 1, 2), (ZONE_A, 2, 3)])),
+        Err(ChainError::DuplicateZone(ZONE_A))
+    ));
+    assert!(matches!(
+        chain.insert(block(b1.hash(), 6, &[([7; 32], 0, 1)])),
+        Err(ChainError::UnknownZone(_))
+    ));
+
+    // the proof must prove the batch of the block
+    let mut forged = block(b1.hash(), 6, &[(ZONE_A, 1, 2)]);
+    forged.proof = block(b1.hash(), 6, &[(ZONE_A, 1, 3)]).proof;
+    assert!(matches!(
+        chain.insert(forged),
+        Err(ChainError::BatchMismatch)
+    ));
+    let mut unproven = block(b1.hash(), 6, &[(ZONE_A, 1, 2)]);
+    unproven.proof.risc0_receipt.journal.bytes.pop();
+    assert!(matches!(
+        chain.insert(unproven),
+        Err(ChainError::InvalidProof(_))
+    ));
+
+    assert_eq!(chain.head(), b1.hash());
+    assert_eq!(chain.zone_state(&ZONE_A), Some(&state(1)));
+}
+
+#[test]
+fn longer_fork_reorgs_the_zone_states() {
+    let mut chain = chain();
+
+    // genesis <- a1 <- a2
+    //         <- b1 <- b2 <- b3
+    let a1 = block(BlockHash::GENESIS, 1, &[(ZONE_A, 0, 1)]);
+    let a2 = block(a1.hash(), 2, &[(ZONE_A, 1, 2)]);
+    let b1 = block(BlockHash::GENESIS, 1, &[(ZONE_A, 0, 10)]);
+    let b2 = block(b1.hash(), 3, &[(ZONE_B, 0, 10)]);
+    let b3 = block(b2.hash(), 4, &[(ZONE_A, 10, 11)]);
+
+    chain.insert(a1.clone()).unwrap();
+    chain.insert(a2.clone()).unwrap();
+    assert_eq!(chain.insert(b1.clone()).unwrap(), ChainUpdate::Forked);
+    // ties keep the first seen head
+    assert_eq!(chain.insert(b2.clone()).unwrap(), ChainUpdate::Forked);
+    assert_eq!(chain.head(), a2.hash());
+    assert_eq!(chain.zone_state(&ZONE_A), Some(&state(2)));
+
+    assert_eq!(
+        chain.insert(b3.clone()).unwrap(),
+        ChainUpdate::Reorg {
+            reverted: vec![a2.hash(), a1.hash()],
+            applied: vec![b1.hash(), b2.hash(), b3.hash()],
+        }
+    );
+    assert_eq!(chain.head(), b3.hash());
+    assert_eq!(chain.height(), 3);
+    assert_eq!(chain.zone_state(&ZONE_A), Some(&state(11)));
+    assert_eq!(chain.zone_state(&ZONE_B), Some(&state(10)));
+
+    // the old fork can still be extended
+    let a3 = block(a2.hash(), 5, &[(ZONE_B, 0, 1)]);
+    assert_eq!(chain.insert(a3).unwrap(), ChainUpdate::Forked);
+}
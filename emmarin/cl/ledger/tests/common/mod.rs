@@ -0,0 +1,114 @@
+//! Fixtures shared by the ledger tests.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use cl::{
+    crust::{
+        tx::{LedgerUpdate, Validity},
+        Balance, Bundle, BundleRoot, NoteCommitment, Nullifier,
+    },
+    ds::{
+        indexed::NullifierOrder,
+        mmr::{MMRProof, MMR},
+    },
+    mantle::{
+        ledger::{LedgerState, TransitionDelta},
+        ZoneId, ZoneState,
+    },
+};
+use ledger::test_utils;
+use ledger_proof_statements::ledger::{LedgerBundleWitness, LedgerProofPrivate};
+use rand::RngCore;
+
+/// The host ledger of a zone running the nop stf.
+pub struct ZoneWitness {
+    pub ledger: LedgerState,
+}
+
+impl ZoneWitness {
+    pub fn new() -> Self {
+        Self {
+            ledger: LedgerState::default(),
+        }
+    }
+
+    pub fn state(&self) -> ZoneState {
+        test_utils::zone_state(&self.ledger)
+    }
+
+    pub fn fill_nfs(&mut self, amount: usize, rng: impl RngCore) {
+        test_utils::fill_nfs(&mut self.ledger, amount, rng);
+    }
+
+    pub fn add_commitment(&mut self, cm: &NoteCommitment) -> (MMR, MMRProof) {
+        self.ledger.add_commitment(cm)
+    }
+}
+
+/// A local bundle of `zone_id` spending `nfs`, valid at any height of the
+/// default domain, its root is derived from `seed`.
+pub fn bundle(zone_id: ZoneId, seed: u8, nfs: Vec<Nullifier>) -> Bundle {
+    Bundle {
+        updates: BTreeMap::from_iter([(
+            zone_id,
+            vec![LedgerUpdate {
+                frontier_nodes: vec![],
+                inputs: nfs,
+                outputs: vec![],
+            }],
+        )]),
+        root: BundleRoot([seed; 32]),
+        fees: Balance::zero(),
+        fee_recipient: None,
+        validity: Validity::default(),
+        domain: [0; 32],
+        migrations: vec![],
+    }
+}
+
+/// The witness of `zone_id` settling `bundles` on `ledger`, built without the
+/// host checks so the guest is handed bundles it must reject itself.
+///
+/// The nullifier proofs insert the distinct nullifiers of the bundles.
+pub fn ledger_witness(
+    ledger: &LedgerState,
+    zone_id: ZoneId,
+    bundles: Vec<Bundle>,
+    nf_order: NullifierOrder,
+) -> LedgerProofPrivate {
+    let mut nfs: Vec<Nullifier> = bundles
+        .iter()
+        .flat_map(|bundle| &bundle.updates[&zone_id])
+        .flat_map(|update| update.inputs.iter().copied())
+        .collect();
+    nfs.sort();
+    nfs.dedup();
+
+    LedgerProofPrivate {
+        ledger: ledger.to_witness(),
+        id: zone_id,
+        bundles: bundles
+            .into_iter()
+            .map(|bundle| LedgerBundleWitness {
+                bundle,
+                cm_root_proofs: BTreeMap::new(),
+            })
+            .collect(),
+        nf_proofs: ledger.clone().add_sorted_nullifiers(&nfs),
+        nf_order,
+        synced: vec![],
+    }
+}
+
+/// [`ledger_witness`] with the nullifiers of the bundles sorted by the host.
+pub fn sorted_ledger_witness(
+    ledger: &LedgerState,
+    zone_id: ZoneId,
+    bundles: Vec<Bundle>,
+) -> LedgerProofPrivate {
+    let nf_order = TransitionDelta::new(zone_id, &bundles, &[])
+        .nullifier_order()
+        .unwrap();
+    ledger_witness(ledger, zone_id, bundles, nf_order)
+}
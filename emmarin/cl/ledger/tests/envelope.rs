@@ -0,0 +1,93 @@
+use ledger::{
+    bundle::ProvedBundle,
+    envelope::{EnvelopeError, Enveloped, JournalKind, ProofEnvelope, SealKind, ENVELOPE_VERSION},
+    stf::StfProof,
+    test_utils::fake_receipt,
+    tx::ProvedTx,
+};
+
+fn tx_id() -> [u8; 32] {
+    risc0_images::TX_IMAGE_ID
+}
+
+#[test]
+fn envelope_round_trip() {
+    let proof = ProvedTx {
+        risc0_receipt: fake_receipt(tx_id(), vec![1, 2, 3, 4]),
+    };
+
+    let envelope = ProofEnvelope::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+    assert_eq!(envelope.image_id, tx_id());
+    assert_eq!(envelope.journal, JournalKind::Tx);
+    assert_eq!(envelope.seal, SealKind::Fake);
+
+    let decoded = ProvedTx::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        decoded.risc0_receipt.journal.bytes,
+        proof.risc0_receipt.journal.bytes
+    );
+}
+
+#[test]
+fn envelope_keeps_stf_image() {
+    let stf = [9u8; 32];
+    let proof = StfProof::from_risc0(stf, fake_receipt(stf, vec![]));
+
+    let decoded = StfProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded.risc0_id, stf);
+    assert_eq!(decoded.stf(), proof.stf());
+}
+
+#[test]
+fn envelope_rejects_wrong_journal() {
+    let proof = ProvedTx {
+        risc0_receipt: fake_receipt(tx_id(), vec![]),
+    };
+
+    assert!(matches!(
+        ProvedBundle::from_bytes(&proof.to_bytes().unwrap()),
+        Err(EnvelopeError::JournalMismatch {
+            expected: JournalKind::Bundle,
+            actual: JournalKind::Tx
+        })
+    ));
+}
+
+#[test]
+fn envelope_rejects_wrong_image() {
+    let receipt = fake_receipt([9u8; 32], vec![]);
+    let envelope = ProofEnvelope::new([9u8; 32], JournalKind::Tx, &receipt).unwrap();
+
+    assert!(matches!(
+        ProvedTx::from_envelope(&envelope),
+        Err(EnvelopeError::ImageMismatch { .. })
+    ));
+}
+
+#[test]
+fn envelope_rejects_bad_header() {
+    let proof = ProvedTx {
+        risc0_receipt: fake_receipt(tx_id(), vec![]),
+    };
+
+    let mut envelope = proof.to_envelope().unwrap();
+    envelope.seal = SealKind::Succinct;
+    assert!(matches!(
+        ProvedTx::from_envelope(&envelope),
+        Err(EnvelopeError::SealMismatch {
+            expected: SealKind::Succinct,
+            actual: Some(SealKind::Fake)
+        })
+    ));
+
+    let mut bytes = proof.to_bytes().unwrap();
+    bytes[..2].copy_from_slice(&(ENVELOPE_VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        ProvedTx::from_bytes(&bytes),
+        Err(EnvelopeError::UnsupportedVersion(_))
+    ));
+    assert!(matches!(
+        ProvedTx::from_bytes(&bytes[..1]),
+        Err(EnvelopeError::Truncated)
+    ));
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use cl::{
+    crust::{
+        balance::UnitWitness, Bundle, InputWitness, NullifierSecret, OutputWitness, TxWitness,
+    },
+    mantle::{
+        update::{BatchUpdate, Update},
+        ProofSystem, ZoneId, ZoneState,
+    },
+};
+use ledger::{
+    batch::ProvedBatch, bundle::ProvedBundle, error::Error, ledger::ProvedLedgerTransition,
+    prover::ProverContext, stf::StfProof, test_utils::arbitrary_unit, tx::ProvedTx,
+    update::ProvedBatchUpdate,
+};
+use ledger_proof_statements::stf::StfPublic;
+use rand::{Rng, RngCore};
+
+use common::ZoneWitness;
+
+const ZONE_A: ZoneId = [0u8; 32];
+const ZONE_B: ZoneId = [1u8; 32];
+
+/// A tx migrating a fresh note of `unit` from zone A to zone B.
+fn migration_tx(zone_a: &mut ZoneWitness, unit: UnitWitness, mut rng: impl RngCore) -> TxWitness {
+    let sk = NullifierSecret::random(&mut rng);
+    let utxo = OutputWitness::new(
+        rng.gen_range(1..1000),
+        unit.unit(),
+        sk.commit(),
+        ZONE_A,
+        &mut rng,
+    );
+    let input = InputWitness::from_output(utxo, sk, unit);
+    let cm_proof = zone_a.ledger.add_commitment(&utxo.note_commitment());
+
+    TxWitness::default().migrate(input, cm_proof, ZONE_B)
+}
+
+fn batch(old: [ZoneState; 2], new: [ZoneState; 2]) -> BatchUpdate {
+    BatchUpdate {
+        updates: vec![
+            Update {
+                zone_id: ZONE_A,
+                old: old[0],
+                new: new[0],
+            },
+            Update {
+                zone_id: ZONE_B,
+                old: old[1],
+                new: new[1],
+            },
+        ],
+    }
+}
+
+#[test]
+fn zone_migration() {
+    let mut rng = rand::thread_rng();
+    let ctx = ProverContext::default();
+
+    let mut zone_a = ZoneWitness::new();
+    zone_a.fill_nfs(2_usize.pow(10), &mut rng);
+    let mut zone_b = ZoneWitness::new();
+
+    let tx_witness = migration_tx(&mut zone_a, arbitrary_unit(&mut rng), &mut rng);
+    let old = [zone_a.state(), zone_b.state()];
+
+    let proved_tx = ProvedTx::prove(&ctx, tx_witness, vec![], vec![]).unwrap();
+    let bundle = ProvedBundle::prove(&ctx, vec![proved_tx], vec![]).unwrap();
+    let synced = [bundle.public().root];
+
+    let ledger_proof_a = ProvedLedgerTransition::prove(
+        &ctx,
+        &mut zone_a.ledger,
+        ZONE_A,
+        vec![bundle.clone()],
+        &synced,
+    )
+    .unwrap();
+    let ledger_proof_b =
+        ProvedLedgerTransition::prove(&ctx, &mut zone_b.ledger, ZONE_B, vec![bundle], &synced)
+            .unwrap();
+
+    let new = [zone_a.state(), zone_b.state()];
+    let stf_proof_a = StfProof::prove_nop(
+        &ctx,
+        StfPublic {
+            old: old[0],
+            new: new[0],
+        },
+    )
+    .unwrap();
+    let stf_proof_b = StfProof::prove_nop(
+        &ctx,
+        StfPublic {
+            old: old[1],
+            new: new[1],
+        },
+    )
+    .unwrap();
+
+    let proved_batch = ProvedBatchUpdate {
+        batch: batch(old, new),
+        ledger_proofs: BTreeMap::from([(ZONE_A, ledger_proof_a), (ZONE_B, ledger_proof_b)]),
+        stf_proofs: BTreeMap::from([(ZONE_A, stf_proof_a), (ZONE_B, stf_proof_b)]),
+    };
+
+    proved_batch.verify().unwrap();
+}
+
+#[test]
+fn zone_migration_execute() {
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..4 {
+        let mut zone_a = ZoneWitness::new();
+        zone_a.fill_nfs(2_usize.pow(10), &mut rng);
+        let mut zone_b = ZoneWitness::new();
+
+        let tx_witness = migration_tx(&mut zone_a, arbitrary_unit(&mut rng), &mut rng);
+        let old = [zone_a.state(), zone_b.state()];
+
+        let tx = ProvedTx::execute(tx_witness, vec![], vec![])
+            .unwrap()
+            .public;
+        assert_eq!(tx.migrations.len(), 1);
+        let bundle = ProvedBundle::execute(vec![tx], vec![]).unwrap().public;
+        let synced = [bundle.root];
+
+        let ledger_a = ProvedLedgerTransition::execute(
+            &mut zone_a.ledger,
+            ZONE_A,
+            vec![bundle.clone()],
+            &synced,
+        )
+        .unwrap()
+        .public;
+        let ledger_b =
+            ProvedLedgerTransition::execute(&mut zone_b.ledger, ZONE_B, vec![bundle], &synced)
+                .unwrap()
+                .public;
+
+        let new = [zone_a.state(), zone_b.state()];
+        assert_eq!(ledger_a.ledger, new[0].ledger);
+        assert_eq!(ledger_b.ledger, new[1].ledger);
+
+        let ledgers = BTreeMap::from([(ZONE_A, ledger_a), (ZONE_B, ledger_b)]);
+        let nop = (ProofSystem::Risc0, StfProof::nop_image());
+        let stfs = BTreeMap::from([(ZONE_A, nop), (ZONE_B, nop)]);
+        let batch = batch(old, new);
+        let executed = ProvedBatch::execute(&batch, &ledgers, &stfs).unwrap();
+        assert_eq!(executed.public, batch);
+    }
+}
+
+#[test]
+fn unsettled_migration_is_rejected() {
+    let mut rng = rand::thread_rng();
+    let mut zone_a = ZoneWitness::new();
+    let tx_witness = migration_tx(&mut zone_a, arbitrary_unit(&mut rng), &mut rng);
+    let tx = tx_witness.commit(
+        &tx_witness.mint_amounts(),
+        &tx_witness.burn_amounts(),
+        &tx_witness.inputs_derived_fields(),
+    );
+    let mut bundle: Bundle = cl::crust::BundleWitness::new(vec![tx]).commit();
+    // zone B withholds the migrated note
+    bundle.updates.get_mut(&ZONE_B).unwrap()[0].outputs.clear();
+
+    let mut zone_b = ZoneWitness::new();
+    let before = zone_b.ledger.to_witness();
+    let err =
+        ProvedLedgerTransition::execute(&mut zone_b.ledger, ZONE_B, vec![bundle], &[]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::UnsettledMigration { zone_id, .. } if zone_id == ZONE_B
+    ));
+    assert_eq!(zone_b.ledger.to_witness(), before);
+}
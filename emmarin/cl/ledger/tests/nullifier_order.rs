@@ -0,0 +1,53 @@
+mod common;
+
+use cl::{
+    crust::Nullifier,
+    mantle::{ledger::LedgerState, ZoneId},
+};
+use ledger::{error::Error, ledger::ProvedLedgerTransition, test_utils};
+
+use common::{bundle, ledger_witness};
+
+const ZONE: ZoneId = [0u8; 32];
+
+#[test]
+fn duplicate_nullifier_across_bundles_is_rejected() {
+    let mut ledger = LedgerState::default();
+    let before = ledger.to_witness();
+
+    let bundles = vec![
+        bundle(ZONE, 1, vec![Nullifier([3; 32]), Nullifier([1; 32])]),
+        bundle(ZONE, 2, vec![Nullifier([2; 32]), Nullifier([3; 32])]),
+    ];
+
+    let err = ProvedLedgerTransition::execute(&mut ledger, ZONE, bundles, &[]).unwrap_err();
+    assert!(matches!(err, Error::DuplicateNullifier(nf) if nf == Nullifier([3; 32])));
+    // the ledger is left untouched
+    assert_eq!(ledger.to_witness(), before);
+}
+
+#[test]
+fn guest_rejects_duplicate_nullifier_across_bundles() {
+    let ledger = LedgerState::default();
+
+    // the guest accepts the bundles when they spend distinct nullifiers
+    let bundles = vec![
+        bundle(ZONE, 1, vec![Nullifier([3; 32]), Nullifier([1; 32])]),
+        bundle(ZONE, 2, vec![Nullifier([2; 32]), Nullifier([4; 32])]),
+    ];
+    let nf_order = test_utils::nullifier_order(vec![1, 2, 0, 3]);
+    let witness = ledger_witness(&ledger, ZONE, bundles, nf_order);
+    assert!(ProvedLedgerTransition::execute_witness(&witness).is_ok());
+
+    let bundles = vec![
+        bundle(ZONE, 1, vec![Nullifier([3; 32]), Nullifier([1; 32])]),
+        bundle(ZONE, 2, vec![Nullifier([2; 32]), Nullifier([3; 32])]),
+    ];
+    // sorts [3, 1, 2, 3] into [1, 2, 3, 3], which the host refuses to build
+    let nf_order = test_utils::nullifier_order(vec![1, 2, 0, 3]);
+    let witness = ledger_witness(&ledger, ZONE, bundles, nf_order);
+    assert!(matches!(
+        ProvedLedgerTransition::execute_witness(&witness),
+        Err(Error::Risc0ExecutionFailed)
+    ));
+}
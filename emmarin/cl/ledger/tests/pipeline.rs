@@ -0,0 +1,216 @@
+use cl::{
+    crust::{InputWitness, NullifierSecret, OutputWitness, TxWitness},
+    mantle::{ledger::LedgerState, ZoneId},
+};
+use ledger::{
+    error::Error,
+    pipeline::{Pipeline, Progress, Stage, ZoneJob},
+    prover::ProverContext,
+    test_utils::nmo,
+};
+
+const ZONE_A: ZoneId = [0u8; 32];
+const ZONE_B: ZoneId = [1u8; 32];
+
+fn transfer(
+    ledger: &mut LedgerState,
+    from_zone: ZoneId,
+    to_zone: ZoneId,
+    value: u64,
+    amount: u64,
+) -> TxWitness {
+    let mut rng = rand::thread_rng();
+    let sender = NullifierSecret::random(&mut rng);
+    let receiver = NullifierSecret::random(&mut rng);
+
+    let utxo = OutputWitness::new(value, nmo().unit(), sender.commit(), from_zone, &mut rng);
+    let cm_proof = ledger.add_commitment(&utxo.note_commitment());
+    let input = InputWitness::from_output(utxo, sender, nmo());
+
+    let (transfer, change) =
+        OutputWitness::spend_with_change(input, amount, receiver.commit(), to_zone, &mut rng);
+
+    TxWitness::default()
+        .add_input(input, cm_proof)
+        .add_output(transfer, "")
+        .add_output(change, "")
+}
+
+#[test]
+fn pipeline_proves_cross_zone_block() {
+    let ctx = ProverContext::dev();
+
+    let mut ledger_a = LedgerState::default();
+    let mut ledger_b = LedgerState::default();
+
+    // bundle 0 moves notes from zone A to zone B, bundle 1 stays within zone B
+    let cross_tx = transfer(&mut ledger_a, ZONE_A, ZONE_B, 10, 8);
+    let local_tx = transfer(&mut ledger_b, ZONE_B, ZONE_B, 5, 3);
+
+    let mut progress = Vec::new();
+    let output = Pipeline::new(&ctx)
+        .with_workers(2)
+        .with_progress(|p: &Progress| progress.push(*p))
+        .prove(
+            vec![vec![cross_tx.into()].into(), vec![local_tx.into()].into()],
+            vec![
+                ZoneJob {
+                    zone_id: ZONE_A,
+                    ledger: &mut ledger_a,
+                    bundles: vec![0],
+                },
+                ZoneJob {
+                    zone_id: ZONE_B,
+                    ledger: &mut ledger_b,
+                    bundles: vec![0, 1],
+                },
+            ],
+        )
+        .unwrap();
+
+    assert_eq!(output.bundles.len(), 2);
+    let cross_root = output.bundles[0].public().root;
+
+    let ledger_proof_a = output.ledgers[&ZONE_A].public();
+    let ledger_proof_b = output.ledgers[&ZONE_B].public();
+    assert_eq!(ledger_proof_a.ledger, ledger_a.to_witness().commit());
+    assert_eq!(ledger_proof_b.ledger, ledger_b.to_witness().commit());
+
+    // both zones settled the cross zone bundle in the same pipeline
+    assert!(!ledger_a.syncs.is_pending(&cross_root));
+    assert!(!ledger_b.syncs.is_pending(&cross_root));
+
+    assert_eq!(progress.len(), 6);
+    assert!(progress
+        .iter()
+        .enumerate()
+        .all(|(i, p)| p.done == i + 1 && p.total == 6));
+    assert_eq!(
+        progress
+            .iter()
+            .filter(|p| matches!(p.stage, Stage::Ledger { .. }))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn pipeline_acks_bundles_pending_from_earlier_batches() {
+    let ctx = ProverContext::dev();
+
+    let mut ledger_a = LedgerState::default();
+    let mut ledger_b = LedgerState::default();
+    let cross_tx = transfer(&mut ledger_a, ZONE_A, ZONE_B, 10, 8);
+
+    // zone A settles its half first, zone B is left out of the batch
+    let output = Pipeline::new(&ctx)
+        .prove(
+            vec![vec![cross_tx.clone().into()].into()],
+            vec![ZoneJob {
+                zone_id: ZONE_A,
+                ledger: &mut ledger_a,
+                bundles: vec![0],
+            }],
+        )
+        .unwrap();
+    let cross_root = output.bundles[0].public().root;
+    assert!(ledger_a.syncs.is_pending(&cross_root));
+    assert!(output.ledgers[&ZONE_A].public().synced.is_empty());
+
+    // zone B settles its half in a later batch, both zones acknowledge it
+    let output = Pipeline::new(&ctx)
+        .prove(
+            vec![vec![cross_tx.into()].into()],
+            vec![
+                ZoneJob {
+                    zone_id: ZONE_A,
+                    ledger: &mut ledger_a,
+                    bundles: vec![],
+                },
+                ZoneJob {
+                    zone_id: ZONE_B,
+                    ledger: &mut ledger_b,
+                    bundles: vec![0],
+                },
+            ],
+        )
+        .unwrap();
+
+    for zone_id in [ZONE_A, ZONE_B] {
+        let synced = output.ledgers[&zone_id].public().synced;
+        assert_eq!(
+            Vec::from_iter(synced.iter().map(|log| log.bundle)),
+            [cross_root]
+        );
+    }
+    assert!(!ledger_a.syncs.is_pending(&cross_root));
+    assert!(!ledger_b.syncs.is_pending(&cross_root));
+}
+
+#[test]
+fn pipeline_rejects_duplicate_zones() {
+    let ctx = ProverContext::dev();
+    let mut ledger = LedgerState::default();
+    let mut copy = LedgerState::default();
+
+    let result = Pipeline::new(&ctx).prove(
+        vec![],
+        vec![
+            ZoneJob {
+                zone_id: ZONE_A,
+                ledger: &mut ledger,
+                bundles: vec![],
+            },
+            ZoneJob {
+                zone_id: ZONE_A,
+                ledger: &mut copy,
+                bundles: vec![],
+            },
+        ],
+    );
+    assert!(matches!(result, Err(Error::DuplicateZone(ZONE_A))));
+}
+
+#[test]
+fn pipeline_rejects_bad_zone_bundles() {
+    let ctx = ProverContext::dev();
+    let mut ledger_a = LedgerState::default();
+    let mut ledger_b = LedgerState::default();
+
+    let result = Pipeline::new(&ctx).prove(
+        vec![],
+        vec![ZoneJob {
+            zone_id: ZONE_A,
+            ledger: &mut ledger_a,
+            bundles: vec![0],
+        }],
+    );
+    assert!(matches!(
+        result,
+        Err(Error::UnknownBundle {
+            zone_id: ZONE_A,
+            bundle: 0
+        })
+    ));
+
+    // zone B settles a bundle only involving zone A, the pipeline fails
+    // instead of hanging on the worker
+    let local_tx = transfer(&mut ledger_a, ZONE_A, ZONE_A, 5, 3);
+    let before = ledger_b.to_witness();
+    let result = Pipeline::new(&ctx).with_workers(2).prove(
+        vec![vec![local_tx.into()].into()],
+        vec![ZoneJob {
+            zone_id: ZONE_B,
+            ledger: &mut ledger_b,
+            bundles: vec![0],
+        }],
+    );
+    assert!(matches!(
+        result,
+        Err(Error::ForeignBundle {
+            zone_id: ZONE_B,
+            ..
+        })
+    ));
+    assert_eq!(ledger_b.to_witness(), before);
+}
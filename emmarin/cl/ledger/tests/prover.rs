@@ -0,0 +1,14 @@
+use ledger::prover::{ProverContext, ProverKind, ReceiptKind};
+
+#[test]
+fn prover_context_opts() {
+    let ctx = ProverContext::new(ProverKind::Dev, ReceiptKind::Composite);
+    let opts = ctx.opts();
+    assert!(opts.dev_mode());
+    assert_eq!(opts.receipt_kind, ReceiptKind::Composite);
+
+    let ctx = ProverContext::default();
+    assert_eq!(ctx.kind, ProverKind::Local);
+    assert_eq!(ctx.opts().receipt_kind, ReceiptKind::Succinct);
+    assert!(ctx.stats().is_empty());
+}
@@ -1,36 +1,28 @@
+mod common;
+
+use std::collections::BTreeMap;
+
 use cl::{
-    crust::{
-        balance::{UnitWitness, NOP_COVENANT},
-        InputWitness, NoteCommitment, Nullifier, NullifierCommitment,
-        NullifierSecret, OutputWitness, TxWitness,
-    },
+    crust::{InputWitness, NullifierCommitment, NullifierSecret, OutputWitness, TxWitness},
     ds::mmr::{MMRProof, MMR},
     mantle::{
         ledger::LedgerState,
         update::{BatchUpdate, Update},
-        ZoneId, ZoneState,
+        ProofSystem, ZoneId,
     },
 };
 use ledger::{
-    bundle::ProvedBundle, ledger::ProvedLedgerTransition, stf::StfProof, tx::ProvedTx,
-    update::ProvedBatchUpdate,
+    batch::ProvedBatch, bundle::ProvedBundle, ledger::ProvedLedgerTransition,
+    prover::ProverContext, stf::StfProof, test_utils::nmo, tx::ProvedTx, update::ProvedBatchUpdate,
 };
-use ledger_proof_statements::stf::StfPublic;
-use rand::{Rng, RngCore};
+use ledger_proof_statements::{ledger::LedgerProofPublic, stf::StfPublic};
 use rand_core::CryptoRngCore;
 
+use common::ZoneWitness;
+
 const ZONE_A: ZoneId = [0u8; 32];
 const ZONE_B: ZoneId = [1u8; 32];
 
-fn nmo() -> UnitWitness {
-    UnitWitness {
-        spending_covenant: NOP_COVENANT,
-        minting_covenant: NOP_COVENANT,
-        burning_covenant: NOP_COVENANT,
-        arg: [0; 32],
-    }
-}
-
 struct User(NullifierSecret);
 
 impl User {
@@ -47,80 +39,83 @@ impl User {
     }
 }
 
-fn cross_transfer_transition(
+fn cross_transfer_tx(
     input: InputWitness,
     input_proof: (MMR, MMRProof),
     to: User,
     amount: u64,
     to_zone: ZoneId,
-    ledger_in: &mut LedgerState,
-    ledger_out: &mut LedgerState,
-) -> (ProvedLedgerTransition, ProvedLedgerTransition) {
+) -> TxWitness {
     assert!(amount <= input.value);
     let mut rng = rand::thread_rng();
 
     let (transfer, change) =
         OutputWitness::spend_with_change(input, amount, to.pk(), to_zone, &mut rng);
 
-    let tx_witness = TxWitness::default()
+    TxWitness::default()
         .add_input(input, input_proof)
         .add_output(transfer, "")
-        .add_output(change, "");
+        .add_output(change, "")
+}
 
+fn cross_transfer_transition(
+    ctx: &ProverContext,
+    tx_witness: TxWitness,
+    from_zone: ZoneId,
+    to_zone: ZoneId,
+    ledger_in: &mut LedgerState,
+    ledger_out: &mut LedgerState,
+) -> (ProvedLedgerTransition, ProvedLedgerTransition) {
     let proved_tx = ProvedTx::prove(
-        tx_witness.clone(),
+        ctx,
+        tx_witness,
         vec![],
         vec![], // we can skip covenant proofs since NMO uses no-op spend covenants
     )
     .unwrap();
 
-    let bundle = ProvedBundle::prove(vec![proved_tx]);
+    let bundle = ProvedBundle::prove(ctx, vec![proved_tx], vec![]).unwrap();
+    // both zones settle the bundle in the same batch
+    let synced = [bundle.public().root];
 
     println!("proving ledger A transition");
     let ledger_in_transition =
-        ProvedLedgerTransition::prove(ledger_in, input.zone_id, vec![bundle.clone()]);
+        ProvedLedgerTransition::prove(ctx, ledger_in, from_zone, vec![bundle.clone()], &synced)
+            .unwrap();
 
     println!("proving ledger B transition");
-    let ledger_out_transition = ProvedLedgerTransition::prove(ledger_out, to_zone, vec![bundle]);
+    let ledger_out_transition =
+        ProvedLedgerTransition::prove(ctx, ledger_out, to_zone, vec![bundle], &synced).unwrap();
 
     (ledger_in_transition, ledger_out_transition)
 }
 
-struct ZoneWitness {
-    ledger: LedgerState,
-}
-
-impl ZoneWitness {
-    fn new() -> Self {
-        let ledger = LedgerState::default();
-
-        Self { ledger }
-    }
-
-    fn state(&self) -> ZoneState {
-        ZoneState {
-            stf: StfProof::nop_stf(),
-            zone_data: [0; 32],
-            ledger: self.ledger.to_witness().commit(),
-        }
-    }
+fn cross_transfer_execute(
+    tx_witness: TxWitness,
+    from_zone: ZoneId,
+    to_zone: ZoneId,
+    ledger_in: &mut LedgerState,
+    ledger_out: &mut LedgerState,
+) -> (LedgerProofPublic, LedgerProofPublic) {
+    let tx = ProvedTx::execute(tx_witness, vec![], vec![])
+        .unwrap()
+        .public;
+    let bundle = ProvedBundle::execute(vec![tx], vec![]).unwrap().public;
+    let synced = [bundle.root];
 
-    fn fill_nfs(&mut self, amount: usize, mut rng: impl RngCore) {
-        self.ledger.add_nullifiers(
-            std::iter::repeat_with(|| Nullifier(rng.gen()))
-                .take(amount)
-                .collect(),
-        );
-    }
+    let ledger_in_transition =
+        ProvedLedgerTransition::execute(ledger_in, from_zone, vec![bundle.clone()], &synced)
+            .unwrap();
+    let ledger_out_transition =
+        ProvedLedgerTransition::execute(ledger_out, to_zone, vec![bundle], &synced).unwrap();
 
-    fn add_commitment(&mut self, cm: &NoteCommitment) -> (MMR, MMRProof) {
-        self.ledger.add_commitment(cm)
-    }
+    (ledger_in_transition.public, ledger_out_transition.public)
 }
 
 #[test]
 fn zone_update_cross() {
     let mut rng = rand::thread_rng();
+    let ctx = ProverContext::default();
 
     // Alice is sending 8 NMO to bob.
     let alice = User::random(&mut rng);
@@ -139,11 +134,11 @@ fn zone_update_cross() {
 
     let (zone_a_old, zone_b_old) = (zone_a.state(), zone_b.state());
 
+    let tx_witness = cross_transfer_tx(alice_input, alice_cm_proof, bob, 8, ZONE_B);
     let (ledger_proof_a, ledger_proof_b) = cross_transfer_transition(
-        alice_input,
-        alice_cm_proof,
-        bob,
-        8,
+        &ctx,
+        tx_witness,
+        ZONE_A,
         ZONE_B,
         &mut zone_a.ledger,
         &mut zone_b.ledger,
@@ -151,23 +146,33 @@ fn zone_update_cross() {
 
     let (zone_a_new, zone_b_new) = (zone_a.state(), zone_b.state());
 
-    let stf_proof_a = StfProof::prove_nop(StfPublic {
-        old: zone_a_old,
-        new: zone_a_new,
-    });
+    let stf_proof_a = StfProof::prove_nop(
+        &ctx,
+        StfPublic {
+            old: zone_a_old,
+            new: zone_a_new,
+        },
+    )
+    .unwrap();
 
-    let stf_proof_b = StfProof::prove_nop(StfPublic {
-        old: zone_b_old,
-        new: zone_b_new,
-    });
+    let stf_proof_b = StfProof::prove_nop(
+        &ctx,
+        StfPublic {
+            old: zone_b_old,
+            new: zone_b_new,
+        },
+    )
+    .unwrap();
 
     let batch = BatchUpdate {
         updates: vec![
             Update {
+                zone_id: ZONE_A,
                 old: zone_a_old,
                 new: zone_a_new,
             },
             Update {
+                zone_id: ZONE_B,
                 old: zone_b_old,
                 new: zone_b_new,
             },
@@ -176,9 +181,82 @@ fn zone_update_cross() {
 
     let proved_batch = ProvedBatchUpdate {
         batch,
-        ledger_proofs: vec![ledger_proof_a, ledger_proof_b],
-        stf_proofs: vec![stf_proof_a, stf_proof_b],
+        ledger_proofs: BTreeMap::from([(ZONE_A, ledger_proof_a), (ZONE_B, ledger_proof_b)]),
+        stf_proofs: BTreeMap::from([(ZONE_A, stf_proof_a), (ZONE_B, stf_proof_b)]),
+    };
+
+    proved_batch.verify().unwrap();
+
+    // aggregate the whole batch into a single proof
+    let batch_proof = ProvedBatch::prove(&ctx, &proved_batch).unwrap();
+    assert!(batch_proof.verify());
+    assert_eq!(batch_proof.public(), proved_batch.batch);
+
+    // every proof of the batch went through the prover context
+    let stats = ctx.stats();
+    assert_eq!(
+        stats.iter().map(|s| s.guest).collect::<Vec<_>>(),
+        ["tx", "bundle", "ledger", "ledger", "stf", "stf", "batch"]
+    );
+    for s in stats.iter().filter(|s| !s.cached) {
+        assert!(s.user_cycles > 0 && s.total_cycles >= s.user_cycles);
+    }
+}
+
+#[test]
+fn zone_update_cross_execute() {
+    let mut rng = rand::thread_rng();
+
+    let alice = User::random(&mut rng);
+    let bob = User::random(&mut rng);
+
+    let utxo = OutputWitness::new(10, nmo().unit(), alice.pk(), ZONE_A, &mut rng);
+    let alice_input = InputWitness::from_output(utxo, alice.sk(), nmo());
+
+    let mut zone_a = ZoneWitness::new();
+    zone_a.fill_nfs(2_usize.pow(10), &mut rng);
+    let alice_cm_proof = zone_a.add_commitment(&utxo.note_commitment());
+
+    let mut zone_b = ZoneWitness::new();
+
+    let (zone_a_old, zone_b_old) = (zone_a.state(), zone_b.state());
+
+    let tx_witness = cross_transfer_tx(alice_input, alice_cm_proof, bob, 8, ZONE_B);
+    let (ledger_a, ledger_b) = cross_transfer_execute(
+        tx_witness,
+        ZONE_A,
+        ZONE_B,
+        &mut zone_a.ledger,
+        &mut zone_b.ledger,
+    );
+
+    let (zone_a_new, zone_b_new) = (zone_a.state(), zone_b.state());
+    assert_eq!(ledger_a.ledger, zone_a_new.ledger);
+    assert_eq!(ledger_b.ledger, zone_b_new.ledger);
+
+    for (old, new) in [(zone_a_old, zone_a_new), (zone_b_old, zone_b_new)] {
+        let stf = StfProof::execute_nop(StfPublic { old, new }).unwrap();
+        assert_eq!(stf.public, StfPublic { old, new });
+    }
+
+    let batch = BatchUpdate {
+        updates: vec![
+            Update {
+                zone_id: ZONE_A,
+                old: zone_a_old,
+                new: zone_a_new,
+            },
+            Update {
+                zone_id: ZONE_B,
+                old: zone_b_old,
+                new: zone_b_new,
+            },
+        ],
     };
 
-    assert!(proved_batch.verify());
+    let ledgers = BTreeMap::from([(ZONE_A, ledger_a), (ZONE_B, ledger_b)]);
+    let nop = (ProofSystem::Risc0, StfProof::nop_image());
+    let stfs = BTreeMap::from([(ZONE_A, nop), (ZONE_B, nop)]);
+    let executed = ProvedBatch::execute(&batch, &ledgers, &stfs).unwrap();
+    assert_eq!(executed.public, batch);
 }
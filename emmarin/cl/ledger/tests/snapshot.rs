@@ -0,0 +1,202 @@
+use cl::{
+    crust::{BundleRoot, NoteCommitment, Nullifier},
+    mantle::{
+        ledger::{LedgerMode, LedgerState, TransitionDelta},
+        ZoneId,
+    },
+};
+use ledger::{
+    snapshot::{LedgerDelta, SnapshotError, ZoneSnapshot, SNAPSHOT_VERSION},
+    test_utils::{self, zone_state},
+};
+
+const ZONE_A: ZoneId = [0u8; 32];
+
+fn transition(seed: u8) -> TransitionDelta {
+    test_utils::transition(ZONE_A, seed)
+}
+
+#[test]
+fn snapshot_round_trip() {
+    let mut ledger = LedgerState::default();
+    transition(1).apply(&mut ledger).unwrap();
+    transition(10).apply(&mut ledger).unwrap();
+
+    let state = zone_state(&ledger);
+    let snapshot = ZoneSnapshot::new(ZONE_A, state, &ledger);
+    assert_eq!(snapshot.height(), 2);
+
+    let imported = ZoneSnapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+    let (imported_state, imported_ledger) = imported.import(ZONE_A, &state.ledger).unwrap();
+
+    assert_eq!(imported_state, state);
+    assert_eq!(imported_ledger.to_witness(), ledger.to_witness());
+}
+
+#[test]
+fn snapshot_rejects_tampered_ledger() {
+    let mut ledger = LedgerState::default();
+    transition(1).apply(&mut ledger).unwrap();
+    let state = zone_state(&ledger);
+
+    let mut snapshot = ZoneSnapshot::new(ZONE_A, state, &ledger);
+    snapshot.ledger.add_commitment(&NoteCommitment([42; 32]));
+
+    assert!(matches!(
+        snapshot.import(ZONE_A, &state.ledger),
+        Err(SnapshotError::LedgerMismatch)
+    ));
+}
+
+#[test]
+fn snapshot_rejects_malformed_mmr() {
+    let mut ledger = LedgerState::default();
+    transition(1).apply(&mut ledger).unwrap();
+    let state = zone_state(&ledger);
+
+    for height in [0, 65] {
+        let mut snapshot = ZoneSnapshot::new(ZONE_A, state, &ledger);
+        snapshot.ledger.bundles.roots[0].height = height;
+
+        assert!(matches!(
+            ZoneSnapshot::from_bytes(&snapshot.to_bytes()),
+            Err(SnapshotError::MalformedMmr)
+        ));
+        assert!(matches!(
+            snapshot.import(ZONE_A, &state.ledger),
+            Err(SnapshotError::MalformedMmr)
+        ));
+    }
+}
+
+#[test]
+fn snapshot_rejects_tampered_archive() {
+    let mut ledger = LedgerState::new(LedgerMode::Archival);
+    transition(1).apply(&mut ledger).unwrap();
+    let state = zone_state(&ledger);
+
+    let mut snapshot = ZoneSnapshot::new(ZONE_A, state, &ledger);
+    snapshot.ledger.archive.as_mut().unwrap().commitments[0] = NoteCommitment([42; 32]);
+
+    assert!(matches!(
+        snapshot.import(ZONE_A, &state.ledger),
+        Err(SnapshotError::ArchiveMismatch)
+    ));
+}
+
+#[test]
+fn snapshot_rejects_unknown_version() {
+    let ledger = LedgerState::default();
+    let mut bytes = ZoneSnapshot::new(ZONE_A, zone_state(&ledger), &ledger).to_bytes();
+    bytes[..2].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+
+    assert!(matches!(
+        ZoneSnapshot::from_bytes(&bytes),
+        Err(SnapshotError::UnsupportedVersion(v)) if v == SNAPSHOT_VERSION + 1
+    ));
+    assert!(matches!(
+        ZoneSnapshot::from_bytes(&bytes[..1]),
+        Err(SnapshotError::Truncated)
+    ));
+}
+
+#[test]
+fn delta_catches_up_from_snapshot() {
+    // the live executor keeps applying transitions after the snapshot is taken
+    let mut live = LedgerState::default();
+    transition(1).apply(&mut live).unwrap();
+    let snapshot = ZoneSnapshot::new(ZONE_A, zone_state(&live), &live);
+
+    let from = live.to_witness().commit();
+    let from_height = live.bundles.len();
+    let transitions = vec![transition(10), transition(20)];
+    for t in &transitions {
+        t.apply(&mut live).unwrap();
+    }
+    let delta = LedgerDelta {
+        zone_id: ZONE_A,
+        from,
+        from_height,
+        transitions,
+        zone_state: zone_state(&live),
+    };
+
+    let (_, mut ledger) = ZoneSnapshot::from_bytes(&snapshot.to_bytes())
+        .unwrap()
+        .import(ZONE_A, &from)
+        .unwrap();
+    let delta = LedgerDelta::from_bytes(&delta.to_bytes()).unwrap();
+    let state = delta.apply(ZONE_A, &mut ledger).unwrap();
+
+    assert_eq!(state.ledger, live.to_witness().commit());
+    assert_eq!(ledger.to_witness(), live.to_witness());
+
+    // the delta does not apply twice
+    assert!(matches!(
+        delta.apply(ZONE_A, &mut ledger),
+        Err(SnapshotError::HeightMismatch {
+            expected: 1,
+            actual: 3
+        })
+    ));
+}
+
+#[test]
+fn snapshot_rejects_other_zone() {
+    let ledger = LedgerState::default();
+    let state = zone_state(&ledger);
+    let snapshot = ZoneSnapshot::new(ZONE_A, state, &ledger);
+
+    assert!(matches!(
+        snapshot.import([1; 32], &state.ledger),
+        Err(SnapshotError::ZoneMismatch { actual: ZONE_A, .. })
+    ));
+}
+
+#[test]
+fn delta_rejects_invalid_transitions() {
+    let mut ledger = LedgerState::default();
+    transition(1).apply(&mut ledger).unwrap();
+    let delta = |transition: TransitionDelta| LedgerDelta {
+        zone_id: ZONE_A,
+        from: ledger.to_witness().commit(),
+        from_height: ledger.bundles.len(),
+        transitions: vec![transition],
+        zone_state: zone_state(&ledger),
+    };
+    let mut target = ledger.clone();
+
+    assert!(matches!(
+        delta(transition(10)).apply([1; 32], &mut target),
+        Err(SnapshotError::ZoneMismatch { .. })
+    ));
+
+    let mut foreign = transition(10);
+    foreign.bundles[0].zones = vec![[1; 32]];
+    assert!(matches!(
+        delta(foreign).apply(ZONE_A, &mut target),
+        Err(SnapshotError::ForeignBundle(_))
+    ));
+
+    let mut duplicate = transition(10);
+    duplicate.bundles[0].nullifiers[1] = Nullifier([10; 32]);
+    assert!(matches!(
+        delta(duplicate).apply(ZONE_A, &mut target),
+        Err(SnapshotError::DuplicateNullifier(nf)) if nf == Nullifier([10; 32])
+    ));
+
+    // nullifiers of the first transition are already spent
+    assert!(matches!(
+        delta(transition(2)).apply(ZONE_A, &mut target),
+        Err(SnapshotError::SpentNullifier(nf)) if nf == Nullifier([2; 32])
+    ));
+
+    let mut unsettled_ack = transition(10);
+    unsettled_ack.synced = vec![BundleRoot([42; 32])];
+    assert!(matches!(
+        delta(unsettled_ack).apply(ZONE_A, &mut target),
+        Err(SnapshotError::InvalidSync(root)) if root == BundleRoot([42; 32])
+    ));
+
+    assert_eq!(target.to_witness(), ledger.to_witness());
+}
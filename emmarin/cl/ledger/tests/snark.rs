@@ -0,0 +1,140 @@
+use cl::crust::Bundle;
+use ledger::{
+    bundle::ProvedBundle,
+    envelope::SealKind,
+    prover::ProverContext,
+    snark::{verifying_key, Calldata, Groth16Proof, Groth16VerifyingKey, SnarkError},
+    test_utils::fake_receipt,
+    verifier::{Verifier, VerifyError},
+};
+use risc0_zkvm::{
+    sha::{Digest, Digestible},
+    Groth16Receipt, InnerReceipt, Receipt, ReceiptClaim,
+};
+
+const IMAGE: [u8; 32] = [1u8; 32];
+
+/// A groth16 receipt with a made up seal, enough to build calldata from.
+fn fake_groth16_receipt(image_id: [u8; 32], journal: Vec<u8>) -> Receipt {
+    let claim = ReceiptClaim::ok(Digest::from(image_id), journal.clone());
+    let params = Digest::from(verifying_key().verifier_parameters);
+    let seal = (0..256).map(|i| i as u8).collect();
+    Receipt::new(
+        InnerReceipt::Groth16(Groth16Receipt::new(seal, claim.into(), params)),
+        journal,
+    )
+}
+
+#[test]
+fn calldata_abi_round_trip() {
+    let calldata = Calldata {
+        seal: (0..260).map(|i| i as u8).collect(),
+        image_id: IMAGE,
+        journal_digest: [2u8; 32],
+    };
+
+    let bytes = calldata.abi_encode();
+    // 3 head words, the seal length and the seal padded to whole words
+    assert_eq!(bytes.len(), 4 * 32 + 288);
+    assert_eq!(bytes[31], 0x60);
+    assert_eq!(&bytes[32..64], &IMAGE);
+    assert_eq!(&bytes[96 + 30..128], &260u16.to_be_bytes());
+
+    assert_eq!(Calldata::abi_decode(&bytes).unwrap(), calldata);
+    assert!(matches!(
+        Calldata::abi_decode(&bytes[..200]),
+        Err(SnarkError::MalformedCalldata)
+    ));
+}
+
+#[test]
+fn calldata_rejects_overflowing_offsets() {
+    let mut bytes = Calldata {
+        seal: vec![1; 32],
+        image_id: IMAGE,
+        journal_digest: [2u8; 32],
+    }
+    .abi_encode();
+
+    // a seal offset at the end of the address space
+    bytes[24..32].copy_from_slice(&u64::MAX.to_be_bytes());
+    assert!(matches!(
+        Calldata::abi_decode(&bytes),
+        Err(SnarkError::MalformedCalldata)
+    ));
+}
+
+#[test]
+fn calldata_of_groth16_receipt_round_trip() {
+    let journal = vec![1, 2, 3, 4];
+    let proof = Groth16Proof {
+        image_id: IMAGE,
+        risc0_receipt: fake_groth16_receipt(IMAGE, journal.clone()),
+    };
+
+    let calldata = proof.calldata().unwrap();
+    assert_eq!(
+        Calldata::abi_decode(&calldata.abi_encode()).unwrap(),
+        calldata
+    );
+    assert_eq!(calldata.seal[..4], verifying_key().selector());
+    assert_eq!(
+        calldata.seal[4..],
+        (0..256).map(|i| i as u8).collect::<Vec<_>>()
+    );
+    assert_eq!(calldata.image_id, IMAGE);
+    assert_eq!(
+        calldata.journal_digest,
+        <[u8; 32]>::from(proof.risc0_receipt.journal.digest())
+    );
+}
+
+#[test]
+fn verifying_key_round_trip() {
+    let key = verifying_key();
+    assert_eq!(key.selector(), key.verifier_parameters[..4]);
+
+    let decoded = Groth16VerifyingKey::from_bytes(&key.to_bytes()).unwrap();
+    assert_eq!(decoded.verifier_parameters, key.verifier_parameters);
+    assert_eq!(decoded.control_root, key.control_root);
+    assert_eq!(decoded.bn254_control_id, key.bn254_control_id);
+    assert_eq!(decoded.verifying_key.digest(), key.verifying_key.digest());
+}
+
+#[test]
+fn snark_rejects_non_groth16_receipts() {
+    let proof = Groth16Proof {
+        image_id: IMAGE,
+        risc0_receipt: fake_receipt(IMAGE, vec![]),
+    };
+
+    assert!(matches!(
+        proof.calldata(),
+        Err(SnarkError::NotGroth16(Some(SealKind::Fake)))
+    ));
+    assert!(matches!(
+        proof.verify_and_decode::<()>(&Verifier::default().with_dev_mode(true)),
+        Err(VerifyError::SealKindMismatch { .. })
+    ));
+}
+
+#[test]
+// run by the `snark` job of .github/workflows/emmarin.yml:
+// cargo test --release -p ledger --test snark -- --ignored snark_wraps_bundle_proof
+#[ignore = "needs the risc0 groth16 prover, which only runs on x86 hosts"]
+fn snark_wraps_bundle_proof() {
+    let ctx = ProverContext::default();
+    let bundle = ProvedBundle::prove(&ctx, vec![], vec![]).unwrap();
+
+    let proof = Groth16Proof::bundle(&ctx, &bundle).unwrap();
+    let public: Bundle = proof.verify_and_decode(&Verifier::default()).unwrap();
+    assert_eq!(public, bundle.public());
+
+    let calldata = Calldata::abi_decode(&proof.calldata().unwrap().abi_encode()).unwrap();
+    assert_eq!(calldata.seal[..4], verifying_key().selector());
+    assert_eq!(calldata.image_id, proof.image_id);
+    assert_eq!(
+        calldata.journal_digest,
+        <[u8; 32]>::from(bundle.risc0_receipt.journal.digest())
+    );
+}
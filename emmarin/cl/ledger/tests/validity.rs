@@ -0,0 +1,105 @@
+mod common;
+
+use cl::{
+    crust::{tx::Validity, Bundle, BundleRoot, Nullifier},
+    mantle::{ledger::LedgerState, Domain, ZoneId},
+};
+use ledger::{error::Error, ledger::ProvedLedgerTransition, test_utils::window};
+
+use common::sorted_ledger_witness;
+
+const ZONE: ZoneId = [0u8; 32];
+const DOMAIN: Domain = [9u8; 32];
+
+fn bundle(seed: u8, validity: Validity, domain: Domain) -> Bundle {
+    Bundle {
+        validity,
+        domain,
+        ..common::bundle(ZONE, seed, vec![Nullifier([seed; 32])])
+    }
+}
+
+/// Run the guest over `bundles` without the host checks.
+fn execute_unchecked(ledger: &LedgerState, bundles: Vec<Bundle>) -> Result<(), Error> {
+    ProvedLedgerTransition::execute_witness(&sorted_ledger_witness(ledger, ZONE, bundles))
+        .map(|_| ())
+}
+
+#[test]
+fn bundle_of_another_domain_is_rejected() {
+    let mut ledger = LedgerState::default().with_domain(DOMAIN);
+    let before = ledger.to_witness();
+
+    let bundles = vec![bundle(1, Validity::default(), [0; 32])];
+
+    let err = ProvedLedgerTransition::execute(&mut ledger, ZONE, bundles, &[]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::DomainMismatch { expected, actual, .. } if expected == DOMAIN && actual == [0; 32]
+    ));
+    assert_eq!(ledger.to_witness(), before);
+}
+
+#[test]
+fn bundle_outside_its_validity_window_is_rejected() {
+    let mut ledger = LedgerState::default().with_domain(DOMAIN);
+    ledger.add_bundle(BundleRoot([0xff; 32]));
+    let before = ledger.to_witness();
+
+    // the ledger holds one bundle, so the bundles are added at heights 1 and 2
+    let bundles = vec![
+        bundle(1, window(Some(1), Some(1)), DOMAIN),
+        bundle(2, window(None, Some(1)), DOMAIN),
+    ];
+
+    let err = ProvedLedgerTransition::execute(&mut ledger, ZONE, bundles, &[]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::OutsideValidity { bundle, height: 2 } if bundle == BundleRoot([2; 32])
+    ));
+    assert_eq!(ledger.to_witness(), before);
+
+    // not yet valid
+    let bundles = vec![bundle(1, window(Some(2), None), DOMAIN)];
+    let err = ProvedLedgerTransition::execute(&mut ledger, ZONE, bundles, &[]).unwrap_err();
+    assert!(matches!(err, Error::OutsideValidity { height: 1, .. }));
+}
+
+#[test]
+fn guest_rejects_bundle_of_another_domain() {
+    let ledger = LedgerState::default().with_domain(DOMAIN);
+
+    assert!(execute_unchecked(&ledger, vec![bundle(1, Validity::default(), DOMAIN)]).is_ok());
+    assert!(matches!(
+        execute_unchecked(&ledger, vec![bundle(1, Validity::default(), [0; 32])]),
+        Err(Error::Risc0ExecutionFailed)
+    ));
+}
+
+#[test]
+fn guest_rejects_bundle_outside_its_validity_window() {
+    let mut ledger = LedgerState::default().with_domain(DOMAIN);
+    ledger.add_bundle(BundleRoot([0xff; 32]));
+
+    // the ledger holds one bundle, so the bundles are added at heights 1 and 2
+    let valid = vec![
+        bundle(1, window(Some(1), Some(1)), DOMAIN),
+        bundle(2, window(None, Some(2)), DOMAIN),
+    ];
+    assert!(execute_unchecked(&ledger, valid).is_ok());
+
+    let expired = vec![
+        bundle(1, window(Some(1), Some(1)), DOMAIN),
+        bundle(2, window(None, Some(1)), DOMAIN),
+    ];
+    assert!(matches!(
+        execute_unchecked(&ledger, expired),
+        Err(Error::Risc0ExecutionFailed)
+    ));
+
+    let not_yet_valid = vec![bundle(1, window(Some(2), None), DOMAIN)];
+    assert!(matches!(
+        execute_unchecked(&ledger, not_yet_valid),
+        Err(Error::Risc0ExecutionFailed)
+    ));
+}
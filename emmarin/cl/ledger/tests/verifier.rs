@@ -0,0 +1,130 @@
+use cl::mantle::{zone::Stf, ProofSystem};
+use ledger::{
+    stf::{risc0_stf, Groth16Backend, StfBackend, StfProof},
+    test_utils::fake_receipt,
+    verifier::{Verifier, VerifyError},
+};
+use ledger_proof_statements::stf::StfPublic;
+
+const IMAGE_A: [u8; 32] = [1u8; 32];
+const IMAGE_B: [u8; 32] = [2u8; 32];
+
+fn journal(value: u64) -> Vec<u8> {
+    risc0_zkvm::serde::to_vec(&value)
+        .unwrap()
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+        .collect()
+}
+
+#[test]
+fn verifier_decodes_valid_receipts() {
+    let verifier = Verifier::default().with_dev_mode(true);
+    let receipt = fake_receipt(IMAGE_A, journal(42));
+
+    let value: u64 = verifier.verify_and_decode(IMAGE_A, &receipt).unwrap();
+    assert_eq!(value, 42);
+}
+
+#[test]
+fn verifier_rejects_wrong_image() {
+    let verifier = Verifier::default().with_dev_mode(true);
+    let receipt = fake_receipt(IMAGE_A, journal(42));
+
+    assert!(matches!(
+        verifier.verify_and_decode::<u64>(IMAGE_B, &receipt),
+        Err(VerifyError::ImageMismatch {
+            expected: IMAGE_B,
+            actual: IMAGE_A
+        })
+    ));
+}
+
+#[test]
+fn verifier_rejects_bad_seal() {
+    // fake receipts carry no proof outside of dev mode
+    let verifier = Verifier::default().with_dev_mode(false);
+    let receipt = fake_receipt(IMAGE_A, journal(42));
+    assert!(matches!(
+        verifier.verify_and_decode::<u64>(IMAGE_A, &receipt),
+        Err(VerifyError::Seal(_))
+    ));
+
+    // the journal no longer matches the claim
+    let verifier = Verifier::default().with_dev_mode(true);
+    let mut receipt = fake_receipt(IMAGE_A, journal(42));
+    receipt.journal.bytes = journal(43);
+    assert!(matches!(
+        verifier.verify_and_decode::<u64>(IMAGE_A, &receipt),
+        Err(VerifyError::Seal(_))
+    ));
+}
+
+#[test]
+fn verifier_rejects_bad_journal() {
+    let verifier = Verifier::default().with_dev_mode(true);
+    let receipt = fake_receipt(IMAGE_A, vec![1, 2, 3]);
+
+    assert!(matches!(
+        verifier.verify_and_decode::<u64>(IMAGE_A, &receipt),
+        Err(VerifyError::Journal(_))
+    ));
+}
+
+#[derive(Debug)]
+struct RejectAll;
+
+impl StfBackend for RejectAll {
+    fn verify(&self, _: &Verifier, stf: Stf, proof: &StfProof) -> Result<StfPublic, VerifyError> {
+        Err(VerifyError::StfMismatch {
+            expected: stf,
+            actual: proof.stf(),
+        })
+    }
+}
+
+#[test]
+fn verifier_dispatches_stf_proofs() {
+    let mut verifier = Verifier::default().with_dev_mode(true);
+    let proof = StfProof::from_risc0(IMAGE_A, fake_receipt(IMAGE_A, vec![]));
+    assert_eq!(proof.stf(), risc0_stf(IMAGE_A));
+
+    // the stf is derived from the proof system and image, not the raw image
+    let other: Stf = ProofSystem::Groth16.stf(&IMAGE_A);
+    assert!(matches!(
+        verifier.stf(IMAGE_A, &proof),
+        Err(VerifyError::StfMismatch { .. })
+    ));
+    assert!(matches!(
+        verifier.stf(other, &proof),
+        Err(VerifyError::StfMismatch { .. })
+    ));
+
+    // groth16 stfs only accept groth16 seals
+    let groth16 = StfProof::from_groth16(IMAGE_A, fake_receipt(IMAGE_A, vec![]));
+    assert!(matches!(
+        verifier.stf(other, &groth16),
+        Err(VerifyError::SealKindMismatch { .. })
+    ));
+
+    // registered stfs are verified by their own backend
+    verifier.stfs.register(risc0_stf(IMAGE_A), RejectAll);
+    assert!(matches!(
+        verifier.stf(risc0_stf(IMAGE_A), &proof),
+        Err(VerifyError::StfMismatch { expected, .. }) if expected == risc0_stf(IMAGE_A)
+    ));
+}
+
+#[test]
+fn registered_backends_check_the_stf() {
+    let mut verifier = Verifier::default().with_dev_mode(true);
+    let stf = risc0_stf(IMAGE_B);
+    verifier.stfs.register(stf, Groth16Backend);
+
+    // a groth16 proof of another image does not pass for the registered stf
+    let proof = StfProof::from_groth16(IMAGE_A, fake_receipt(IMAGE_A, vec![]));
+    assert!(matches!(
+        verifier.stf(stf, &proof),
+        Err(VerifyError::StfMismatch { expected, .. }) if expected == stf
+    ));
+}
@@ -0,0 +1,22 @@
+use crate::ledger::LedgerProofPublic;
+use cl::mantle::{update::Update, ProofSystem};
+use serde::{Deserialize, Serialize};
+
+/// The journal of the batch proof is the aggregated `BatchUpdate`, i.e. the
+/// old and new states of every zone in the batch.
+pub use cl::mantle::update::BatchUpdate as BatchProofPublic;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProofPrivate {
+    pub zones: Vec<BatchZoneWitness>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchZoneWitness {
+    pub update: Update,
+    /// Public statement of the zone ledger transition, proven by an assumption
+    pub ledger: LedgerProofPublic,
+    /// Proof system and image of the zone stf, which must derive `update.old.stf`
+    pub stf_system: ProofSystem,
+    pub stf_image: [u8; 32],
+}
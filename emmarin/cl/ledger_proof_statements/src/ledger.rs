@@ -1,8 +1,8 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use cl::{
     crust::{Bundle, BundleRoot, NoteCommitment},
-    ds::indexed::BatchUpdateProof,
+    ds::indexed::{BatchUpdateProof, NullifierOrder},
     ds::merkle,
     mantle::{
         ledger::{Ledger, LedgerWitness},
@@ -12,12 +12,19 @@ use cl::{
 use risc0_zkvm::guest::env;
 use serde::{Deserialize, Serialize};
 
+pub use cl::mantle::sync::SyncLog;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LedgerProofPublic {
     pub old_ledger: Ledger,
     pub ledger: Ledger,
     pub id: ZoneId,
+    /// Cross zone bundles settled by this zone in this transition
     pub sync_logs: Vec<SyncLog>,
+    /// Cross zone bundles this zone acknowledges as settled by all zones involved
+    pub synced: Vec<SyncLog>,
+    /// Commitments added to the zone, the outputs of cross zone bundles are
+    /// only added once the bundle is acknowledged
     pub outputs: Vec<NoteCommitment>,
 }
 
@@ -27,6 +34,9 @@ pub struct LedgerProofPrivate {
     pub id: ZoneId,
     pub bundles: Vec<LedgerBundleWitness>,
     pub nf_proofs: BatchUpdateProof,
+    /// Sorts the nullifiers of the bundles, in bundle order
+    pub nf_order: NullifierOrder,
+    pub synced: Vec<BundleRoot>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,10 +45,68 @@ pub struct LedgerBundleWitness {
     pub cm_root_proofs: BTreeMap<[u8; 32], merkle::Path>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SyncLog {
+/// A cross zone bundle that was not acknowledged by exactly the zones it touches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncLogMismatch {
     pub bundle: BundleRoot,
-    pub zones: Vec<ZoneId>,
+    pub expected: Vec<ZoneId>,
+    pub actual: Vec<ZoneId>,
+}
+
+/// Check the cross zone bundles settled and acknowledged by the given ledger
+/// transitions of a batch.
+///
+/// A zone may settle its half of a cross zone bundle before its counterparts,
+/// in which case the bundle stays pending in its ledger. A bundle is only
+/// released once every zone involved acknowledges it in the same batch, and
+/// it expires from the zones still holding it past the sync window.
+pub fn check_sync_logs<'a>(
+    ledgers: impl IntoIterator<Item = &'a LedgerProofPublic>,
+) -> Result<(), SyncLogMismatch> {
+    // bundle -> zones the bundle touches
+    let mut expected_zones = BTreeMap::<BundleRoot, BTreeSet<ZoneId>>::new();
+    // bundle -> zones acknowledging the bundle as settled
+    let mut acked_zones = BTreeMap::<BundleRoot, BTreeSet<ZoneId>>::new();
+
+    for ledger in ledgers {
+        for (sync_log, acked) in ledger
+            .sync_logs
+            .iter()
+            .map(|log| (log, false))
+            .chain(ledger.synced.iter().map(|log| (log, true)))
+        {
+            let zones = BTreeSet::from_iter(sync_log.zones.iter().copied());
+            let expected = expected_zones
+                .entry(sync_log.bundle)
+                .or_insert(zones.clone());
+            if expected != &zones || !zones.contains(&ledger.id) {
+                return Err(SyncLogMismatch {
+                    bundle: sync_log.bundle,
+                    expected: expected.iter().copied().collect(),
+                    actual: zones.into_iter().collect(),
+                });
+            }
+            if acked {
+                acked_zones
+                    .entry(sync_log.bundle)
+                    .or_default()
+                    .insert(ledger.id);
+            }
+        }
+    }
+
+    for (bundle, acked) in acked_zones {
+        let expected = &expected_zones[&bundle];
+        if &acked != expected {
+            return Err(SyncLogMismatch {
+                bundle,
+                expected: expected.iter().copied().collect(),
+                actual: acked.into_iter().collect(),
+            });
+        }
+    }
+
+    Ok(())
 }
 
 impl LedgerProofPrivate {
@@ -49,24 +117,112 @@ impl LedgerProofPrivate {
         let nf_proofs_len: usize = env::read();
         let mut data = vec![0; nf_proofs_len];
         env::read_slice(&mut data);
+        let nf_order = env::read();
+        let synced = env::read();
 
         LedgerProofPrivate {
             ledger,
             id,
             bundles,
             nf_proofs: BatchUpdateProof::from_raw_data(data),
+            nf_order,
+            synced,
         }
     }
 }
 
 #[cfg(not(target_os = "zkvm"))]
 impl LedgerProofPrivate {
-    pub fn write(&self, env: &mut risc0_zkvm::ExecutorEnvBuilder) {
-        env.write(&self.ledger).unwrap();
-        env.write(&self.id).unwrap();
-        env.write(&self.bundles).unwrap();
+    pub fn write(&self, env: &mut risc0_zkvm::ExecutorEnvBuilder) -> risc0_zkvm::Result<()> {
+        env.write(&self.ledger)?;
+        env.write(&self.id)?;
+        env.write(&self.bundles)?;
 
-        env.write(&self.nf_proofs.as_slice().len()).unwrap();
+        env.write(&self.nf_proofs.as_slice().len())?;
         env.write_slice(self.nf_proofs.as_slice());
+        env.write(&self.nf_order)?;
+        env.write(&self.synced)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cl::mantle::ledger::LedgerState;
+
+    const ZONE_A: ZoneId = [0; 32];
+    const ZONE_B: ZoneId = [1; 32];
+
+    fn cross_log() -> SyncLog {
+        SyncLog {
+            bundle: BundleRoot([7; 32]),
+            zones: vec![ZONE_A, ZONE_B],
+        }
+    }
+
+    fn public(id: ZoneId, sync_logs: Vec<SyncLog>, synced: Vec<SyncLog>) -> LedgerProofPublic {
+        let ledger = LedgerState::default().to_witness().commit();
+        LedgerProofPublic {
+            old_ledger: ledger,
+            ledger,
+            id,
+            sync_logs,
+            synced,
+            outputs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_settled_and_acked_by_all_zones() {
+        let a = public(ZONE_A, vec![cross_log()], vec![cross_log()]);
+        let b = public(ZONE_B, vec![cross_log()], vec![cross_log()]);
+
+        assert_eq!(check_sync_logs([&a, &b]), Ok(()));
+    }
+
+    #[test]
+    fn test_half_settled_stays_pending() {
+        // zone A settles its half first, zone B is free to settle it in a later batch
+        let a = public(ZONE_A, vec![cross_log()], vec![]);
+
+        assert_eq!(check_sync_logs([&a]), Ok(()));
+
+        // zone B settles later, zone A acknowledges from its pending syncs
+        let a = public(ZONE_A, vec![], vec![cross_log()]);
+        let b = public(ZONE_B, vec![cross_log()], vec![cross_log()]);
+
+        assert_eq!(check_sync_logs([&a, &b]), Ok(()));
+    }
+
+    #[test]
+    fn test_withheld_half_cannot_be_acked() {
+        // zone A claims the bundle is fully settled while zone B withholds its half
+        let a = public(ZONE_A, vec![cross_log()], vec![cross_log()]);
+        let b = public(ZONE_B, vec![], vec![]);
+
+        assert_eq!(
+            check_sync_logs([&a, &b]),
+            Err(SyncLogMismatch {
+                bundle: cross_log().bundle,
+                expected: vec![ZONE_A, ZONE_B],
+                actual: vec![ZONE_A],
+            })
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_zones() {
+        let a = public(ZONE_A, vec![cross_log()], vec![]);
+        let b = public(
+            ZONE_B,
+            vec![SyncLog {
+                bundle: cross_log().bundle,
+                zones: vec![ZONE_B],
+            }],
+            vec![],
+        );
+
+        assert!(check_sync_logs([&a, &b]).is_err());
     }
 }
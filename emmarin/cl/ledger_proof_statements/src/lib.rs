@@ -1,3 +1,4 @@
+pub mod batch;
 pub mod covenant;
 pub mod ledger;
 pub mod stf;
@@ -1,4 +1,7 @@
-use cl::ds::merkle;
+use cl::{
+    ds::merkle,
+    mantle::sync::{SettledSync, SyncError},
+};
 use hex::FromHex;
 use ledger_proof_statements::ledger::{
     LedgerBundleWitness, LedgerProofPrivate, LedgerProofPublic, SyncLog,
@@ -11,9 +14,11 @@ fn main() {
         id,
         bundles,
         nf_proofs,
+        nf_order,
+        synced,
     } = LedgerProofPrivate::read();
     let old_ledger = ledger.clone();
-    let mut sync_logs = vec![];
+    let mut settled = vec![];
     let mut outputs = vec![];
 
     let mut nullifiers = vec![];
@@ -29,20 +34,25 @@ fn main() {
         )
         .unwrap();
 
-        if bundle.updates.len() > 1 {
-            // This is a cross zone bundle, add a sync log for it to ensure all zones
-            // also approve it.
-            sync_logs.push(SyncLog {
-                bundle: bundle.root,
-                zones: bundle.updates.keys().copied().collect(),
-            });
-        }
+        // rejects stale bundles and replays of bundles from other domains
+        ledger.assert_bundle_valid(&bundle);
+
+        // This is a cross zone bundle if it touches other zones, its outputs
+        // are held back until all zones also approve it.
+        let cross_zone = bundle.updates.len() > 1;
+        let mut bundle_outputs = vec![];
 
         let ledger_updates = bundle
             .updates
             .get(&id)
             .expect("attempting to prove a bundle that is not for this zone");
 
+        // notes migrating through this zone must be spent or recreated here
+        assert!(
+            bundle.migrations_settled_by(id),
+            "note migration is not settled by this zone"
+        );
+
         for ledger_update in ledger_updates {
             for node in &ledger_update.frontier_nodes {
                 let past_cm_root_proof = cm_root_proofs
@@ -56,25 +66,55 @@ fn main() {
                 );
             }
 
-            for (cm, _data) in &ledger_update.outputs {
+            bundle_outputs.extend(ledger_update.outputs.iter().map(|(cm, _data)| *cm));
+            nullifiers.extend(ledger_update.inputs.clone());
+        }
+
+        if cross_zone {
+            settled.push(SettledSync {
+                log: SyncLog {
+                    bundle: bundle.root,
+                    zones: bundle.updates.keys().copied().collect(),
+                },
+                outputs: bundle_outputs,
+            });
+        } else {
+            for cm in &bundle_outputs {
                 ledger.add_commitment(cm);
-                outputs.push(*cm);
             }
-            nullifiers.extend(ledger_update.inputs.clone());
+            outputs.extend(bundle_outputs);
         }
 
         ledger.add_bundle(bundle.root);
     }
 
-    // TODO: sort outside and check
-    nullifiers.sort();
+    // the host sorts, we only check the order, which rejects nullifiers
+    // spent twice across bundles
+    let nullifiers = nf_order.sorted(&nullifiers);
     ledger.assert_nfs_update(&nullifiers, &nf_proofs);
 
+    // Track our half of the cross zone bundles until all counterpart zones
+    // have settled them as well, the released ones add their outputs.
+    let sync_logs = settled.iter().map(|s| s.log.clone()).collect();
+    let released = match ledger.syncs.transition(settled, &synced) {
+        Ok(released) => released,
+        Err(SyncError::SettledTwice(_)) => panic!("cross zone bundle settled twice"),
+        Err(SyncError::UnexpectedAck(_)) => {
+            panic!("acknowledged a cross zone bundle this zone did not settle")
+        }
+    };
+    for cm in released.iter().flat_map(|s| &s.outputs) {
+        ledger.add_commitment(cm);
+        outputs.push(*cm);
+    }
+    let synced = released.into_iter().map(|s| s.log).collect();
+
     env::commit(&LedgerProofPublic {
         old_ledger: old_ledger.commit(),
         ledger: ledger.commit(),
         id,
         sync_logs,
+        synced,
         outputs,
     });
 }
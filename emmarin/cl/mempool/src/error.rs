@@ -0,0 +1,36 @@
+use cl::{
+    crust::{NoteCommitment, Nullifier, TxRoot},
+    mantle::Domain,
+};
+use ledger::verifier::VerifyError;
+use thiserror::Error;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid tx proof")]
+    InvalidProof(#[from] VerifyError),
+    #[error("failed to prove bundle")]
+    Prover(#[from] ledger::error::Error),
+    #[error("tx {} is already in the mempool", .0.hex())]
+    DuplicateTx(TxRoot),
+    #[error(
+        "nullifier {} is already spent by pending tx {}",
+        hex::encode(.nullifier),
+        .tx.hex()
+    )]
+    ConflictingNullifier { nullifier: Nullifier, tx: TxRoot },
+    #[error("nullifier {} is already spent", hex::encode(.0))]
+    SpentNullifier(Nullifier),
+    #[error("tx {} is bound to domain {}", .tx.hex(), hex::encode(.domain))]
+    WrongDomain { tx: TxRoot, domain: Domain },
+    #[error("fee output {} is not an output of tx {}", hex::encode(.output.0), .tx.hex())]
+    NotAFeeOutput { tx: TxRoot, output: NoteCommitment },
+    #[error("tx {} expired at bundle height {valid_until}, the ledger is at {height}", .tx.hex())]
+    Expired {
+        tx: TxRoot,
+        valid_until: u64,
+        height: u64,
+    },
+}
@@ -0,0 +1,458 @@
+//! Collects proved txs from users and groups them into balanced bundles for
+//! the ledger prover.
+
+pub mod error;
+pub mod matching;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cl::{
+    crust::{tx::Validity, Balance, Nullifier, OutputWitness, Tx, TxRoot},
+    mantle::Domain,
+};
+use ledger::{bundle::ProvedBundle, prover::ProverContext, tx::ProvedTx, verifier::Verifier};
+
+pub use error::{Error, Result};
+use matching::{imbalance, Matcher, Solver};
+
+/// A tx waiting in the mempool, with its verified public.
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+    pub proof: ProvedTx,
+    pub tx: Tx,
+    /// Outputs of the tx claiming the fees of its bundle for the builder
+    pub fee_outputs: Vec<OutputWitness>,
+}
+
+impl PendingTx {
+    fn nullifiers(&self) -> impl Iterator<Item = Nullifier> + '_ {
+        self.tx
+            .updates
+            .values()
+            .flat_map(|update| update.inputs.iter().copied())
+    }
+
+    /// What the tx contributes to the balance of a bundle, the fee is left for
+    /// the builder's claim tx of the bundle, see [`Mempool::submit_fee_claim`].
+    fn offset(&self) -> Balance {
+        let mut fee = Balance::zero();
+        if let Some(f) = self.tx.fee {
+            fee.insert_positive(f.unit, f.amount);
+        }
+        Balance::combine([&self.tx.balance, &fee])
+    }
+
+    /// The fee paid by the tx less the fees claimed by its fee outputs, the
+    /// fee outputs of a bundle must claim exactly the fees of its txs.
+    fn claim(&self) -> Balance {
+        let mut claim = Balance::zero();
+        if let Some(f) = self.tx.fee {
+            claim.insert_positive(f.unit, f.amount);
+        }
+        for output in &self.fee_outputs {
+            claim.insert_negative(output.unit, output.value);
+        }
+        Balance::combine([&claim])
+    }
+}
+
+/// The bundles proven by [`Mempool::prove_bundles`].
+#[derive(Debug)]
+pub struct ProvedBundles {
+    pub bundles: Vec<ProvedBundle>,
+    /// The txs of each bundle that failed to prove, evicted from the mempool
+    pub evicted: Vec<(Vec<PendingTx>, Error)>,
+}
+
+#[derive(Debug, Default)]
+pub struct Mempool {
+    verifier: Verifier,
+    /// Txs bound to other domains are rejected
+    domain: Domain,
+    /// Pending txs in arrival order
+    pending: Vec<PendingTx>,
+    /// Nullifiers of the pending txs
+    nullifiers: BTreeMap<Nullifier, TxRoot>,
+    /// Nullifiers of txs already bundled or settled
+    spent: BTreeSet<Nullifier>,
+    /// Bundle height of the ledger, last seen by [`Mempool::evict_expired`]
+    height: u64,
+}
+
+impl Mempool {
+    pub fn new(verifier: Verifier) -> Self {
+        Self {
+            verifier,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_domain(mut self, domain: Domain) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn contains(&self, root: TxRoot) -> bool {
+        self.pending.iter().any(|p| p.tx.root == root)
+    }
+
+    pub fn pending(&self) -> &[PendingTx] {
+        &self.pending
+    }
+
+    /// Verify a proved tx and queue it for bundling.
+    ///
+    /// Txs spending a nullifier of a pending or already spent tx are rejected,
+    /// the first tx seen wins. So are txs expired at the ledger height.
+    pub fn submit(&mut self, proof: ProvedTx) -> Result<TxRoot> {
+        self.submit_fee_claim(proof, vec![])
+    }
+
+    /// Queue the builder's own tx claiming the fees of the bundle it ends up
+    /// in through `fee_outputs`, outputs of the tx owned by the builder.
+    pub fn submit_fee_claim(
+        &mut self,
+        proof: ProvedTx,
+        fee_outputs: Vec<OutputWitness>,
+    ) -> Result<TxRoot> {
+        let tx = self.verifier.tx(&proof)?;
+        let root = tx.root;
+        for output in &fee_outputs {
+            let cm = output.note_commitment();
+            if !tx
+                .updates
+                .get(&output.zone_id)
+                .is_some_and(|update| update.has_output(&cm))
+            {
+                return Err(Error::NotAFeeOutput {
+                    tx: root,
+                    output: cm,
+                });
+            }
+        }
+        if let Some(valid_until) = tx.validity.valid_until.filter(|u| *u < self.height) {
+            return Err(Error::Expired {
+                tx: root,
+                valid_until,
+                height: self.height,
+            });
+        }
+        let pending = PendingTx {
+            proof,
+            tx,
+            fee_outputs,
+        };
+
+        if self.contains(root) {
+            return Err(Error::DuplicateTx(root));
+        }
+        if pending.tx.domain != self.domain {
+            return Err(Error::WrongDomain {
+                tx: root,
+                domain: pending.tx.domain,
+            });
+        }
+
+        let mut seen = BTreeSet::new();
+        for nullifier in pending.nullifiers() {
+            if self.spent.contains(&nullifier) {
+                return Err(Error::SpentNullifier(nullifier));
+            }
+            if let Some(tx) = self.nullifiers.get(&nullifier) {
+                return Err(Error::ConflictingNullifier { nullifier, tx: *tx });
+            }
+            if !seen.insert(nullifier) {
+                return Err(Error::ConflictingNullifier {
+                    nullifier,
+                    tx: root,
+                });
+            }
+        }
+
+        self.nullifiers
+            .extend(seen.into_iter().map(|nf| (nf, root)));
+        self.pending.push(pending);
+        Ok(root)
+    }
+
+    pub fn remove(&mut self, root: TxRoot) -> Option<PendingTx> {
+        let idx = self.pending.iter().position(|p| p.tx.root == root)?;
+        let pending = self.pending.remove(idx);
+        for nullifier in pending.nullifiers() {
+            self.nullifiers.remove(&nullifier);
+        }
+        Some(pending)
+    }
+
+    /// Evict the pending txs whose validity window ends before `height`, the
+    /// bundle height of the ledger they would be settled in. Txs expired at
+    /// `height` are rejected from then on.
+    pub fn evict_expired(&mut self, height: u64) -> Vec<PendingTx> {
+        self.height = self.height.max(height);
+        let expired: Vec<TxRoot> = self
+            .pending
+            .iter()
+            .filter(|p| {
+                p.tx.validity
+                    .valid_until
+                    .is_some_and(|until| until < height)
+            })
+            .map(|p| p.tx.root)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|root| self.remove(root))
+            .collect()
+    }
+
+    /// Record nullifiers spent outside of this mempool, e.g. by settled bundles
+    /// of other builders, and evict the pending txs spending them.
+    pub fn mark_spent(&mut self, nullifiers: impl IntoIterator<Item = Nullifier>) {
+        for nullifier in nullifiers {
+            if let Some(root) = self.nullifiers.get(&nullifier).copied() {
+                self.remove(root);
+            }
+            self.spent.insert(nullifier);
+        }
+    }
+
+    /// Group pending txs into bundles that balance to zero.
+    ///
+    /// Starting from the oldest pending tx, the tx that brings the bundle
+    /// closest to balance is added until the bundle balances and its fees are
+    /// claimed exactly by the fee outputs of its txs. Only txs whose validity
+    /// windows intersect are bundled together. A tx that cannot be balanced by
+    /// the pending txs stays in the mempool. The bundled txs are removed and
+    /// their nullifiers are considered spent.
+    pub fn build_bundles(&mut self) -> Vec<Vec<PendingTx>> {
+        let groups = self.bundle_groups();
+        let funding = groups.iter().map(|_| None).collect();
+        self.take_bundles(groups, funding)
+    }
+
+    /// Indices of the pending txs of each bundle built by [`Self::build_bundles`].
+    fn bundle_groups(&self) -> Vec<Vec<usize>> {
+        let offsets: Vec<Balance> = self.pending.iter().map(PendingTx::offset).collect();
+        let claims: Vec<Balance> = self.pending.iter().map(PendingTx::claim).collect();
+        let score = |offset: &Balance, claim: &Balance| imbalance(offset) + imbalance(claim);
+        let mut bundled = vec![false; self.pending.len()];
+        let mut groups = Vec::new();
+
+        for seed in 0..self.pending.len() {
+            if bundled[seed] {
+                continue;
+            }
+            let mut group = vec![seed];
+            let mut offset = offsets[seed].clone();
+            let mut claim = claims[seed].clone();
+            let mut validity = self.pending[seed].tx.validity;
+
+            while score(&offset, &claim) > 0 {
+                let best = (0..self.pending.len())
+                    .filter(|i| !bundled[*i] && !group.contains(i))
+                    .filter(|i| !validity.intersect(self.pending[*i].tx.validity).is_empty())
+                    .map(|i| {
+                        let offset = Balance::combine([&offset, &offsets[i]]);
+                        let claim = Balance::combine([&claim, &claims[i]]);
+                        (score(&offset, &claim), i, offset, claim)
+                    })
+                    .min_by_key(|(score, i, ..)| (*score, *i));
+
+                match best {
+                    Some((best, i, combined, claimed)) if best < score(&offset, &claim) => {
+                        group.push(i);
+                        offset = combined;
+                        claim = claimed;
+                        validity = validity.intersect(self.pending[i].tx.validity);
+                    }
+                    _ => break,
+                }
+            }
+
+            if score(&offset, &claim) == 0 && !validity.is_empty() {
+                group.sort();
+                for i in &group {
+                    bundled[*i] = true;
+                }
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    /// Group pending txs with complementary intents into bundles, see
+    /// [`Matcher::match_all`]. Like [`Self::build_bundles`], the fees of a
+    /// bundle must be claimed exactly and the validity windows of its txs must
+    /// intersect.
+    ///
+    /// Matches that do not net to zero are offered to `solver`, whose tx closes
+    /// the bundle if it verifies and cancels the residual exactly. The bundled
+    /// txs are removed and their nullifiers are considered spent.
+    pub fn match_intents(
+        &mut self,
+        matcher: &Matcher,
+        mut solver: Option<&mut dyn Solver>,
+    ) -> Vec<Vec<PendingTx>> {
+        let offsets: Vec<Balance> = self.pending.iter().map(PendingTx::offset).collect();
+        let mut groups = Vec::new();
+        let mut funding = Vec::new();
+        let mut funded_nullifiers = BTreeSet::new();
+
+        for found in matcher.match_all(&offsets) {
+            let matched = || found.txs.iter().map(|&i| &self.pending[i]);
+            if found.residual.is_zero() {
+                if !can_bundle(matched()) {
+                    continue;
+                }
+                groups.push(found.txs);
+                funding.push(None);
+                continue;
+            }
+            let Some(solver) = solver.as_deref_mut() else {
+                continue;
+            };
+            let Some(proof) = solver.fund(&found.residual) else {
+                continue;
+            };
+            let Some(funded) = self.check_funding(proof, &found.residual, &mut funded_nullifiers)
+            else {
+                continue;
+            };
+            if can_bundle(matched().chain([&funded])) {
+                groups.push(found.txs);
+                funding.push(Some(funded));
+            }
+        }
+
+        self.take_bundles(groups, funding)
+    }
+
+    /// A solver tx is only accepted if it is valid, does not conflict with any
+    /// other tx and exactly cancels `residual`.
+    fn check_funding(
+        &self,
+        proof: ProvedTx,
+        residual: &Balance,
+        funded_nullifiers: &mut BTreeSet<Nullifier>,
+    ) -> Option<PendingTx> {
+        let tx = self.verifier.tx(&proof).ok()?;
+        let funded = PendingTx {
+            proof,
+            tx,
+            fee_outputs: vec![],
+        };
+
+        if imbalance(&Balance::combine([residual, &funded.offset()])) != 0 {
+            return None;
+        }
+        let nullifiers: BTreeSet<Nullifier> = funded.nullifiers().collect();
+        let conflicts = nullifiers.len() != funded.nullifiers().count()
+            || nullifiers.iter().any(|nf| {
+                self.spent.contains(nf)
+                    || self.nullifiers.contains_key(nf)
+                    || funded_nullifiers.contains(nf)
+            });
+        if conflicts {
+            return None;
+        }
+        funded_nullifiers.extend(nullifiers);
+        Some(funded)
+    }
+
+    /// Move the txs of each group out of the pending txs, closing each bundle
+    /// with its funding tx if any.
+    fn take_bundles(
+        &mut self,
+        groups: Vec<Vec<usize>>,
+        funding: Vec<Option<PendingTx>>,
+    ) -> Vec<Vec<PendingTx>> {
+        let mut txs: Vec<Option<PendingTx>> = std::mem::take(&mut self.pending)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let bundles: Vec<Vec<PendingTx>> = groups
+            .into_iter()
+            .zip(funding)
+            .map(|(group, funded)| {
+                group
+                    .into_iter()
+                    .filter_map(|i| txs[i].take())
+                    .chain(funded)
+                    .collect()
+            })
+            .collect();
+        self.pending = txs.into_iter().flatten().collect();
+
+        for pending in bundles.iter().flatten() {
+            for nullifier in pending.nullifiers() {
+                self.nullifiers.remove(&nullifier);
+                self.spent.insert(nullifier);
+            }
+        }
+        bundles
+    }
+
+    /// Build the bundles and prove them for the ledger prover, the fees of a
+    /// bundle are claimed by the fee outputs of its txs.
+    ///
+    /// The txs of a bundle failing to prove are evicted rather than bundled
+    /// again on the next call, their nullifiers are not considered spent.
+    pub fn prove_bundles(&mut self, ctx: &ProverContext) -> ProvedBundles {
+        let mut proven = Vec::new();
+        let mut bundles = Vec::new();
+        let mut failed = Vec::new();
+        for group in self.bundle_groups() {
+            let txs = group
+                .iter()
+                .map(|&i| self.pending[i].proof.clone())
+                .collect();
+            let fee_outputs = group
+                .iter()
+                .flat_map(|&i| self.pending[i].fee_outputs.iter().copied())
+                .collect();
+            match ProvedBundle::prove(ctx, txs, fee_outputs) {
+                Ok(bundle) => {
+                    proven.push(group);
+                    bundles.push(bundle);
+                }
+                Err(e) => {
+                    let roots = Vec::from_iter(group.iter().map(|&i| self.pending[i].tx.root));
+                    failed.push((roots, Error::Prover(e)));
+                }
+            }
+        }
+
+        let funding = proven.iter().map(|_| None).collect();
+        self.take_bundles(proven, funding);
+        let evicted = failed
+            .into_iter()
+            .map(|(roots, e)| {
+                let txs = roots.into_iter().filter_map(|r| self.remove(r)).collect();
+                (txs, e)
+            })
+            .collect();
+
+        ProvedBundles { bundles, evicted }
+    }
+}
+
+/// The fee outputs of `txs` claim exactly their fees and their validity
+/// windows intersect, see [`cl::crust::BundleWitness::commit`].
+fn can_bundle<'a>(txs: impl IntoIterator<Item = &'a PendingTx>) -> bool {
+    let (claims, validity) = txs.into_iter().fold(
+        (Vec::new(), Validity::default()),
+        |(mut claims, validity), tx| {
+            claims.push(tx.claim());
+            (claims, validity.intersect(tx.tx.validity))
+        },
+    );
+    Balance::combine(&claims).is_zero() && !validity.is_empty()
+}
@@ -0,0 +1,274 @@
+//! Coincidence of wants between pending txs.
+//!
+//! A tx with a positive balance in a unit offers that unit to the bundle, a
+//! tx with a negative balance wants it. Matching looks for sets of txs whose
+//! offers and wants cancel out, and may ask a [`Solver`] to close whatever is
+//! left with its own funds.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cl::crust::{Balance, Unit};
+use ledger::tx::ProvedTx;
+
+/// Provides txs out of its own funds to balance bundles no set of pending txs
+/// can balance on its own.
+pub trait Solver {
+    /// A proved tx whose balance cancels `residual`, if the solver is willing
+    /// to fund it.
+    fn fund(&mut self, residual: &Balance) -> Option<ProvedTx>;
+}
+
+/// Pending txs indexed by the sign of their balance in each unit.
+#[derive(Debug, Default)]
+pub struct IntentIndex {
+    offers: BTreeMap<Unit, BTreeSet<usize>>,
+    wants: BTreeMap<Unit, BTreeSet<usize>>,
+}
+
+impl IntentIndex {
+    pub fn new(offsets: &[Balance]) -> Self {
+        let mut index = Self::default();
+        for (i, offset) in offsets.iter().enumerate() {
+            for balance in &offset.balances {
+                if balance.is_pos() {
+                    index.offers.entry(balance.unit).or_default().insert(i);
+                } else if balance.is_neg() {
+                    index.wants.entry(balance.unit).or_default().insert(i);
+                }
+            }
+        }
+        index
+    }
+
+    /// Txs offering `unit`.
+    pub fn offers(&self, unit: Unit) -> impl Iterator<Item = usize> + '_ {
+        self.offers.get(&unit).into_iter().flatten().copied()
+    }
+
+    /// Txs wanting `unit`.
+    pub fn wants(&self, unit: Unit) -> impl Iterator<Item = usize> + '_ {
+        self.wants.get(&unit).into_iter().flatten().copied()
+    }
+
+    fn remove(&mut self, i: usize) {
+        for set in self.offers.values_mut().chain(self.wants.values_mut()) {
+            set.remove(&i);
+        }
+    }
+}
+
+/// The outcome of matching a tx.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Indices of the matched txs, in ascending order
+    pub txs: Vec<usize>,
+    /// What the matched txs leave unbalanced, zero for a full match
+    pub residual: Balance,
+}
+
+/// Total amount by which a balance is off from zero, over all units.
+pub(crate) fn imbalance(balance: &Balance) -> u128 {
+    balance
+        .balances
+        .iter()
+        .map(|b| b.pos.abs_diff(b.neg) as u128)
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Matcher {
+    /// Largest set of pending txs matched together
+    pub max_txs: usize,
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Self { max_txs: 4 }
+    }
+}
+
+impl Matcher {
+    /// Match the txs of `offsets` among themselves, oldest first.
+    ///
+    /// Each tx is matched with the first set of complementary txs found that
+    /// nets to zero. When there is none, the set with the smallest residual is
+    /// returned instead so a solver may fund it, possibly the tx alone. A tx is
+    /// part of at most one match, full matches take precedence.
+    pub fn match_all(&self, offsets: &[Balance]) -> Vec<Match> {
+        let mut index = IntentIndex::new(offsets);
+        let mut matched = vec![false; offsets.len()];
+        let mut matches = Vec::new();
+
+        for seed in 0..offsets.len() {
+            if matched[seed] {
+                continue;
+            }
+            let mut search = Search {
+                offsets,
+                index: &index,
+                matched: &matched,
+                max_txs: self.max_txs.max(1),
+                best: None,
+            };
+            search.visit(&mut vec![seed], offsets[seed].clone());
+            let found = search.best.expect("the tx alone is always a candidate");
+
+            if found.residual.is_zero() {
+                for &i in &found.txs {
+                    matched[i] = true;
+                    index.remove(i);
+                }
+            }
+            matches.push(found);
+        }
+
+        // partial matches may overlap with full matches found later or with
+        // each other, keep the oldest partial match of each free tx
+        for m in matches.iter_mut().filter(|m| !m.residual.is_zero()) {
+            if m.txs.iter().any(|i| matched[*i]) {
+                m.txs.clear();
+                continue;
+            }
+            for &i in &m.txs {
+                matched[i] = true;
+            }
+        }
+        matches.retain(|m| !m.txs.is_empty());
+        matches
+    }
+}
+
+struct Search<'a> {
+    offsets: &'a [Balance],
+    index: &'a IntentIndex,
+    matched: &'a [bool],
+    max_txs: usize,
+    best: Option<Match>,
+}
+
+impl Search<'_> {
+    /// Depth first search over the txs complementing the first unbalanced
+    /// unit of `offset`, returns true once a full match is found.
+    fn visit(&mut self, group: &mut Vec<usize>, offset: Balance) -> bool {
+        let score = imbalance(&offset);
+        let best_score = self.best.as_ref().map(|b| imbalance(&b.residual));
+        if best_score.is_none_or(|b| score < b) {
+            let mut txs = group.clone();
+            txs.sort();
+            self.best = Some(Match {
+                txs,
+                residual: offset.clone(),
+            });
+        }
+        if offset.is_zero() {
+            return true;
+        }
+        if group.len() >= self.max_txs {
+            return false;
+        }
+
+        let Some(unit) = offset.balances.iter().find(|b| !b.is_zero()) else {
+            return false;
+        };
+        let candidates: Vec<usize> = if unit.is_pos() {
+            self.index.wants(unit.unit).collect()
+        } else {
+            self.index.offers(unit.unit).collect()
+        };
+
+        for i in candidates {
+            if self.matched[i] || group.contains(&i) {
+                continue;
+            }
+            group.push(i);
+            let next = Balance::combine([&offset, &self.offsets[i]]);
+            if self.visit(group, next) {
+                return true;
+            }
+            group.pop();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cl::crust::{balance::UnitBalance, UnitWitness};
+
+    fn unit(name: &[u8]) -> Unit {
+        UnitWitness::nop(name).unit()
+    }
+
+    fn offset(balances: &[(&[u8], u64, u64)]) -> Balance {
+        Balance {
+            balances: balances
+                .iter()
+                .map(|(name, pos, neg)| UnitBalance {
+                    unit: unit(name),
+                    pos: *pos,
+                    neg: *neg,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_index_by_sign() {
+        let index = IntentIndex::new(&[
+            offset(&[(b"NMO", 10, 0), (b"MEM", 0, 90)]),
+            offset(&[(b"MEM", 90, 0), (b"NMO", 0, 10)]),
+        ]);
+        assert_eq!(index.offers(unit(b"NMO")).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(index.wants(unit(b"NMO")).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(index.wants(unit(b"ETH")).count(), 0);
+    }
+
+    #[test]
+    fn test_pairs_complementary_intents() {
+        let matches = Matcher::default().match_all(&[
+            offset(&[(b"NMO", 10, 0), (b"MEM", 0, 90)]),
+            offset(&[(b"ETH", 1, 0), (b"MEM", 0, 5)]),
+            offset(&[(b"MEM", 90, 0), (b"NMO", 0, 10)]),
+        ]);
+        assert_eq!(
+            matches,
+            vec![
+                Match {
+                    txs: vec![0, 2],
+                    residual: Balance::zero(),
+                },
+                // nothing wants ETH, a solver may still fund it
+                Match {
+                    txs: vec![1],
+                    residual: offset(&[(b"ETH", 1, 0), (b"MEM", 0, 5)]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ring_of_intents() {
+        // NMO -> MEM -> ETH -> NMO
+        let matches = Matcher::default().match_all(&[
+            offset(&[(b"NMO", 10, 0), (b"MEM", 0, 90)]),
+            offset(&[(b"MEM", 90, 0), (b"ETH", 0, 1)]),
+            offset(&[(b"ETH", 1, 0), (b"NMO", 0, 10)]),
+        ]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].txs, vec![0, 1, 2]);
+        assert!(matches[0].residual.is_zero());
+    }
+
+    #[test]
+    fn test_partial_match_leaves_residual() {
+        let matches = Matcher::default().match_all(&[
+            offset(&[(b"NMO", 10, 0), (b"MEM", 0, 90)]),
+            offset(&[(b"MEM", 80, 0), (b"NMO", 0, 10)]),
+        ]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].txs, vec![0, 1]);
+        assert_eq!(matches[0].residual.unit_balance(unit(b"MEM")).neg, 90);
+        assert_eq!(imbalance(&matches[0].residual), 10);
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use cl::{
+    crust::{
+        balance::UnitBalance,
+        tx::{Fee, LedgerUpdate, Validity},
+        Balance, Nullifier, NullifierSecret, OutputWitness, Tx, TxRoot, Unit,
+    },
+    test_utils,
+};
+use ledger::{tx::ProvedTx, verifier::Verifier};
+use mempool::{
+    matching::{Matcher, Solver},
+    Error, Mempool,
+};
+use risc0_zkvm::{sha::Digest, FakeReceipt, InnerReceipt, Receipt, ReceiptClaim};
+
+fn nmo() -> Unit {
+    test_utils::nmo().unit()
+}
+
+fn mem() -> Unit {
+    test_utils::mem().unit()
+}
+
+fn verifier() -> Verifier {
+    Verifier::default().with_dev_mode(true)
+}
+
+/// A tx spending `nfs` whose balance is `pos` minus `neg` per unit.
+fn tx(seed: u8, nfs: &[u8], balances: &[(Unit, u64, u64)]) -> Tx {
+    Tx {
+        root: TxRoot([seed; 32]),
+        balance: Balance {
+            balances: balances
+                .iter()
+                .map(|(unit, pos, neg)| UnitBalance {
+                    unit: *unit,
+                    pos: *pos,
+                    neg: *neg,
+                })
+                .collect(),
+        },
+        updates: BTreeMap::from_iter([(
+            [0; 32],
+            LedgerUpdate {
+                frontier_nodes: vec![],
+                inputs: nfs.iter().map(|nf| Nullifier([*nf; 32])).collect(),
+                outputs: vec![],
+            },
+        )]),
+        data: vec![],
+        fee: None,
+        validity: Validity::default(),
+        domain: [0; 32],
+        migrations: vec![],
+    }
+}
+
+fn prove(tx: &Tx) -> ProvedTx {
+    let journal: Vec<u8> = risc0_zkvm::serde::to_vec(tx)
+        .unwrap()
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+        .collect();
+    let claim = ReceiptClaim::ok(Digest::from(verifier().tx), journal.clone());
+    ProvedTx {
+        risc0_receipt: Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal),
+    }
+}
+
+fn roots(bundle: &[mempool::PendingTx]) -> Vec<TxRoot> {
+    bundle.iter().map(|p| p.tx.root).collect()
+}
+
+#[test]
+fn rejects_invalid_proofs() {
+    let mut pool = Mempool::new(verifier());
+    let mut proof = prove(&tx(1, &[1], &[]));
+    proof.risc0_receipt.journal.bytes.pop();
+
+    assert!(matches!(pool.submit(proof), Err(Error::InvalidProof(_))));
+    assert!(pool.is_empty());
+}
+
+#[test]
+fn rejects_conflicting_nullifiers() {
+    let mut pool = Mempool::new(verifier());
+    pool.submit(prove(&tx(1, &[1, 2], &[]))).unwrap();
+
+    assert!(matches!(
+        pool.submit(prove(&tx(1, &[1, 2], &[]))),
+        Err(Error::DuplicateTx(_))
+    ));
+    assert!(matches!(
+        pool.submit(prove(&tx(2, &[2, 3], &[]))),
+        Err(Error::ConflictingNullifier { nullifier, tx })
+            if nullifier == Nullifier([2; 32]) && tx == TxRoot([1; 32])
+    ));
+    assert!(matches!(
+        pool.submit(prove(&tx(3, &[4, 4], &[]))),
+        Err(Error::ConflictingNullifier { .. })
+    ));
+
+    // once bundled, the nullifiers stay spent
+    assert_eq!(pool.build_bundles().len(), 1);
+    assert!(matches!(
+        pool.submit(prove(&tx(4, &[1], &[]))),
+        Err(Error::SpentNullifier(_))
+    ));
+}
+
+#[test]
+fn mark_spent_evicts_pending_txs() {
+    let mut pool = Mempool::new(verifier());
+    pool.submit(prove(&tx(1, &[1], &[]))).unwrap();
+    pool.submit(prove(&tx(2, &[2], &[]))).unwrap();
+
+    pool.mark_spent([Nullifier([1; 32])]);
+    assert!(!pool.contains(TxRoot([1; 32])));
+    assert!(pool.contains(TxRoot([2; 32])));
+}
+
+#[test]
+fn rejects_other_domains_and_evicts_expired_txs() {
+    let mut pool = Mempool::new(verifier()).with_domain([7; 32]);
+    assert!(matches!(
+        pool.submit(prove(&tx(1, &[1], &[]))),
+        Err(Error::WrongDomain { domain, .. }) if domain == [0; 32]
+    ));
+
+    let bound = |seed, valid_until| Tx {
+        validity: Validity {
+            valid_from: None,
+            valid_until,
+        },
+        domain: [7; 32],
+        ..tx(seed, &[seed], &[])
+    };
+    pool.submit(prove(&bound(2, Some(3)))).unwrap();
+    pool.submit(prove(&bound(3, Some(4)))).unwrap();
+    pool.submit(prove(&bound(4, None))).unwrap();
+
+    assert!(pool.evict_expired(3).is_empty());
+    assert_eq!(roots(&pool.evict_expired(4)), vec![TxRoot([2; 32])]);
+    assert_eq!(pool.len(), 2);
+    assert!(pool.contains(TxRoot([4; 32])));
+
+    // txs expired at the ledger height are no longer accepted
+    assert!(matches!(
+        pool.submit(prove(&bound(5, Some(3)))),
+        Err(Error::Expired {
+            valid_until: 3,
+            height: 4,
+            ..
+        })
+    ));
+    pool.submit(prove(&bound(6, Some(4)))).unwrap();
+}
+
+#[test]
+fn groups_offsetting_txs_into_balanced_bundles() {
+    let mut pool = Mempool::new(verifier());
+    // a swap: gives 10 NMO and wants 90 MEM
+    pool.submit(prove(&tx(1, &[1], &[(nmo(), 10, 0), (mem(), 0, 90)])))
+        .unwrap();
+    // already balanced on its own
+    pool.submit(prove(&tx(2, &[2], &[]))).unwrap();
+    // cannot be balanced by anything in the pool
+    pool.submit(prove(&tx(3, &[3], &[(mem(), 5, 0)]))).unwrap();
+    // solves part of the swap
+    pool.submit(prove(&tx(4, &[4], &[(nmo(), 0, 4), (mem(), 40, 0)])))
+        .unwrap();
+    // solves the rest of the swap
+    pool.submit(prove(&tx(5, &[5], &[(nmo(), 0, 6), (mem(), 50, 0)])))
+        .unwrap();
+
+    let bundles = pool.build_bundles();
+    assert_eq!(bundles.len(), 2);
+    assert_eq!(
+        roots(&bundles[0]),
+        vec![TxRoot([1; 32]), TxRoot([4; 32]), TxRoot([5; 32])]
+    );
+    assert_eq!(roots(&bundles[1]), vec![TxRoot([2; 32])]);
+
+    assert_eq!(pool.len(), 1);
+    assert!(pool.contains(TxRoot([3; 32])));
+}
+
+#[test]
+fn fees_are_left_for_the_builder() {
+    let mut rng = rand::thread_rng();
+    let mut pool = Mempool::new(verifier());
+    let mut paying = tx(1, &[1], &[]);
+    paying.fee = Some(Fee {
+        unit: nmo(),
+        amount: 2,
+    });
+    pool.submit(prove(&paying)).unwrap();
+    assert!(pool.build_bundles().is_empty());
+
+    let builder = NullifierSecret::random(&mut rng).commit();
+    let fee_output = OutputWitness::new(2, nmo(), builder, [0; 32], &mut rng);
+    let mut claim = tx(2, &[2], &[(nmo(), 0, 2)]);
+    assert!(matches!(
+        pool.submit_fee_claim(prove(&claim), vec![fee_output]),
+        Err(Error::NotAFeeOutput { tx, .. }) if tx == TxRoot([2; 32])
+    ));
+
+    // the builder's claim balances the fee
+    let update = claim.updates.get_mut(&[0; 32]).unwrap();
+    update.outputs = vec![(fee_output.note_commitment(), vec![])];
+    pool.submit_fee_claim(prove(&claim), vec![fee_output])
+        .unwrap();
+    let bundles = pool.build_bundles();
+    assert_eq!(roots(&bundles[0]), vec![TxRoot([1; 32]), TxRoot([2; 32])]);
+    assert_eq!(bundles[0][1].fee_outputs, vec![fee_output]);
+}
+
+#[test]
+fn bundles_only_txs_with_intersecting_windows() {
+    let mut pool = Mempool::new(verifier());
+    let bound = |seed, valid_from, valid_until, balances: &[(Unit, u64, u64)]| Tx {
+        validity: Validity {
+            valid_from,
+            valid_until,
+        },
+        ..tx(seed, &[seed], balances)
+    };
+    pool.submit(prove(&bound(1, None, Some(3), &[(nmo(), 10, 0)])))
+        .unwrap();
+    // balances the first tx but is only valid after it expired
+    pool.submit(prove(&bound(2, Some(5), None, &[(nmo(), 0, 10)])))
+        .unwrap();
+    assert!(pool.build_bundles().is_empty());
+
+    pool.submit(prove(&bound(3, Some(2), Some(8), &[(nmo(), 0, 10)])))
+        .unwrap();
+    let bundles = pool.build_bundles();
+    assert_eq!(roots(&bundles[0]), vec![TxRoot([1; 32]), TxRoot([3; 32])]);
+    assert!(pool.contains(TxRoot([2; 32])));
+}
+
+#[test]
+fn fee_claims_are_only_bundled_with_the_fees_they_claim() {
+    let mut rng = rand::thread_rng();
+    let mut pool = Mempool::new(verifier());
+    let builder = NullifierSecret::random(&mut rng).commit();
+    let fee_output = OutputWitness::new(2, nmo(), builder, [0; 32], &mut rng);
+    let mut claim = tx(1, &[1], &[(nmo(), 0, 2)]);
+    let update = claim.updates.get_mut(&[0; 32]).unwrap();
+    update.outputs = vec![(fee_output.note_commitment(), vec![])];
+    pool.submit_fee_claim(prove(&claim), vec![fee_output])
+        .unwrap();
+
+    // balances the claim, but pays no fee for it to claim
+    pool.submit(prove(&tx(2, &[2], &[(nmo(), 2, 0)]))).unwrap();
+    assert!(pool.build_bundles().is_empty());
+
+    let mut paying = tx(3, &[3], &[]);
+    paying.fee = Some(Fee {
+        unit: nmo(),
+        amount: 2,
+    });
+    pool.submit(prove(&paying)).unwrap();
+    let bundles = pool.build_bundles();
+    assert_eq!(roots(&bundles[0]), vec![TxRoot([1; 32]), TxRoot([3; 32])]);
+    assert!(pool.contains(TxRoot([2; 32])));
+}
+
+/// Funds any residual with the tx it was given, once.
+struct FixedSolver(Option<Tx>);
+
+impl Solver for FixedSolver {
+    fn fund(&mut self, _residual: &Balance) -> Option<ProvedTx> {
+        self.0.take().map(|tx| prove(&tx))
+    }
+}
+
+#[test]
+fn matches_complementary_intents() {
+    let mut pool = Mempool::new(verifier());
+    pool.submit(prove(&tx(1, &[1], &[(nmo(), 10, 0), (mem(), 0, 90)])))
+        .unwrap();
+    pool.submit(prove(&tx(2, &[2], &[(mem(), 5, 0)]))).unwrap();
+    pool.submit(prove(&tx(3, &[3], &[(mem(), 90, 0), (nmo(), 0, 10)])))
+        .unwrap();
+
+    let bundles = pool.match_intents(&Matcher::default(), None);
+    assert_eq!(bundles.len(), 1);
+    assert_eq!(roots(&bundles[0]), vec![TxRoot([1; 32]), TxRoot([3; 32])]);
+    assert!(pool.contains(TxRoot([2; 32])));
+}
+
+#[test]
+fn solver_funds_the_residual() {
+    let mut pool = Mempool::new(verifier());
+    pool.submit(prove(&tx(1, &[1], &[(nmo(), 10, 0), (mem(), 0, 90)])))
+        .unwrap();
+    pool.submit(prove(&tx(2, &[2], &[(mem(), 80, 0), (nmo(), 0, 10)])))
+        .unwrap();
+
+    // a solver tx that does not cancel the residual is ignored
+    let mut solver = FixedSolver(Some(tx(3, &[3], &[(mem(), 20, 0)])));
+    assert!(pool
+        .match_intents(&Matcher::default(), Some(&mut solver))
+        .is_empty());
+    assert_eq!(pool.len(), 2);
+
+    let mut solver = FixedSolver(Some(tx(3, &[3], &[(mem(), 10, 0)])));
+    let bundles = pool.match_intents(&Matcher::default(), Some(&mut solver));
+    assert_eq!(
+        roots(&bundles[0]),
+        vec![TxRoot([1; 32]), TxRoot([2; 32]), TxRoot([3; 32])]
+    );
+    assert!(pool.is_empty());
+}
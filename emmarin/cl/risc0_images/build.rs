@@ -0,0 +1,22 @@
+// Fail the build when a guest ELF was built against a stale ID of another guest.
+// Changes to the guest sources are not detected here, `cargo xtask build-images
+// --check` rebuilds the guests and compares their IDs, it runs in the `images`
+// job of .github/workflows/emmarin.yml.
+#[allow(dead_code)]
+mod guests {
+    include!("src/guests.rs");
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    // guests only embed the IDs, and are rebuilt one by one while the ELFs of
+    // the guests after them are still stale
+    if std::env::var_os("CARGO_FEATURE_ELF").is_none() {
+        return;
+    }
+
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    if let Err(e) = guests::check_embedded_ids(&dir) {
+        panic!("{e}");
+    }
+}
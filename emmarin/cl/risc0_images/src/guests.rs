@@ -0,0 +1,71 @@
+// Shared with the build script through `include!` and with xtask through
+// `#[path]`, keep it free of imports from this crate.
+
+/// A guest image, stored as the `<name>_ID` and `<name>_ELF` files of this crate.
+#[derive(Debug, Clone, Copy)]
+pub struct Guest {
+    pub name: &'static str,
+    /// Package building the guest, relative to the emmarin/cl workspace
+    pub package: &'static str,
+    /// Images whose ID the guest embeds to verify their proofs
+    pub embeds: &'static [&'static str],
+}
+
+/// All guests in build order, a guest comes after every image it embeds.
+pub static GUESTS: &[Guest] = &[
+    Guest {
+        name: "STF_NOP",
+        package: "risc0_proofs",
+        embeds: &[],
+    },
+    Guest {
+        name: "TX",
+        package: "risc0_proofs",
+        embeds: &[],
+    },
+    Guest {
+        name: "BUNDLE",
+        package: "bundle_risc0_proof",
+        embeds: &["TX"],
+    },
+    Guest {
+        name: "LEDGER",
+        package: "ledger_risc0_proof",
+        embeds: &["BUNDLE"],
+    },
+    Guest {
+        name: "BATCH",
+        package: "batch_risc0_proof",
+        embeds: &["LEDGER"],
+    },
+];
+
+/// Check that every guest ELF in `dir` embeds the current ID of the images it
+/// verifies.
+///
+/// Guests embed the hex ID through `include_str!`, so the ID shows up verbatim
+/// in the ELF. Guests that are not built yet, with a missing or empty ELF, are
+/// skipped.
+pub fn check_embedded_ids(dir: &std::path::Path) -> Result<(), String> {
+    for guest in GUESTS {
+        let elf = match std::fs::read(dir.join(format!("{}_ELF", guest.name))) {
+            Ok(elf) if !elf.is_empty() => elf,
+            _ => continue,
+        };
+        for embedded in guest.embeds {
+            let path = dir.join(format!("{embedded}_ID"));
+            let id = std::fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            let id = id.trim().as_bytes();
+            if !elf.windows(id.len()).any(|w| w == id) {
+                return Err(format!(
+                    "{}_ELF does not embed the current {embedded}_ID {}, rebuild the images \
+                     with `cargo xtask build-images`",
+                    guest.name,
+                    String::from_utf8_lossy(id)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,15 +1,69 @@
-pub static STF_NOP_ID: &str = include_str!("STF_NOP_ID");
+mod guests;
+
+pub use guests::{check_embedded_ids, Guest, GUESTS};
+
+pub const STF_NOP_ID: &str = include_str!("STF_NOP_ID");
+pub const STF_NOP_IMAGE_ID: [u8; 32] = parse_id(STF_NOP_ID);
 #[cfg(feature = "elf")]
 pub static STF_NOP_ELF: &[u8] = include_bytes!("STF_NOP_ELF");
 
-pub static TX_ID: &str = include_str!("TX_ID");
+pub const TX_ID: &str = include_str!("TX_ID");
+pub const TX_IMAGE_ID: [u8; 32] = parse_id(TX_ID);
 #[cfg(feature = "elf")]
 pub static TX_ELF: &[u8] = include_bytes!("TX_ELF");
 
-pub static BUNDLE_ID: &str = include_str!("BUNDLE_ID");
+pub const BUNDLE_ID: &str = include_str!("BUNDLE_ID");
+pub const BUNDLE_IMAGE_ID: [u8; 32] = parse_id(BUNDLE_ID);
 #[cfg(feature = "elf")]
 pub static BUNDLE_ELF: &[u8] = include_bytes!("BUNDLE_ELF");
 
-pub static LEDGER_ID: &str = include_str!("LEDGER_ID");
+pub const LEDGER_ID: &str = include_str!("LEDGER_ID");
+pub const LEDGER_IMAGE_ID: [u8; 32] = parse_id(LEDGER_ID);
 #[cfg(feature = "elf")]
 pub static LEDGER_ELF: &[u8] = include_bytes!("LEDGER_ELF");
+
+pub const BATCH_ID: &str = include_str!("BATCH_ID");
+pub const BATCH_IMAGE_ID: [u8; 32] = parse_id(BATCH_ID);
+#[cfg(feature = "elf")]
+pub static BATCH_ELF: &[u8] = include_bytes!("BATCH_ELF");
+
+/// Parse a hex image ID at compile time, so a malformed ID file fails the
+/// build rather than every use of the ID.
+///
+/// An empty ID parses to zeros, `cargo xtask build-images` creates the image
+/// files of guests not built yet empty.
+const fn parse_id(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            b'A'..=b'F' => c - b'A' + 10,
+            _ => panic!("image ID is not hex"),
+        }
+    }
+
+    let hex = hex.as_bytes();
+    let mut id = [0u8; 32];
+    if hex.is_empty() {
+        return id;
+    }
+    assert!(hex.len() == 64, "image ID is not 32 bytes");
+    let mut i = 0;
+    while i < 32 {
+        id[i] = nibble(hex[2 * i]) << 4 | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    id
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_id() {
+        assert_eq!(parse_id(""), [0; 32]);
+        let hex = "00ff".repeat(16);
+        assert_eq!(parse_id(&hex), [[0x00, 0xff]; 16].concat()[..]);
+    }
+}
@@ -0,0 +1,187 @@
+//! Maintenance tasks for the emmarin guests, run from `emmarin/cl`.
+//!
+//! ```text
+//! cargo xtask build-images [--check]
+//! cargo xtask check-images
+//! ```
+//!
+//! `build-images` builds every guest with `cargo risczero build`, which runs
+//! the build in the risc0 docker image so the ELFs are reproducible, and
+//! writes the `*_ID` and `*_ELF` files of `risc0_images`. Guests are built in
+//! the order of [`GUESTS`] so a guest always embeds the fresh ID of the guests
+//! it verifies. Missing image files are created empty first, the guests
+//! depend on `risc0_images`, which embeds all of them. With `--check` nothing
+//! is written and the task fails if a fresh build does not match the
+//! committed images, e.g. after a change to the guest sources.
+//!
+//! `check-images` only checks the committed images: each ID must be the image
+//! ID of its ELF, and each ELF must embed the current ID of the guests it
+//! verifies.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, Stdio},
+};
+
+// not a dependency on `risc0_images`, it does not compile while images are missing
+#[path = "../../risc0_images/src/guests.rs"]
+mod guests;
+
+use guests::{check_embedded_ids, GUESTS};
+
+const USAGE: &str = "usage:
+  cargo xtask build-images [--check]
+  cargo xtask check-images";
+
+fn workspace() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+fn images_dir() -> PathBuf {
+    workspace().join("risc0_images").join("src")
+}
+
+/// Build the guests of `package`, returning the image ID and ELF path of each
+/// guest by name.
+fn build_package(package: &str) -> Result<BTreeMap<String, (String, PathBuf)>, String> {
+    let manifest = workspace().join(package).join("Cargo.toml");
+    let mut child = Command::new("cargo")
+        .args(["risczero", "build", "--manifest-path"])
+        .arg(&manifest)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run cargo risczero build: {e}"))?;
+
+    // `cargo risczero build` reports each guest as `ImageID: <hex> - <path>.bin`
+    let mut images = BTreeMap::new();
+    for line in BufReader::new(child.stdout.take().unwrap()).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        println!("{line}");
+        let Some((id, elf)) = line
+            .split_once("ImageID: ")
+            .and_then(|(_, rest)| rest.split_once(" - "))
+        else {
+            continue;
+        };
+        let elf = PathBuf::from(elf.trim());
+        let name = elf
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or(format!("unexpected guest path {}", elf.display()))?
+            .to_uppercase();
+        images.insert(name, (id.trim().to_string(), elf));
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("building {package} failed with {status}"));
+    }
+    Ok(images)
+}
+
+/// Create the image files missing from `dir` empty, so that `risc0_images`
+/// compiles before the guests are first built.
+fn bootstrap_images(dir: &Path) -> Result<(), String> {
+    for guest in GUESTS {
+        for file in [format!("{}_ID", guest.name), format!("{}_ELF", guest.name)] {
+            let path = dir.join(file);
+            if !path.exists() {
+                println!("Creating empty {}", path.display());
+                std::fs::write(&path, "").map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn build_images(check: bool) -> Result<(), String> {
+    let dir = images_dir();
+    let mut built = BTreeMap::new();
+    if !check {
+        bootstrap_images(&dir)?;
+    }
+
+    for guest in GUESTS {
+        if !built.contains_key(guest.name) {
+            println!("Building {}", guest.package);
+            built.extend(build_package(guest.package)?);
+        }
+        let (id, elf) = built.get(guest.name).ok_or(format!(
+            "{} did not build the {} guest",
+            guest.package, guest.name
+        ))?;
+
+        let id_path = dir.join(format!("{}_ID", guest.name));
+        if check {
+            let committed = std::fs::read_to_string(&id_path).unwrap_or_default();
+            if committed.trim() != id {
+                return Err(format!(
+                    "{}_ID is stale: committed {}, built {id}",
+                    guest.name,
+                    committed.trim()
+                ));
+            }
+        } else {
+            // later guests embed this ID, it must be in place before they build
+            std::fs::write(&id_path, id).map_err(|e| e.to_string())?;
+            std::fs::copy(elf, dir.join(format!("{}_ELF", guest.name)))
+                .map_err(|e| e.to_string())?;
+        }
+        println!("{}_ID: {id}", guest.name);
+    }
+
+    check_images()
+}
+
+fn check_images() -> Result<(), String> {
+    let dir = images_dir();
+    for guest in GUESTS {
+        let id_path = dir.join(format!("{}_ID", guest.name));
+        let elf_path = dir.join(format!("{}_ELF", guest.name));
+        let id = std::fs::read_to_string(&id_path)
+            .map_err(|e| format!("failed to read {}: {e}", id_path.display()))?;
+        let elf = std::fs::read(&elf_path)
+            .map_err(|e| format!("failed to read {}: {e}", elf_path.display()))?;
+
+        let image_id = risc0_zkvm::compute_image_id(&elf)
+            .map_err(|e| format!("invalid {}_ELF: {e}", guest.name))?;
+        let image_id = hex::encode(image_id.as_bytes());
+        if id.trim() != image_id {
+            return Err(format!(
+                "{}_ID {} is not the image ID {image_id} of {}_ELF",
+                guest.name,
+                id.trim(),
+                guest.name
+            ));
+        }
+    }
+    check_embedded_ids(&dir)?;
+
+    println!("images are consistent");
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["build-images"] => build_images(false),
+        ["build-images", "--check"] => build_images(true),
+        ["check-images"] => check_images(),
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
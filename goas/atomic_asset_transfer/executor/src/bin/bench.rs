@@ -0,0 +1,109 @@
+//! Records the cycle counts of the zone stf guest in the report format of the
+//! emmarin `bench` crate, so runs can be compared with `bench compare`.
+//!
+//! Only the guest is executed, no proof is generated.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use bench::report::{Record, Report};
+use cl::{BalanceWitness, NoteWitness, OutputWitness, PartialTxWitness};
+use clap::Parser;
+use common::{BoundTx, SignedBoundTx, Tx, Withdraw};
+use executor::ZoneNotes;
+use rand::{rngs::StdRng, SeedableRng};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Number of withdraws applied by the zone stf, one run per value
+    #[arg(long, value_delimiter = ',', default_value = "1,4")]
+    txs: Vec<u64>,
+
+    /// Where to write the report
+    #[arg(short, long, default_value = "goas.json")]
+    out: PathBuf,
+}
+
+/// Execute the zone stf over `txs` withdraws of a single account.
+fn zone_stf(txs: u64) -> Record {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let mut alice = common::new_account(&mut rng);
+    let alice_vk = alice.verifying_key().to_bytes();
+
+    let zone_start =
+        ZoneNotes::new_with_balances("ZONE", BTreeMap::from_iter([(alice_vk, txs)]), &mut rng);
+
+    let mut zone_end = zone_start.clone();
+    let mut signed = Vec::new();
+    let mut binds = Vec::new();
+    for _ in 0..txs {
+        let bind =
+            OutputWitness::public(NoteWitness::basic(1, *common::ZONE_CL_FUNDS_UNIT, &mut rng));
+        let signed_withdraw = SignedBoundTx::sign(
+            BoundTx {
+                tx: Tx::Withdraw(Withdraw {
+                    from: alice_vk,
+                    amount: 1,
+                }),
+                bind: bind.commit_note(),
+            },
+            &mut alice,
+        );
+        zone_end = zone_end.run(signed_withdraw.bound_tx.tx).0;
+        signed.push(signed_withdraw);
+        binds.push(cl::InputWitness::public(bind));
+    }
+
+    let mut inputs = binds;
+    inputs.push(zone_start.state_input_witness());
+    inputs.push(zone_start.fund_input_witness());
+    let ptx = PartialTxWitness {
+        inputs,
+        outputs: vec![zone_end.state_note, zone_end.fund_note],
+        balance_blinding: BalanceWitness::random_blinding(&mut rng),
+    };
+
+    let n = txs as usize;
+    let inputs = signed
+        .into_iter()
+        .enumerate()
+        .map(|(i, tx)| (tx, ptx.input_witness(i)))
+        .collect();
+
+    let session = executor::execute_zone_stf(
+        zone_start.state.clone(),
+        inputs,
+        ptx.input_witness(n),
+        ptx.output_witness(0),
+        ptx.output_witness(1),
+    );
+
+    let user_cycles = session.segments.iter().map(|s| s.cycles as u64).sum();
+    let total_cycles = session.segments.iter().map(|s| 1u64 << s.po2).sum();
+    Record::new("goas_zone_stf", &[("txs", txs)]).with_cycles(
+        session.segments.len() as u64,
+        user_cycles,
+        total_cycles,
+    )
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    let mut report = Report::default();
+    for txs in args.txs {
+        let record = zone_stf(txs);
+        println!(
+            "{}: {} user cycles, {} total cycles, {} segments",
+            record.key(),
+            record.user_cycles,
+            record.total_cycles,
+            record.segments
+        );
+        report.push(record);
+    }
+    report.write(&args.out)?;
+
+    Ok(())
+}
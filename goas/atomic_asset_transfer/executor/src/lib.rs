@@ -148,6 +148,33 @@ pub fn prove_zone_stf(
     ledger::ConstraintProof::from_risc0(goas_risc0_proofs::ZONE_STATE_ID, receipt)
 }
 
+/// Run the zone stf guest without proving it, e.g. to count its cycles.
+pub fn execute_zone_stf(
+    state: StateWitness,
+    inputs: Vec<(SignedBoundTx, cl::PartialTxInputWitness)>,
+    zone_in: cl::PartialTxInputWitness,
+    zone_out: cl::PartialTxOutputWitness,
+    funds_out: cl::PartialTxOutputWitness,
+) -> risc0_zkvm::SessionInfo {
+    let private_inputs = ZoneStatePrivate {
+        state,
+        inputs,
+        zone_in,
+        zone_out,
+        funds_out,
+    };
+
+    let env = risc0_zkvm::ExecutorEnv::builder()
+        .write(&private_inputs)
+        .unwrap()
+        .build()
+        .unwrap();
+
+    risc0_zkvm::default_executor()
+        .execute(env, goas_risc0_proofs::ZONE_STATE_ELF)
+        .unwrap()
+}
+
 pub fn prove_zone_fund_constraint(
     in_zone_funds: cl::PartialTxInputWitness,
     zone_note: cl::PartialTxOutputWitness,